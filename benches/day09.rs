@@ -0,0 +1,53 @@
+//! Times the BOOST program of day 9 in sensor boost mode (the second
+//! part), to track the performance of the interpreter precisely.
+//!
+//! ```bash
+//! $ cargo bench --bench day09
+//! ```
+
+use lib::prelude::{Cell, Program};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The input of the second part: runs the program in sensor boost mode.
+const SENSOR_BOOST_MODE: Cell = 2;
+
+/// The number of timed runs, after a warm-up one.
+const RUNS: usize = 20;
+
+fn run(source_code: &str) -> Duration {
+    let mut program: Program = source_code.parse().expect("Invalid program");
+    program.push_input(SENSOR_BOOST_MODE);
+
+    let start = Instant::now();
+    let output = program.execute().expect("The program failed");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output.len(), 1);
+
+    elapsed
+}
+
+fn main() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("input/day-9.txt");
+    let source_code = match fs::read_to_string(&path) {
+        Ok(source_code) => source_code,
+        Err(_) => {
+            eprintln!("Skipped: {} is missing", path.display());
+            return;
+        }
+    };
+
+    run(&source_code);
+    let mut durations: Vec<Duration> = (0..RUNS).map(|_| run(&source_code)).collect();
+    durations.sort();
+
+    println!(
+        "day 9, sensor mode: min {:?}, median {:?}, max {:?} ({} runs)",
+        durations[0],
+        durations[RUNS / 2],
+        durations[RUNS - 1],
+        RUNS
+    );
+}
@@ -0,0 +1,93 @@
+//! Generates the days modules declarations and registry from the files
+//! in `src/days`, so adding a `dayNN.rs` file is enough to wire a day.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    let days_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("src/days");
+    println!("cargo:rerun-if-changed={}", days_dir.display());
+
+    let mut days: Vec<(u8, String)> = fs::read_dir(&days_dir)
+        .expect("Unable to read the days directory")
+        .map(|entry| entry.expect("Unable to read the days directory").path())
+        .filter(|path| path.file_name().unwrap() != "mod.rs")
+        .filter_map(|path| {
+            let module = path.file_stem().unwrap().to_string_lossy().to_string();
+            let number = module
+                .strip_prefix("day")
+                .filter(|_| path.extension() == Some("rs".as_ref()))
+                .and_then(|number| number.parse::<u8>().ok());
+
+            // Other files, e.g. editor backups or helpers, are not days.
+            if number.is_none() {
+                println!(
+                    "cargo:warning=Ignoring {} in src/days: day modules must be named dayNN.rs",
+                    path.display()
+                );
+            }
+
+            number.map(|number| (number, module))
+        })
+        .collect();
+
+    days.sort();
+
+    let modules: String = days
+        .iter()
+        .map(|(_, module)| {
+            format!(
+                "#[path = {:?}]\npub mod {};\n",
+                days_dir
+                    .join(format!("{}.rs", module))
+                    .display()
+                    .to_string(),
+                module
+            )
+        })
+        .collect();
+
+    let registry: String = days
+        .iter()
+        .map(|(number, module)| {
+            // Days without capabilities don't have to declare them.
+            let source = fs::read_to_string(days_dir.join(format!("{}.rs", module)))
+                .expect("Unable to read a day module");
+            let capabilities = if source.contains("pub const CAPABILITIES") {
+                format!("{}::CAPABILITIES", module)
+            } else {
+                "&[]".to_string()
+            };
+
+            format!(
+                "    Day {{ number: {number}, title: {module}::TITLE, labels: {module}::LABELS, solve: {module}::solve, capabilities: {capabilities} }},\n",
+                number = number,
+                module = module,
+                capabilities = capabilities
+            )
+        })
+        .collect();
+
+    fs::write(
+        Path::new(&env::var("OUT_DIR").unwrap()).join("days.rs"),
+        format!(
+            "{}\n/// All the days implemented, in order.\npub const DAYS: [Day; {}] = [\n{}];\n",
+            modules,
+            days.len(),
+            registry
+        ),
+    )
+    .expect("Unable to write the days registry");
+
+    // The compiler version, reported by the doctor command.
+    let rustc_version = Command::new(env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string()))
+        .arg("--version")
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=RUSTC_VERSION={}", rustc_version);
+}
@@ -0,0 +1,38 @@
+//! Compares the execution time of a tight loop with and without the cache
+//! of decoded instructions.
+//!
+//! ```bash
+//! $ cargo run --release --example decode_cache
+//! ```
+
+use lib::prelude::{catalog::CORPUS_LOOP, Cell, Program};
+use std::time::{Duration, Instant};
+
+/// The number of iterations of the loop.
+const ITERATIONS: Cell = 5_000_000;
+
+fn run(decode_cache: bool) -> Duration {
+    let mut program: Program = CORPUS_LOOP.source_code.parse().unwrap();
+    program.set_decode_cache(decode_cache);
+    program.patch(4, ITERATIONS).unwrap();
+
+    let start = Instant::now();
+    let output = program.execute().expect("The loop failed");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output, vec![ITERATIONS * (ITERATIONS + 1) / 2]);
+
+    elapsed
+}
+
+fn main() {
+    let uncached = run(false);
+    let cached = run(true);
+
+    println!("Without the decode cache: {:?}", uncached);
+    println!("With the decode cache:    {:?}", cached);
+    println!(
+        "Speedup: {:.2}×",
+        uncached.as_secs_f64() / cached.as_secs_f64()
+    );
+}
@@ -0,0 +1,49 @@
+//! Plays the guessing game of the Intcode corpus: think of a number from
+//! 1 to 1023, and the program finds it by binary search.
+//!
+//! ```bash
+//! $ cargo run --example guessing_game
+//! ```
+
+use lib::prelude::{catalog::CORPUS_GUESSING_GAME, Event, Program};
+use std::io::{self, BufRead, Write};
+
+fn main() {
+    let mut program: Program = CORPUS_GUESSING_GAME.source_code.parse().unwrap();
+    let mut lines = io::stdin().lock().lines();
+    let mut found = false;
+
+    println!("Think of a number from 1 to 1023.");
+
+    loop {
+        match program.run_until_event().expect("The game failed") {
+            Event::Output(guess) => print!("Is it {}? [lower/higher/yes] ", guess),
+            Event::NeedsInput => {
+                io::stdout().flush().unwrap();
+
+                let answer = match lines.next() {
+                    Some(Ok(line)) => match line.trim().chars().next() {
+                        Some('l') => -1,
+                        Some('h') => 1,
+                        Some('y') => 0,
+                        _ => {
+                            print!("Please answer lower, higher or yes: ");
+                            continue;
+                        }
+                    },
+                    _ => return,
+                };
+
+                found = answer == 0;
+                program.push_input(answer);
+            }
+            Event::Halted | Event::Stopped(_) => break,
+        }
+    }
+
+    if found {
+        println!("Found it!");
+    } else {
+        println!("That's not possible, you must have cheated.");
+    }
+}
@@ -0,0 +1,23 @@
+//! Profiles the BOOST program of day 9, in sensor boost mode (the second
+//! part), to find where it spends its time.
+//!
+//! ```bash
+//! $ cargo run --release --example profile
+//! ```
+
+use lib::prelude::{Cell, Program};
+use std::fs;
+
+/// The input of the second part: runs the program in sensor boost mode.
+const SENSOR_BOOST_MODE: Cell = 2;
+
+fn main() {
+    let source_code = fs::read_to_string("input/day-9.txt").expect("Unable to open input file");
+    let mut program: Program = source_code.parse().expect("Invalid program");
+    program.push_input(SENSOR_BOOST_MODE);
+    program.enable_profiling();
+
+    let output = program.execute().expect("The program failed");
+    println!("Output: {:?}\n", output);
+    print!("{:.20}", program.profile());
+}
@@ -0,0 +1,76 @@
+//! Compares the rendering of a large picture through a formatter, one
+//! character at a time, and with `render::compose`.
+//!
+//! ```bash
+//! $ cargo run --release --example render
+//! ```
+
+use lib::prelude::compose;
+use std::fmt::{Display, Error, Formatter, Write};
+use std::time::{Duration, Instant};
+
+const WIDTH: usize = 200;
+const HEIGHT: usize = 100;
+
+/// The number of pictures rendered by each method.
+const ROUNDS: u32 = 1000;
+
+/// A picture, with its pixels on or off.
+struct Picture {
+    pixels: Vec<bool>,
+}
+
+impl Picture {
+    fn pixel_at(&self, x: usize, y: usize) -> char {
+        match self.pixels[y * WIDTH + x] {
+            true => '×',
+            false => ' ',
+        }
+    }
+
+    fn compose(&self) -> String {
+        compose(WIDTH, HEIGHT, |x, y| self.pixel_at(x, y))
+    }
+}
+
+/// Renders the picture as it used to be, writing the characters one by
+/// one to the formatter.
+impl Display for Picture {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
+        for y in 0..HEIGHT {
+            for x in 0..WIDTH {
+                f.write_char(self.pixel_at(x, y))
+                    .expect("Cannot write pixel");
+            }
+            f.write_str("\n").expect("Cannot write line");
+        }
+
+        Ok(())
+    }
+}
+
+fn time(render: impl Fn() -> String) -> Duration {
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        render();
+    }
+    start.elapsed() / ROUNDS
+}
+
+fn main() {
+    let picture = Picture {
+        pixels: (0..WIDTH * HEIGHT).map(|pixel| pixel % 7 < 3).collect(),
+    };
+    assert_eq!(picture.to_string(), picture.compose());
+
+    let by_char = time(|| picture.to_string());
+    let composed = time(|| picture.compose());
+
+    println!("{}×{} pictures, {} rounds", WIDTH, HEIGHT, ROUNDS);
+    println!("Character by character: {:?}", by_char);
+    println!("Composed:               {:?}", composed);
+    println!(
+        "Speedup: {:.2}×",
+        by_char.as_secs_f64() / composed.as_secs_f64()
+    );
+}
@@ -1,27 +1,48 @@
-use crate::{first_answer, input, second_answer};
+use crate::{lines, Result, Value};
 
-pub fn run() {
-    let input: Vec<i32> = input(1)
-        .iter()
-        .map(|mass_str| mass_str.parse::<i32>().expect("Invalid number in input!"))
-        .collect();
+pub const TITLE: &str = "The Tyranny of the Rocket Equation";
+
+pub const LABELS: [&str; 2] = ["Naive fuel mass", "Real fuel mass"];
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let input: Vec<i32> = lines(input)
+        .map(|mass_str| mass_str.parse::<i32>())
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|_| "Invalid number in input!")?;
 
     let fuel_mass: i32 = input.iter().map(|m| *m).map(compute_fuel_naive).sum();
-    let real_fuel_mass: i32 = input
-        .iter()
-        .map(|mass| {
-            std::iter::successors(Some(compute_fuel_naive(*mass)), |mass| {
-                Some(compute_fuel_naive(*mass))
-            })
-            .take_while(|mass| *mass > 0)
-            .sum::<i32>()
-        })
-        .sum();
+    let real_fuel_mass: i32 = input.iter().map(|mass| compute_fuel_total(*mass)).sum();
 
-    first_answer("Naive fuel mass", &fuel_mass);
-    second_answer("Real fuel mass", &real_fuel_mass);
+    Ok((fuel_mass.into(), real_fuel_mass.into()))
 }
 
-fn compute_fuel_naive(mass: i32) -> i32 {
+///
+/// Computes the fuel needed to launch a module of the given mass, without
+/// the fuel's own mass.
+///
+/// ```rust
+/// # use lib::days::day01::compute_fuel_naive;
+/// assert_eq!(compute_fuel_naive(12), 2);
+/// assert_eq!(compute_fuel_naive(1969), 654);
+/// ```
+pub fn compute_fuel_naive(mass: i32) -> i32 {
     (mass as i32 / 3) - 2
 }
+
+///
+/// Computes the fuel needed to launch a module of the given mass, the
+/// fuel needing fuel too.
+///
+/// ```rust
+/// # use lib::days::day01::compute_fuel_total;
+/// assert_eq!(compute_fuel_total(14), 2);
+/// assert_eq!(compute_fuel_total(1969), 966);
+/// assert_eq!(compute_fuel_total(100756), 50346);
+/// ```
+pub fn compute_fuel_total(mass: i32) -> i32 {
+    std::iter::successors(Some(compute_fuel_naive(mass)), |mass| {
+        Some(compute_fuel_naive(*mass))
+    })
+    .take_while(|mass| *mass > 0)
+    .sum()
+}
@@ -1,35 +1,59 @@
 use itertools::Itertools;
 
-use crate::intcode::Program;
-use crate::{first_answer, input, second_answer};
-
-pub fn run() {
-    let source_code_raw = input(2).get(0).expect("Input file is empty").clone();
-    let mut program: Program = source_code_raw.parse().unwrap();
-
-    program.patch(1, 12);
-    program.patch(2, 2);
-
-    match program.execute() {
-        Ok(_) => first_answer("Program output", &program.get(0).unwrap()),
-        Err(e) => println!("{:?}", e),
-    }
-
-    const MOON_LANDING: i64 = 1969_07_20;
-
-    (0..99).tuple_combinations().for_each(|(noun, verb)| {
-        let mut program: Program = source_code_raw.parse().unwrap();
-
-        program.patch(1, noun);
-        program.patch(2, verb);
-
-        match program.execute() {
-            Ok(_) if program.get(0).unwrap() == MOON_LANDING => second_answer(
-                format!("Found noun = {} and verb = {}, so", noun, verb).as_str(),
-                &(100 * noun + verb),
-            ),
-            Ok(_) => (),
-            Err(e) => println!("{:?}", e),
-        }
-    });
+use crate::intcode::{Cell, Program};
+use crate::{first_line, relabel, Error, Result, Value};
+use std::error;
+
+///
+/// Runs the program from scratch with the given noun and verb, and returns
+/// its output, i.e. the first cell of its memory.
+///
+/// ```rust
+/// # use lib::days::day02::run_with;
+/// # use lib::intcode::Program;
+/// let mut program: Program = "1,0,0,0,99".parse().unwrap();
+/// assert_eq!(run_with(&mut program, 4, 4).unwrap(), 198);
+///
+/// let mut program: Program = "1,0,0,0".parse().unwrap();
+/// assert_eq!(
+///     run_with(&mut program, 0, 0).unwrap_err().to_string(),
+///     "Ran off the end of the memory at address 4; is the halt instruction (99) missing?"
+/// );
+/// ```
+pub fn run_with(
+    program: &mut Program,
+    noun: Cell,
+    verb: Cell,
+) -> std::result::Result<Cell, Box<dyn error::Error>> {
+    program.reset();
+    program.patch_many(&[(1, noun), (2, verb)])?.execute()?;
+
+    Ok(program.get(0))
+}
+
+pub const TITLE: &str = "1202 Program Alarm";
+
+pub const LABELS: [&str; 2] = ["Program output", "Noun and verb giving the moon landing"];
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let source_code_raw = first_line(input)?;
+    let mut program: Program = source_code_raw.parse()?;
+
+    let program_output = run_with(&mut program, 12, 2)?;
+
+    const MOON_LANDING: Cell = 1969_07_20;
+
+    // Programs failing for some noun and verb are not an issue, as long as
+    // one of them lands us on the moon.
+    let (noun, verb) = (0..99)
+        .tuple_combinations()
+        .find(|(noun, verb)| run_with(&mut program, *noun, *verb).ok() == Some(MOON_LANDING))
+        .ok_or_else(|| Error::from("No noun and verb give the moon landing date"))?;
+
+    relabel(
+        2,
+        format!("Found noun = {} and verb = {}, so", noun, verb),
+    );
+
+    Ok((program_output.into(), (100 * noun + verb).into()))
 }
@@ -1,13 +1,14 @@
-use crate::{first_answer, input, second_answer};
+use crate::{explain, lines, Value};
 
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::ops;
 use std::str::FromStr;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
-struct Vector {
-    x: i32,
-    y: i32,
+/// A point or a direction. Points are ordered by `x`, then `y`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Vector {
+    pub x: i32,
+    pub y: i32,
 }
 
 impl Vector {
@@ -61,21 +62,35 @@ impl FromStr for WirePart {
     type Err = &'static str;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(WirePart {
-            direction: match s.chars().nth(0).unwrap() {
-                'U' => Vector { x: 0, y: 1 },
-                'D' => Vector { x: 0, y: -1 },
-                'L' => Vector { x: -1, y: 0 },
-                'R' => Vector { x: 1, y: 0 },
-                _ => panic!("Invalid wire part: unknown direction"),
-            },
-            length: s[1..].parse::<u32>().expect("Invalid wire part: NaN"),
-        })
+        let direction = match s.chars().next() {
+            Some('U') => Vector { x: 0, y: 1 },
+            Some('D') => Vector { x: 0, y: -1 },
+            Some('L') => Vector { x: -1, y: 0 },
+            Some('R') => Vector { x: 1, y: 0 },
+            Some(_) => return Err("Invalid wire part: unknown direction"),
+            None => return Err("Invalid wire part: empty"),
+        };
+
+        match s[1..].parse::<u32>() {
+            Ok(0) => Err("Invalid wire part: zero length"),
+            Ok(length) => Ok(WirePart { direction, length }),
+            Err(_) => Err("Invalid wire part: NaN"),
+        }
     }
 }
 
+///
+/// A wire, made of parts going up, down, left or right from the center.
+///
+/// ```rust
+/// # use lib::days::day03::Wire;
+/// assert!("R8,U5,L5,D3".parse::<Wire>().is_ok());
+/// assert_eq!("R8,U0,L5".parse::<Wire>().err(), Some("Invalid wire part: zero length"));
+/// assert_eq!("R8,X5".parse::<Wire>().err(), Some("Invalid wire part: unknown direction"));
+/// assert_eq!("R8,,L5".parse::<Wire>().err(), Some("Invalid wire part: empty"));
+/// ```
 #[derive(Debug)]
-struct Wire {
+pub struct Wire {
     parts: Vec<WirePart>,
     points: Vec<Vector>,
 }
@@ -87,8 +102,8 @@ impl FromStr for Wire {
         let mut wire = Wire {
             parts: s
                 .split(',')
-                .map(|instruction| instruction.parse().unwrap())
-                .collect(),
+                .map(|instruction| instruction.parse())
+                .collect::<Result<_, _>>()?,
             points: vec![],
         };
 
@@ -113,7 +128,7 @@ impl Wire {
                             .map(|i| current_point + part.direction * i)
                             .collect();
                     points.extend(new_points.iter());
-                    (*new_points.last().unwrap(), points)
+                    (new_points.last().cloned().unwrap_or(current_point), points)
                 },
             )
             .1;
@@ -123,13 +138,34 @@ impl Wire {
         self.points.iter().cloned().collect()
     }
 
-    fn intersect_all(wires: &Vec<Wire>) -> HashSet<Vector> {
-        wires
-            .iter()
-            .skip(1)
-            .fold(wires.first().unwrap().points_set(), |acc, wire| {
-                acc.intersection(&wire.points_set()).cloned().collect()
-            })
+    ///
+    /// Returns the points shared by all the wires, in order. With less than
+    /// two wires, there is nothing to intersect and the set is empty.
+    ///
+    /// ```rust
+    /// # use lib::days::day03::{Vector, Wire};
+    /// let wire = |data: &str| data.parse::<Wire>().unwrap();
+    ///
+    /// assert_eq!(
+    ///     Wire::intersect_all(&[wire("R8,U5,L5,D3"), wire("U7,R6,D4,L4")])
+    ///         .into_iter()
+    ///         .collect::<Vec<_>>(),
+    ///     vec![Vector { x: 3, y: 3 }, Vector { x: 6, y: 5 }]
+    /// );
+    /// assert!(Wire::intersect_all(&[wire("R8,U5,L5,D3")]).is_empty());
+    /// assert!(Wire::intersect_all(&[]).is_empty());
+    /// ```
+    pub fn intersect_all(wires: &[Wire]) -> BTreeSet<Vector> {
+        match wires {
+            [first, others @ ..] if !others.is_empty() => others
+                .iter()
+                .fold(first.points_set(), |acc, wire| {
+                    acc.intersection(&wire.points_set()).cloned().collect()
+                })
+                .into_iter()
+                .collect(),
+            _ => BTreeSet::new(),
+        }
     }
 
     fn signal_delay_to(&self, point: &Vector) -> u32 {
@@ -137,30 +173,109 @@ impl Wire {
     }
 }
 
-pub fn run() {
-    let wires = input(3)
-        .iter()
-        .map(|wire_data| wire_data.parse().expect("Invalid wire data"))
-        .collect();
-    let intersections = Wire::intersect_all(&wires);
-
-    let min_manhattan = intersections
-        .iter()
-        .map(|p| p.manhattan_distance(&CENTER))
-        .min()
-        .unwrap();
+/// A point shared by wires, with the number of steps each wire takes to
+/// reach it, in the order of the wires.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Intersection {
+    pub point: Vector,
+    pub steps: Vec<u32>,
+}
 
-    let min_signal: u32 = intersections
-        .iter()
-        .map(|intersection| {
-            wires
+///
+/// Returns the intersections of the wires, in the order of their points,
+/// with the number of steps each wire takes to reach them.
+///
+/// ```rust
+/// # use lib::days::day03::{intersections, Vector, Wire};
+/// let wires = vec!["R8,U5,L5,D3".parse::<Wire>().unwrap(), "U7,R6,D4,L4".parse().unwrap()];
+///
+/// let found = intersections(&wires);
+/// assert_eq!(found.len(), 2);
+/// assert_eq!((found[0].point, &found[0].steps), (Vector { x: 3, y: 3 }, &vec![20, 20]));
+/// assert_eq!((found[1].point, &found[1].steps), (Vector { x: 6, y: 5 }, &vec![15, 15]));
+///
+/// assert!(intersections(&wires[..1]).is_empty());
+/// ```
+pub fn intersections(wires: &[Wire]) -> Vec<Intersection> {
+    Wire::intersect_all(wires)
+        .into_iter()
+        .map(|point| Intersection {
+            point,
+            steps: wires
                 .iter()
-                .map(|wire| wire.signal_delay_to(intersection))
-                .sum()
+                .map(|wire| wire.signal_delay_to(&point))
+                .collect(),
         })
-        .min()
-        .unwrap();
+        .collect()
+}
+
+///
+/// Finds the intersection minimizing the given metric, and returns it
+/// alongside its value. Ties are broken by the point's coordinates (the
+/// first in order wins).
+///
+/// ```rust
+/// # use lib::days::day03::{best_intersection, intersections, Intersection, Vector, Wire};
+/// let wires = vec!["R8,U5,L5,D3".parse::<Wire>().unwrap(), "U7,R6,D4,L4".parse().unwrap()];
+/// let found = intersections(&wires);
+///
+/// let manhattan = |i: &Intersection| (i.point.x.abs() + i.point.y.abs()) as i64;
+/// let (closest, distance) = best_intersection(&found, manhattan).unwrap();
+/// assert_eq!((closest.point, distance), (Vector { x: 3, y: 3 }, 6));
+/// assert_eq!(closest.steps, vec![20, 20]);
+///
+/// let combined = |i: &Intersection| i.steps.iter().sum::<u32>() as i64;
+/// assert_eq!(best_intersection(&found, combined).unwrap().1, 30);
+///
+/// // Closest to the point (7, 7), with the Chebyshev distance.
+/// let anchor = |i: &Intersection| (i.point.x - 7).abs().max((i.point.y - 7).abs()) as i64;
+/// let (closest, distance) = best_intersection(&found, anchor).unwrap();
+/// assert_eq!((closest.point, distance), (Vector { x: 6, y: 5 }, 2));
+///
+/// assert!(best_intersection(&[], manhattan).is_none());
+/// ```
+pub fn best_intersection(
+    intersections: &[Intersection],
+    metric: impl Fn(&Intersection) -> i64,
+) -> Option<(&Intersection, i64)> {
+    intersections
+        .iter()
+        .map(|intersection| (intersection, metric(intersection)))
+        .min_by_key(|(_, value)| *value)
+}
+
+pub const TITLE: &str = "Crossed Wires";
+
+pub const LABELS: [&str; 2] = [
+    "Distance to the closest intersection",
+    "Minimal combined steps (signal)",
+];
+
+pub fn solve(input: &str) -> crate::Result<(Value, Value)> {
+    let wires: Vec<Wire> = lines(input)
+        .map(|wire_data| wire_data.parse())
+        .collect::<std::result::Result<_, _>>()?;
+
+    let intersections = intersections(&wires);
+
+    let (closest_intersection, min_manhattan) = best_intersection(&intersections, |intersection| {
+        intersection.point.manhattan_distance(&CENTER) as i64
+    })
+    .ok_or("No intersections found")?;
+
+    let (_, min_signal) = best_intersection(&intersections, |intersection| {
+        intersection.steps.iter().sum::<u32>() as i64
+    })
+    .ok_or("No intersections found")?;
+
+    explain("Intersections found", &intersections.len());
+    explain(
+        "Closest intersection",
+        &format!(
+            "({}, {})",
+            closest_intersection.point.x, closest_intersection.point.y
+        ),
+    );
 
-    first_answer("Distance to the closest intersection", &min_manhattan);
-    second_answer("Minimal combined steps (signal)", &min_signal);
+    Ok((min_manhattan.into(), min_signal.into()))
 }
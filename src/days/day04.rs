@@ -1,26 +1,45 @@
-use crate::{first_answer, second_answer};
+use crate::{first_line, Result, Value};
 
 use itertools::Itertools;
-use std::ops::Range;
+use std::ops::RangeInclusive;
 
-const RANGE: Range<u32> = 256_310..732_737;
+pub const TITLE: &str = "Secure Container";
 
-pub fn run() {
-    first_answer(
-        "Number of possible passwords",
-        &RANGE
+pub const LABELS: [&str; 2] = [
+    "Number of possible passwords",
+    "Number of possible passwords with the additional rule",
+];
+
+/// Parses the `min-max` range of passwords, both included.
+pub fn parse_range(input: &str) -> Result<RangeInclusive<u32>> {
+    let bounds = first_line(input)?
+        .split('-')
+        .map(|bound| bound.trim().parse::<u32>())
+        .collect::<std::result::Result<Vec<u32>, _>>()
+        .map_err(|_| "Invalid range bound")?;
+
+    match bounds[..] {
+        [min, max] => Ok(min..=max),
+        _ => Err("The range must be two bounds separated by a dash".into()),
+    }
+}
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let range = parse_range(input)?;
+
+    Ok((
+        range
+            .clone()
             .filter(ordered_numbers)
             .filter(|i| number_groups_sizes_match(i, |n| n >= 2))
-            .count(),
-    );
-
-    second_answer(
-        "Number of possible passwords with the additional rule",
-        &RANGE
+            .count()
+            .into(),
+        range
             .filter(ordered_numbers)
             .filter(|i| number_groups_sizes_match(i, |n| n == 2))
-            .count(),
-    );
+            .count()
+            .into(),
+    ))
 }
 
 ///
@@ -61,7 +80,148 @@ where
 {
     i.to_string()
         .chars()
-        .group_by(|d| d.clone())
+        .group_by(|d| *d)
         .into_iter()
         .any(|(_group, iter)| predicate(iter.count() as u8))
 }
+
+/// Whether a number is a valid password for the first and the second part.
+pub type Verdict = (bool, bool);
+
+/// A number for which the implementation and the oracle disagree.
+/// See `verify_against_oracle`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mismatch {
+    pub number: u32,
+
+    /// The verdict of the oracle.
+    pub expected: Verdict,
+
+    /// The verdict of the implementation.
+    pub actual: Verdict,
+}
+
+/// Checks a number against the rules of both parts, as `solve` does.
+pub fn verdict(i: u32) -> Verdict {
+    (
+        ordered_numbers(&i) && number_groups_sizes_match(&i, |n| n >= 2),
+        ordered_numbers(&i) && number_groups_sizes_match(&i, |n| n == 2),
+    )
+}
+
+///
+/// Reference implementation of the rules, deliberately naive: it works on
+/// the digits as characters, and checks each rule the most literal way.
+/// Only used to verify `verdict`.
+///
+/// ```rust
+/// # use lib::days::day04::oracle_verdict;
+/// assert_eq!(oracle_verdict(111_111), (true, false));
+/// assert_eq!(oracle_verdict(112_233), (true, true));
+/// assert_eq!(oracle_verdict(123_444), (true, false));
+/// assert_eq!(oracle_verdict(111_122), (true, true));
+/// assert_eq!(oracle_verdict(223_450), (false, false));
+/// ```
+pub fn oracle_verdict(i: u32) -> Verdict {
+    let digits: Vec<char> = i.to_string().chars().collect();
+
+    let mut ordered = true;
+    let mut has_pair = false;
+    let mut has_exact_pair = false;
+
+    for j in 0..digits.len() - 1 {
+        if digits[j] > digits[j + 1] {
+            ordered = false;
+        }
+
+        if digits[j] == digits[j + 1] {
+            has_pair = true;
+
+            let same_before = j > 0 && digits[j - 1] == digits[j];
+            let same_after = j + 2 < digits.len() && digits[j + 2] == digits[j];
+
+            if !same_before && !same_after {
+                has_exact_pair = true;
+            }
+        }
+    }
+
+    (ordered && has_pair, ordered && has_exact_pair)
+}
+
+///
+/// Compares `verdict` against the reference `oracle_verdict`, on the whole
+/// range if it has at most `sample` numbers, or else on `sample` numbers
+/// drawn from it with the given seed. Returns the first mismatch.
+///
+/// ```rust
+/// # use lib::days::day04::verify_against_oracle;
+/// assert_eq!(verify_against_oracle(100_000..=200_000, 1_000, 42), Ok(()));
+/// ```
+pub fn verify_against_oracle(
+    range: RangeInclusive<u32>,
+    sample: usize,
+    seed: u64,
+) -> std::result::Result<(), Mismatch> {
+    verify_implementation(range, sample, seed, verdict)
+}
+
+///
+/// Same as `verify_against_oracle`, but for any implementation of the
+/// rules.
+///
+/// ```rust
+/// # use lib::days::day04::{verdict, verify_implementation, Mismatch};
+/// // Wrong for numbers with a group of exactly three digits.
+/// let wrong = |i: u32| match verdict(i) {
+///     (first, _) if i.to_string().contains("444") && !i.to_string().contains("4444") => (first, true),
+///     verdict => verdict,
+/// };
+///
+/// assert_eq!(
+///     verify_implementation(123_440..=123_450, 100, 0, wrong),
+///     Err(Mismatch { number: 123_444, expected: (true, false), actual: (true, true) })
+/// );
+/// assert!(verify_implementation(100_000..=999_999, 10_000, 7, wrong).is_err());
+/// ```
+pub fn verify_implementation<F>(
+    range: RangeInclusive<u32>,
+    sample: usize,
+    seed: u64,
+    implementation: F,
+) -> std::result::Result<(), Mismatch>
+where
+    F: Fn(u32) -> Verdict,
+{
+    let check = |number: u32| {
+        let expected = oracle_verdict(number);
+        let actual = implementation(number);
+
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(Mismatch {
+                number,
+                expected,
+                actual,
+            })
+        }
+    };
+
+    let (start, end) = (*range.start(), *range.end());
+    let size = (end as u64 + 1).saturating_sub(start as u64);
+
+    if size <= sample as u64 {
+        return range.into_iter().try_for_each(check);
+    }
+
+    // A linear congruential generator is random enough to sample numbers.
+    let mut state = seed;
+    (0..sample).try_for_each(|_| {
+        state = state
+            .wrapping_mul(6_364_136_223_846_793_005)
+            .wrapping_add(1_442_695_040_888_963_407);
+
+        check(start + ((state >> 33) % size) as u32)
+    })
+}
@@ -1,28 +1,32 @@
-use crate::intcode::Program;
-use crate::{answer, input};
+use crate::intcode::{Cell, Program};
+use crate::{first_line, Error, Result, Value};
 
-pub fn run() {
-    let source_code_raw = input(5).get(0).expect("Input file is empty").clone();
+pub const TITLE: &str = "Sunny with a Chance of Asteroids";
 
-    vec![1, 5]
-        .into_iter()
-        .enumerate()
-        .for_each(|(answer_num, input)| {
-            let mut program: Program = source_code_raw.parse().unwrap();
+pub const LABELS: [&str; 2] = [
+    "Diagnostic code for system ID 1",
+    "Diagnostic code for system ID 5",
+];
 
-            program.set_input(move |_| Ok(input.clone()));
+/// Runs the diagnostic program for a system, returning the diagnostic code.
+/// All the outputs before it are tests results, and must be zero.
+fn diagnostic_code(source_code: &str, system_id: Cell) -> Result<Cell> {
+    let mut program: Program = source_code.parse()?;
 
-            match program.execute() {
-                Ok(output) => answer(
-                    answer_num + 1,
-                    format!("Diagnostic code for system ID {}", input).as_str(),
-                    &output
-                        .iter()
-                        .filter(|i| i != &&0)
-                        .map(|i| i.to_string())
-                        .collect::<String>(),
-                ),
-                Err(e) => println!("{:?}", e),
-            };
-        });
+    match program.execute_with_inputs(&[system_id])?.split_last() {
+        Some((code, tests)) if tests.iter().all(|test| *test == 0) => Ok(*code),
+        Some(_) => Err(Error {
+            message: format!("Diagnostic tests failed: {}", program.output_csv()),
+        }),
+        None => Err("No diagnostic code".into()),
+    }
+}
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let source_code = first_line(input)?;
+
+    Ok((
+        diagnostic_code(source_code, 1)?.into(),
+        diagnostic_code(source_code, 5)?.into(),
+    ))
 }
@@ -1,69 +1,331 @@
-use crate::{first_answer, input, second_answer};
+use crate::util::Interner;
+use crate::{collect, explain_enabled, lines, stats_enabled, Value};
+use itertools::Itertools;
+use std::cmp::Reverse;
 use std::collections::binary_heap::BinaryHeap;
-use std::collections::HashMap;
+use std::collections::{HashSet, VecDeque};
 
 /// Our orbits map (graph). Stored as a list of edges connected from one point to another.
+/// Bodies are interned, so the edges are lists of symbols indexed by symbol.
 #[derive(Debug)]
-struct OrbitalMap {
-    edges: HashMap<String, Vec<String>>,
+pub struct OrbitalMap {
+    names: Interner,
+    edges: Vec<Vec<u32>>,
+
+    /// The body each body orbits, as given by the orbits, indexed by symbol.
+    parents: Vec<Option<u32>>,
+}
+
+/// The differences between two orbital maps, as `(parent, satellite)` edges.
+/// See `OrbitalMap::diff`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrbitDiff {
+    /// Edges only in the other map.
+    pub added: Vec<(String, String)>,
+
+    /// Edges only in this map.
+    pub removed: Vec<(String, String)>,
+}
+
+/// Structural statistics of an orbital map. See `OrbitalMap::stats`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OrbitStats {
+    /// The depth of the body the farthest from the root.
+    pub max_depth: usize,
+
+    /// The number of bodies at each depth, starting with the root at depth 0.
+    pub depth_histogram: Vec<usize>,
+
+    /// The largest subtree directly orbiting the root, as its root and
+    /// its size (in bodies). None if nothing orbits the root.
+    pub largest_subtree: Option<(String, usize)>,
+
+    /// The number of bodies orbited by nothing.
+    pub leaves: usize,
+
+    /// The total number of direct and indirect orbits, i.e. the sum of the
+    /// depths. See `OrbitalMap::checksum`.
+    pub checksum: usize,
 }
 
 impl OrbitalMap {
-    pub fn new(orbits: Vec<String>) -> Self {
+    /// Builds a map from `A)B` orbits. Malformed orbits are ignored.
+    pub fn new(orbits: &[&str]) -> Self {
+        OrbitalMap::from_edges(orbits.iter().filter_map(|orbit| {
+            match orbit.split(')').collect::<Vec<&str>>()[..] {
+                [parent, satellite] => Some((parent, satellite)),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Builds a map from `(parent, satellite)` edges.
+    pub fn from_edges<'a>(edges: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
         let mut orbits_map = OrbitalMap {
-            edges: HashMap::new(),
+            names: Interner::new(),
+            edges: vec![],
+            parents: vec![],
         };
 
-        orbits
-            .iter()
-            .map(|orbit| orbit.split(')').collect::<Vec<&str>>())
-            .filter(|orbit| orbit.len() == 2)
-            .for_each(|orbit| {
-                let from = orbit[0];
-                let to = orbit[1];
-
-                orbits_map
-                    .edges
-                    .entry(from.to_string())
-                    .or_insert_with(|| vec![])
-                    .push(to.to_string());
-                orbits_map
-                    .edges
-                    .entry(to.to_string())
-                    .or_insert_with(|| vec![])
-                    .push(from.to_string());
-            });
+        edges.into_iter().for_each(|(from, to)| {
+            let from = orbits_map.intern(from);
+            let to = orbits_map.intern(to);
+
+            orbits_map.link(from, to);
+        });
 
         orbits_map
     }
 
+    /// Interns a body, making room for its edges if it's a new one.
+    fn intern(&mut self, body: &str) -> u32 {
+        let symbol = self.names.get_or_intern(body);
+        if symbol as usize == self.edges.len() {
+            self.edges.push(vec![]);
+            self.parents.push(None);
+        }
+        symbol
+    }
+
+    /// Adds the edge of `satellite` orbiting `parent`.
+    fn link(&mut self, parent: u32, satellite: u32) {
+        self.edges[parent as usize].push(satellite);
+        self.edges[satellite as usize].push(parent);
+        self.parents[satellite as usize] = Some(parent);
+    }
+
+    ///
+    /// Adds an orbit to the map, `satellite` orbiting `parent`. Fails if
+    /// the satellite already orbits another body, or if the orbit would
+    /// close a cycle, e.g. a body orbiting itself.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::OrbitalMap;
+    /// let orbits = vec!["COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L"];
+    /// let mut map = OrbitalMap::new(&orbits);
+    /// assert_eq!(map.checksum(), 42);
+    ///
+    /// // G now orbits C instead of B.
+    /// map.remove_orbit("B", "G").unwrap();
+    /// map.add_orbit("C", "G").unwrap();
+    ///
+    /// let rebuilt: Vec<String> = orbits.iter().map(|o| o.replace("B)G", "C)G")).collect();
+    /// let rebuilt = OrbitalMap::new(&rebuilt.iter().map(String::as_str).collect::<Vec<_>>());
+    /// assert_eq!(map.checksum(), rebuilt.checksum());
+    /// assert_eq!(map.checksum(), 44);
+    /// assert_eq!(map.stats("COM"), rebuilt.stats("COM"));
+    ///
+    /// assert_eq!(map.add_orbit("D", "G").unwrap_err().message, "G already orbits C");
+    /// map.add_orbit("L", "M").unwrap();
+    /// assert_eq!(map.checksum(), 44 + 8);
+    ///
+    /// assert_eq!(map.add_orbit("X", "X").unwrap_err().message, "X can't orbit itself");
+    /// assert_eq!(
+    ///     map.add_orbit("C", "COM").unwrap_err().message,
+    ///     "COM can't orbit C, which orbits it"
+    /// );
+    /// assert_eq!(map.checksum(), 44 + 8);
+    /// ```
+    pub fn add_orbit(&mut self, parent: &str, satellite: &str) -> crate::Result<()> {
+        if let Some(current) = self
+            .names
+            .get(satellite)
+            .and_then(|satellite| self.parents[satellite as usize])
+        {
+            return Err(crate::Error {
+                message: format!("{} already orbits {}", satellite, self.name(current)),
+            });
+        }
+
+        if parent == satellite {
+            return Err(crate::Error {
+                message: format!("{} can't orbit itself", satellite),
+            });
+        }
+
+        // The satellite orbits nothing, so a cycle would go through the
+        // bodies the parent orbits. The walk is bounded, as maps built from
+        // orbits lists are not checked for cycles.
+        if let (Some(parent_symbol), Some(satellite_symbol)) =
+            (self.names.get(parent), self.names.get(satellite))
+        {
+            let mut ancestors = std::iter::successors(Some(parent_symbol), |body| {
+                self.parents[*body as usize]
+            })
+            .take(self.parents.len());
+            if ancestors.any(|body| body == satellite_symbol) {
+                return Err(crate::Error {
+                    message: format!("{} can't orbit {}, which orbits it", satellite, parent),
+                });
+            }
+        }
+
+        let parent = self.intern(parent);
+        let satellite = self.intern(satellite);
+        self.link(parent, satellite);
+
+        Ok(())
+    }
+
+    ///
+    /// Removes the orbit of `satellite` around `parent` from the map. Both
+    /// bodies stay in the map. Fails if there is no such orbit.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::OrbitalMap;
+    /// let mut map = OrbitalMap::new(&["COM)B", "B)C"]);
+    /// map.remove_orbit("B", "C").unwrap();
+    ///
+    /// assert_eq!(map.checksum(), 1);
+    /// assert_eq!(map.distance("C", "COM"), None);
+    /// assert_eq!(map.remove_orbit("B", "C").unwrap_err().message, "C doesn't orbit B");
+    /// assert_eq!(map.remove_orbit("X", "Y").unwrap_err().message, "Y doesn't orbit X");
+    /// ```
+    pub fn remove_orbit(&mut self, parent: &str, satellite: &str) -> crate::Result<()> {
+        let symbols = (self.names.get(parent), self.names.get(satellite));
+
+        match symbols {
+            (Some(parent), Some(satellite))
+                if self.parents[satellite as usize] == Some(parent) =>
+            {
+                self.edges[parent as usize].retain(|body| *body != satellite);
+                self.edges[satellite as usize].retain(|body| *body != parent);
+                self.parents[satellite as usize] = None;
+
+                Ok(())
+            }
+            _ => Err(crate::Error {
+                message: format!("{} doesn't orbit {}", satellite, parent),
+            }),
+        }
+    }
+
+    fn name(&self, symbol: u32) -> String {
+        self.names.resolve(symbol).to_string()
+    }
+
+    /// Returns the `(parent, satellite)` edges of the map, parents being
+    /// the bodies closer to `root`, sorted. Bodies not connected to `root`
+    /// are left out.
+    fn oriented_edges(&self, root: &str) -> Vec<(String, String)> {
+        let mut edges: Vec<(String, String)> = self
+            .oriented_symbol_edges(root)
+            .into_iter()
+            .map(|(parent, satellite)| (self.name(parent), self.name(satellite)))
+            .collect();
+
+        edges.sort();
+        edges
+    }
+
+    /// Returns the `(parent, satellite)` edges of the map as symbols, in
+    /// traversal order from `root`.
+    fn oriented_symbol_edges(&self, root: &str) -> Vec<(u32, u32)> {
+        let mut edges = vec![];
+        let mut visited = vec![false; self.edges.len()];
+        let mut queue: VecDeque<u32> = VecDeque::new();
+
+        if let Some(root) = self.names.get(root) {
+            visited[root as usize] = true;
+            queue.push_back(root);
+        }
+
+        while let Some(body) = queue.pop_front() {
+            for &other_body in &self.edges[body as usize] {
+                if !visited[other_body as usize] {
+                    visited[other_body as usize] = true;
+                    edges.push((body, other_body));
+                    queue.push_back(other_body);
+                }
+            }
+        }
+
+        edges
+    }
+
+    ///
+    /// Regenerates the `A)B` orbits list of the map, with parents being
+    /// the bodies closer to `root`, in sorted order.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::OrbitalMap;
+    /// let map = OrbitalMap::new(&[
+    ///     "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L",
+    /// ]);
+    ///
+    /// let lines = map.to_lines("COM");
+    /// assert_eq!(lines[..3], ["B)C", "B)G", "C)D"]);
+    /// let borrowed: Vec<&str> = lines.iter().map(String::as_str).collect();
+    /// assert_eq!(OrbitalMap::new(&borrowed).to_lines("COM"), lines);
+    /// ```
+    pub fn to_lines(&self, root: &str) -> Vec<String> {
+        self.oriented_edges(root)
+            .into_iter()
+            .map(|(from, to)| format!("{}){}", from, to))
+            .collect()
+    }
+
+    ///
+    /// Lists the orbits added and removed in `other` compared to this map,
+    /// both being oriented from `root`.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::{OrbitalMap, OrbitDiff};
+    /// let before = OrbitalMap::new(&["COM)B", "B)C", "C)D", "B)E", "E)F"]);
+    /// let after = OrbitalMap::new(&["COM)B", "B)C", "C)D", "B)E", "C)F"]);
+    ///
+    /// assert_eq!(
+    ///     before.diff(&after, "COM"),
+    ///     OrbitDiff {
+    ///         added: vec![("C".to_string(), "F".to_string())],
+    ///         removed: vec![("E".to_string(), "F".to_string())],
+    ///     }
+    /// );
+    /// ```
+    pub fn diff(&self, other: &OrbitalMap, root: &str) -> OrbitDiff {
+        let edges = self.oriented_edges(root);
+        let other_edges = other.oriented_edges(root);
+
+        OrbitDiff {
+            added: other_edges
+                .iter()
+                .filter(|edge| edges.binary_search(edge).is_err())
+                .cloned()
+                .collect(),
+            removed: edges
+                .iter()
+                .filter(|edge| other_edges.binary_search(edge).is_err())
+                .cloned()
+                .collect(),
+        }
+    }
+
     /// Computes the distance from `from` to `to` using the
     /// Dijkstra algorithm.
     pub fn distance(&self, from: &str, to: &str) -> Option<usize> {
-        let mut distances: HashMap<String, usize> = self
-            .edges
-            .keys()
-            .map(|body| (body.clone(), std::usize::MAX))
-            .collect();
+        self.distance_between(self.names.get(from)?, self.names.get(to)?)
+    }
 
-        distances.insert(from.to_string(), 0);
+    fn distance_between(&self, from: u32, to: u32) -> Option<usize> {
+        let mut distances = vec![usize::MAX; self.edges.len()];
+        distances[from as usize] = 0;
 
         let mut heap = BinaryHeap::new();
-        heap.push((from.to_string(), 0));
+        heap.push(Reverse((0, from)));
 
-        while let Some((body, dist)) = heap.pop() {
-            if body == to.to_string() {
+        while let Some(Reverse((dist, body))) = heap.pop() {
+            if body == to {
                 return Some(dist);
             }
 
-            if dist > distances.get(&body).unwrap().clone() {
+            if dist > distances[body as usize] {
                 continue;
             }
 
-            for other_body in self.edges.get(&body).unwrap() {
-                if dist + 1 < distances.get(other_body).unwrap().clone() {
-                    distances.insert(body.clone(), dist + 1);
-                    heap.push((other_body.clone(), dist + 1));
+            for &other_body in &self.edges[body as usize] {
+                if dist + 1 < distances[other_body as usize] {
+                    distances[other_body as usize] = dist + 1;
+                    heap.push(Reverse((dist + 1, other_body)));
                 }
             }
         }
@@ -71,24 +333,186 @@ impl OrbitalMap {
         None
     }
 
+    ///
+    /// Finds the closest body both `a` and `b` orbit, directly or
+    /// indirectly, parents being the bodies closer to `root`.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::OrbitalMap;
+    /// let map = OrbitalMap::new(&[
+    ///     "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L", "K)YOU",
+    ///     "I)SAN",
+    /// ]);
+    ///
+    /// assert_eq!(map.common_ancestor("YOU", "SAN", "COM"), Some("D".to_string()));
+    /// assert_eq!(map.common_ancestor("H", "L", "COM"), Some("B".to_string()));
+    /// ```
+    pub fn common_ancestor(&self, a: &str, b: &str, root: &str) -> Option<String> {
+        let mut parents = vec![None; self.edges.len()];
+        for (parent, satellite) in self.oriented_symbol_edges(root) {
+            parents[satellite as usize] = Some(parent);
+        }
+
+        let ancestors = |body: u32| {
+            std::iter::successors(parents[body as usize], |body| parents[*body as usize])
+        };
+
+        let a_ancestors: HashSet<u32> = ancestors(self.names.get(a)?).collect();
+        let common = ancestors(self.names.get(b)?).find(|body| a_ancestors.contains(body));
+        common.map(|body| self.name(body))
+    }
+
+    ///
+    /// Computes structural statistics of the map, and its checksum from
+    /// `root`, in a single traversal.
+    ///
+    /// ```rust
+    /// # use lib::days::day06::{OrbitalMap, OrbitStats};
+    /// let map = OrbitalMap::new(&[
+    ///     "COM)B", "B)C", "C)D", "D)E", "E)F", "B)G", "G)H", "D)I", "E)J", "J)K", "K)L",
+    /// ]);
+    ///
+    /// assert_eq!(
+    ///     map.stats("COM"),
+    ///     OrbitStats {
+    ///         max_depth: 7,
+    ///         depth_histogram: vec![1, 1, 2, 2, 2, 2, 1, 1],
+    ///         largest_subtree: Some(("B".to_string(), 11)),
+    ///         leaves: 4,
+    ///         checksum: 42,
+    ///     }
+    /// );
+    ///
+    /// let single = OrbitalMap::new(&[]).stats("COM");
+    /// assert_eq!(single.max_depth, 0);
+    /// assert_eq!(single.depth_histogram, vec![1]);
+    /// assert_eq!(single.largest_subtree, None);
+    /// assert_eq!(single.leaves, 1);
+    /// assert_eq!(single.checksum, 0);
+    /// ```
+    pub fn stats(&self, root: &str) -> OrbitStats {
+        let bodies = self.traverse(root);
+
+        let max_depth = bodies.iter().map(|(_, _, depth)| *depth).max().unwrap_or(0);
+        let mut depth_histogram = vec![0; max_depth + 1];
+        let mut subtree_sizes = vec![1; bodies.len()];
+        let mut satellites_count = vec![0; bodies.len()];
+
+        for (i, (_, parent, depth)) in bodies.iter().enumerate().rev() {
+            depth_histogram[*depth] += 1;
+
+            if let Some(parent) = parent {
+                subtree_sizes[*parent] += subtree_sizes[i];
+                satellites_count[*parent] += 1;
+            }
+        }
+
+        OrbitStats {
+            max_depth,
+            largest_subtree: bodies
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, parent, _))| *parent == Some(0))
+                .max_by_key(|(i, _)| subtree_sizes[*i])
+                .map(|(i, (body, _, _))| {
+                    (self.name(body.unwrap()), subtree_sizes[i])
+                }),
+            leaves: satellites_count.iter().filter(|count| **count == 0).count(),
+            checksum: depth_histogram
+                .iter()
+                .enumerate()
+                .map(|(depth, count)| depth * count)
+                .sum(),
+            depth_histogram,
+        }
+    }
+
+    /// Returns the bodies connected to `root`, in breadth-first order, with
+    /// the index of their parent and their depth. The root is None if it's
+    /// not in the map, in which case it's alone.
+    fn traverse(&self, root: &str) -> Vec<(Option<u32>, Option<usize>, usize)> {
+        let mut bodies: Vec<(Option<u32>, Option<usize>, usize)> =
+            vec![(self.names.get(root), None, 0)];
+        let mut visited = vec![false; self.edges.len()];
+
+        if let Some(root) = bodies[0].0 {
+            visited[root as usize] = true;
+        }
+
+        let mut i = 0;
+        while i < bodies.len() {
+            let (body, _, depth) = bodies[i];
+
+            if let Some(body) = body {
+                for &satellite in &self.edges[body as usize] {
+                    if !visited[satellite as usize] {
+                        visited[satellite as usize] = true;
+                        bodies.push((Some(satellite), Some(i), depth + 1));
+                    }
+                }
+            }
+
+            i += 1;
+        }
+
+        bodies
+    }
+
+    /// Counts the direct and indirect orbits, i.e. sums the depths of the
+    /// bodies from COM, in a single traversal.
     pub fn checksum(&self) -> usize {
-        self.edges
-            .keys()
-            .filter_map(|body| self.distance(body.as_str(), "COM"))
+        self.traverse("COM")
+            .iter()
+            .map(|(_, _, depth)| depth)
             .sum()
     }
 }
 
-pub fn run() {
-    let orbital_map = OrbitalMap::new(input(6));
+pub const TITLE: &str = "Universal Orbit Map";
+
+pub const LABELS: [&str; 2] = [
+    "Orbital map checksum",
+    "How many orbital transfers from us (YOU) to Santa (SAN)",
+];
 
-    first_answer("Orbital map checksum", &orbital_map.checksum());
+pub fn solve(input: &str) -> crate::Result<(Value, Value)> {
+    let orbital_map = OrbitalMap::new(&lines(input).collect::<Vec<&str>>());
+
+    // The statistics come with the checksum, so it's not computed twice.
+    let checksum = if stats_enabled() {
+        let stats = orbital_map.stats("COM");
+        collect("Maximal depth", &stats.max_depth);
+        collect("Bodies per depth", &stats.depth_histogram.iter().join(" "));
+        if let Some((body, size)) = &stats.largest_subtree {
+            collect("Largest subtree", &format!("{} ({} bodies)", body, size));
+        }
+        collect("Leaves", &stats.leaves);
+
+        stats.checksum
+    } else {
+        orbital_map.checksum()
+    };
+
+    // Each of these is a traversal of the map, only done to explain.
+    if explain_enabled() {
+        let depth = |body| orbital_map.distance(body, "COM").ok_or("YOU or SAN not found");
+        collect("Depth of YOU", &depth("YOU")?);
+        collect("Depth of SAN", &depth("SAN")?);
+        collect(
+            "Common ancestor of YOU and SAN",
+            &orbital_map
+                .common_ancestor("YOU", "SAN", "COM")
+                .ok_or("YOU and SAN have no common ancestor")?,
+        );
+    }
 
     // We compute the distance from YOU to SAN with Dijkstra, but we
     // want the number of orbits **transfers**, so we have to remove two
     // hops for the first and last orbits.
-    second_answer(
-        "How many orbital transfers from us (YOU) to Santa (SAN)",
-        &(orbital_map.distance("YOU", "SAN").unwrap() - 2),
-    );
+    let transfers = orbital_map
+        .distance("YOU", "SAN")
+        .ok_or("YOU and SAN are not connected")?
+        - 2;
+
+    Ok((checksum.into(), transfers.into()))
 }
@@ -1,129 +1,194 @@
-use crate::intcode::{Error, Program};
-use crate::{first_answer, input, second_answer};
+use crate::intcode::{self, Cell, Program};
+use crate::{explain, first_line, Result, Value};
 
 use itertools::Itertools;
-use std::sync::{
-    mpsc::{channel, Receiver, Sender},
-    Arc,
-};
-use std::thread;
-use std::thread::JoinHandle;
-
-/// Runs an amplifier. This should be executed in a thread.
-/// Data should be received from `rx`, and output sent as they go to `tx`.
-fn run_amplifier(source_code: Arc<String>, rx: Receiver<i64>, tx: Sender<i64>) {
-    let mut program: Program = source_code.parse().unwrap();
-
-    program.set_input(move |_| {
-        rx.recv().map_err(|_| Error {
-            message: "Cannot receive input",
+
+///
+/// Runs the amplifiers in series, each one with its phase setting, the
+/// first one receiving a 0 signal. Returns the signal outputted by each
+/// amplifier.
+///
+/// ```rust
+/// # use lib::days::day07::run_chain;
+/// assert_eq!(
+///     run_chain("3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0", &[4, 3, 2, 1, 0]).unwrap(),
+///     vec![4, 43, 432, 4321, 43210]
+/// );
+/// ```
+pub fn run_chain(source_code: &str, phase_settings: &[Cell]) -> Result<Vec<Cell>> {
+    let mut signals = Vec::with_capacity(phase_settings.len());
+
+    for &setting in phase_settings {
+        let mut program: Program = source_code.parse()?;
+
+        // The amplifier reads the signal of the previous one right from
+        // `signals`, borrowed for the duration of the execution.
+        let outputs = program.with_input_scope(
+            |n| match n {
+                0 => Ok(setting),
+                1 => Ok(signals.last().cloned().unwrap_or(0)),
+                _ => Err(intcode::Error::InvalidInput("More inputs requested than provided")),
+            },
+            |amplifier| amplifier.execute(),
+        )?;
+
+        signals.push(
+            *outputs
+                .first()
+                .ok_or("The amplifier did not output anything")?,
+        );
+    }
+
+    Ok(signals)
+}
+
+/// The signals of the amplifiers in a feedback loop. See `run_feedback`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Feedback {
+    /// The last signal outputted by the last amplifier, to the thrusters.
+    pub signal: Cell,
+
+    /// The signals outputted by each amplifier (stage), round after round,
+    /// up to the number of rounds recorded.
+    pub signals: Vec<Vec<Cell>>,
+
+    /// The number of rounds run, recorded or not.
+    pub rounds: usize,
+}
+
+///
+/// Runs the amplifiers in a feedback loop, each one with its phase
+/// setting: the first one receives a 0 signal, then the signal of the last
+/// one, until they halt. The signals of the `max_rounds` first rounds are
+/// recorded.
+///
+/// ```rust
+/// # use lib::days::day07::run_feedback;
+/// let source_code = "3,26,1001,26,-4,26,3,27,1002,27,2,27,1,27,26,27,4,27,1001,28,-1,28,\
+///                    1005,28,6,99,0,0,5";
+/// let feedback = run_feedback(source_code, &[9, 8, 7, 6, 5], 2).unwrap();
+///
+/// assert_eq!(feedback.signal, 139629729);
+/// assert_eq!(feedback.rounds, 5);
+/// assert_eq!(
+///     feedback.signals,
+///     vec![
+///         vec![5, 263],
+///         vec![14, 530],
+///         vec![31, 1063],
+///         vec![64, 2128],
+///         vec![129, 4257],
+///     ]
+/// );
+///
+/// let feedback = run_feedback(source_code, &[9, 8, 7, 6, 5], 10).unwrap();
+/// assert_eq!(feedback.signals[4].len(), 5);
+/// assert_eq!(feedback.signals[4].last(), Some(&139629729));
+///
+/// assert!(run_feedback("99", &[9, 8, 7, 6, 5], 10).is_err());
+/// ```
+pub fn run_feedback(source_code: &str, phase_settings: &[Cell], max_rounds: usize) -> Result<Feedback> {
+    let mut amplifiers = phase_settings
+        .iter()
+        .map(|&setting| {
+            let mut program: Program = source_code.parse()?;
+            program.push_input(setting);
+            Ok(program)
         })
-    });
+        .collect::<Result<Vec<Program>>>()?;
+
+    let mut signals = vec![Vec::with_capacity(max_rounds.min(16)); amplifiers.len()];
+    let mut signal = 0;
+    let mut thrusters = None;
+    let mut rounds = 0;
 
     loop {
-        match program.execute_until_next_output() {
-            Ok(output) => {
-                // We ignore transmissions error as programs may run an extra, harmless, step
-                // and transmit to an already-closed thread. We don't care.
-                let _ = tx.send(output);
-
-                if !program.is_running() {
-                    break;
+        for (stage, amplifier) in amplifiers.iter_mut().enumerate() {
+            amplifier.push_input(signal);
+            signal = match amplifier.execute_until_next_output()? {
+                Some(output) => output,
+                None => {
+                    return Ok(Feedback {
+                        signal: thrusters
+                            .ok_or("The amplifiers did not output anything")?,
+                        signals,
+                        rounds,
+                    })
                 }
+            };
+
+            if rounds < max_rounds {
+                signals[stage].push(signal);
             }
-            Err(e) => panic!(e),
         }
+
+        thrusters = Some(signal);
+        rounds += 1;
     }
 }
 
-pub fn run() {
-    let source_code = Arc::new(input(7).first().expect("Empty source code").clone());
-
-    first_answer(
-        "Highest output signal",
-        &(0..5)
-            .permutations(5)
-            .map(|phase_setting_sequence| {
-                phase_setting_sequence
-                    .into_iter()
-                    .fold(0, |output_signal, setting| {
-                        let mut program: Program = Arc::clone(&source_code).parse().unwrap();
-                        program.set_input(move |n| match n {
-                            0 => Ok(setting.clone() as i64),
-                            1 => Ok(output_signal.clone() as i64),
-                            _ => Err(Error {
-                                message: "Too many inputs",
-                            }),
-                        });
-
-                        match program.execute() {
-                            Ok(output) => output[0],
-                            Err(e) => panic!(e), // too lazy for proper error handling
-                        }
-                    })
-            })
-            .max()
-            .unwrap(),
-    );
+/// The number of rounds of the feedback loop explained.
+const RECORDED_ROUNDS: usize = 5;
 
-    second_answer(
-        "Highest output signal with feedback loop",
-        &(5..10)
-            .permutations(5)
-            .map(|phase_setting_sequence| {
-                let (tx_input, rx_a) = channel();
-                let (tx_a, rx_b) = channel();
-                let (tx_b, rx_c) = channel();
-                let (tx_c, rx_d) = channel();
-                let (tx_d, rx_e) = channel();
-                let (tx_e, rx_output) = channel();
-
-                tx_input
-                    .send(phase_setting_sequence[0])
-                    .expect("Unable to send phase setting to amplifier A");
-                tx_a.send(phase_setting_sequence[1])
-                    .expect("Unable to send phase setting to amplifier B");
-                tx_b.send(phase_setting_sequence[2])
-                    .expect("Unable to send phase setting to amplifier C");
-                tx_c.send(phase_setting_sequence[3])
-                    .expect("Unable to send phase setting to amplifier D");
-                tx_d.send(phase_setting_sequence[4])
-                    .expect("Unable to send phase setting to amplifier E");
-
-                let amplifiers_threads: Vec<JoinHandle<_>> = vec![
-                    (rx_a, tx_a),
-                    (rx_b, tx_b),
-                    (rx_c, tx_c),
-                    (rx_d, tx_d),
-                    (rx_e, tx_e),
-                ]
-                .into_iter()
-                .map(|(rx, tx)| {
-                    let atomic_source_code = Arc::clone(&source_code);
-                    thread::spawn(move || run_amplifier(atomic_source_code, rx, tx))
-                })
-                .collect();
-
-                tx_input
-                    .send(0)
-                    .expect("Unable to send startup signal to first amplifier");
-
-                let mut output = 0;
-
-                while let Ok(amplifier_output) = rx_output.recv() {
-                    output = amplifier_output;
-
-                    // Same as in `run_amplifier`: we don't care if the transmission fails.
-                    let _ = tx_input.send(amplifier_output);
-                }
+pub const TITLE: &str = "Amplification Circuit";
 
-                for thread in amplifiers_threads {
-                    thread.join().expect("Unable to join ACS thread");
-                }
+pub const LABELS: [&str; 2] = [
+    "Highest output signal",
+    "Highest output signal with feedback loop",
+];
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let source_code = first_line(input)?;
 
-                output
-            })
-            .max()
-            .unwrap(),
+    let (highest_signal, winning_phase_settings, signals) = (0..5)
+        .permutations(5)
+        .map(|phase_settings| {
+            let signals = run_chain(source_code, &phase_settings)?;
+            Ok((signals[signals.len() - 1], phase_settings, signals))
+        })
+        .collect::<Result<Vec<_>>>()?
+        .into_iter()
+        .max()
+        .ok_or("No phase settings to try")?;
+
+    explain(
+        "Winning phase settings",
+        &winning_phase_settings.iter().join(","),
+    );
+    explain(
+        "Signals through the amplifiers",
+        &signals.iter().join(" → "),
     );
+
+    // Only the best feedback loop so far is kept, with its signals.
+    let mut best: Option<(Vec<Cell>, Feedback)> = None;
+    for phase_settings in (5..10).permutations(5) {
+        let feedback = run_feedback(source_code, &phase_settings, RECORDED_ROUNDS)?;
+        if best
+            .as_ref()
+            .is_none_or(|(_, best)| feedback.signal > best.signal)
+        {
+            best = Some((phase_settings, feedback));
+        }
+    }
+    let (winning_phase_settings, feedback) = best.ok_or("No phase settings to try")?;
+
+    explain(
+        "Winning phase settings with feedback loop",
+        &winning_phase_settings.iter().join(","),
+    );
+    for (stage, signals) in feedback.signals.iter().enumerate() {
+        let more = if signals.len() < feedback.rounds {
+            " → …"
+        } else {
+            ""
+        };
+        explain(
+            &format!("Signals of amplifier {}", (b'A' + stage as u8) as char),
+            &format!("{}{}", signals.iter().join(" → "), more),
+        );
+    }
+    explain("Feedback rounds", &feedback.rounds);
+
+    Ok((highest_signal.into(), feedback.signal.into()))
 }
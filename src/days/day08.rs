@@ -1,61 +1,94 @@
-use crate::{first_answer, input, second_answer};
-use itertools::Itertools;
-use std::fmt::{Display, Error, Formatter, Write};
+use crate::days::Capability;
+use crate::render::compose;
+use crate::{first_line, Value};
+use std::fmt::{Display, Error, Formatter};
 
 #[derive(Debug)]
-struct SpaceImage {
-    /// A list of layers.
-    /// Each layer is a list of pixels. Each list is width × height in size.
-    layers: Vec<Vec<u32>>,
+pub struct SpaceImage {
+    /// The pixels of all layers, one layer after the other.
+    /// Each layer is width × height in size, except maybe the last one.
+    data: Vec<u32>,
 
     width: usize,
     height: usize,
 }
 
 impl SpaceImage {
-    pub fn new(data: &String, width: usize, height: usize) -> Self {
+    pub fn new(data: &str, width: usize, height: usize) -> Self {
         SpaceImage {
-            layers: data
+            data: data
                 .chars()
                 .filter_map(|num_str| num_str.to_digit(10))
-                .chunks(width * height)
-                .into_iter()
-                .map(|layer| layer.into_iter().collect::<Vec<_>>())
                 .collect(),
             width,
             height,
         }
     }
 
-    /// Calculates the Space Image Checksum.
-    pub fn checksum(&self) -> usize {
-        let (ones, twos): (Vec<u32>, Vec<u32>) = self
-            .layers
-            .iter()
-            .map(|layer_pixels| {
-                (
-                    layer_pixels.clone(),
-                    layer_pixels
-                        .into_iter()
-                        .filter(|pixel| *pixel == &0)
-                        .count(),
-                )
+    /// Returns the layers of the image, as slices of pixels.
+    pub fn layers(&self) -> impl Iterator<Item = &[u32]> {
+        self.data.chunks(self.width * self.height)
+    }
+
+    ///
+    /// Counts the zeros, ones and twos of each layer, in one pass.
+    ///
+    /// ```rust
+    /// # use lib::days::day08::SpaceImage;
+    /// let image = SpaceImage::new("123456789012", 3, 2);
+    /// assert_eq!(image.layer_digit_counts(), vec![[0, 1, 1], [1, 1, 1]]);
+    /// ```
+    pub fn layer_digit_counts(&self) -> Vec<[usize; 3]> {
+        self.layers()
+            .map(|layer| {
+                let mut counts = [0; 3];
+                for pixel in layer {
+                    if let Some(count) = counts.get_mut(*pixel as usize) {
+                        *count += 1;
+                    }
+                }
+                counts
             })
-            .min_by(|(_, zeros_1), (_, zeros_2)| zeros_1.cmp(zeros_2))
-            .unwrap()
-            .0
-            .into_iter()
-            .filter(|n| *n == 1 || *n == 2)
-            .partition(|n| *n == 1);
+            .collect()
+    }
 
-        ones.len() * twos.len()
+    ///
+    /// Calculates the Space Image Checksum: the number of ones times the
+    /// number of twos, in the layer with the fewest zeros.
+    ///
+    /// ```rust
+    /// # use lib::days::day08::SpaceImage;
+    /// assert_eq!(SpaceImage::new("123456789012", 3, 2).checksum(), 1);
+    ///
+    /// // Checks a large pseudo-random image against a naive checksum.
+    /// let mut seed: u64 = 2019;
+    /// let data: String = (0..1000 * 20)
+    ///     .map(|_| {
+    ///         seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+    ///         std::char::from_digit(((seed >> 33) % 3) as u32, 10).unwrap()
+    ///     })
+    ///     .collect();
+    ///
+    /// let count = |layer: &[char], digit: char| layer.iter().filter(|c| **c == digit).count();
+    /// let chars: Vec<char> = data.chars().collect();
+    /// let layer = chars.chunks(20).min_by_key(|layer| count(layer, '0')).unwrap();
+    ///
+    /// assert_eq!(
+    ///     SpaceImage::new(&data, 5, 4).checksum(),
+    ///     count(layer, '1') * count(layer, '2')
+    /// );
+    /// ```
+    pub fn checksum(&self) -> usize {
+        self.layer_digit_counts()
+            .into_iter()
+            .min_by_key(|[zeros, _, _]| *zeros)
+            .map_or(0, |[_, ones, twos]| ones * twos)
     }
 
     pub fn pixels(&self) -> Vec<u32> {
-        (0..self.layers.first().unwrap().len())
+        (0..self.layers().next().unwrap().len())
             .map(|pixel| {
-                self.layers
-                    .iter()
+                self.layers()
                     .map(|layer| layer.get(pixel).unwrap_or(&2))
                     .cloned()
                     .fold(2, |final_pix, pix| match final_pix {
@@ -69,27 +102,25 @@ impl SpaceImage {
 
 impl Display for SpaceImage {
     fn fmt(&self, f: &mut Formatter<'_>) -> Result<(), Error> {
-        self.pixels()
-            .iter()
-            .chunks(self.width)
-            .into_iter()
-            .for_each(|line| {
-                line.into_iter()
-                    .map(|pixel| match pixel {
-                        1 => '×',
-                        _ => ' ',
-                    })
-                    .for_each(|pixel| f.write_char(pixel).expect("Cannot write space image pixel"));
-                f.write_str("\n").expect("Cannot write space image line");
-            });
+        let pixels = self.pixels();
 
-        Ok(())
+        f.write_str(&compose(self.width, self.height, |x, y| {
+            match pixels.get(y * self.width + x) {
+                Some(1) => '×',
+                _ => ' ',
+            }
+        }))
     }
 }
 
-pub fn run() {
-    let space_image = SpaceImage::new(input(8).first().unwrap(), 25, 6);
+pub const TITLE: &str = "Space Image Format";
+
+pub const CAPABILITIES: &[Capability] = &[Capability::Visual];
+
+pub const LABELS: [&str; 2] = ["Space Image Checksum", "Space Image"];
+
+pub fn solve(input: &str) -> crate::Result<(Value, Value)> {
+    let space_image = SpaceImage::new(first_line(input)?, 25, 6);
 
-    first_answer("Space Image Checksum", &space_image.checksum());
-    second_answer("Space Image", &format!("\n\n{}", space_image));
+    Ok((space_image.checksum().into(), space_image.to_string().into()))
 }
@@ -1,30 +1,23 @@
-use crate::intcode::Program;
-use crate::{first_answer, input, second_answer};
+use crate::intcode::{Cell, Program};
+use crate::{first_line, Result, Value};
 
-pub fn run() {
-    let source_code = input(9).get(0).expect("Invalid input").clone();
+pub const TITLE: &str = "Sensor Boost";
 
-    let mut test_boost_program: Program = source_code.parse().expect("Invalid BOOST program");
-    test_boost_program.set_input(move |_| Ok(1));
+pub const LABELS: [&str; 2] = ["BOOST keycode", "Coordinates of the distress signal"];
 
-    let mut sensor_boost_program: Program = source_code.parse().expect("Invalid BOOST program");
-    sensor_boost_program.set_input(move |_| Ok(2));
+/// Runs the BOOST program in the given mode, returning its first output.
+fn boost(source_code: &str, mode: Cell) -> Result<Cell> {
+    let mut program: Program = source_code.parse()?;
+    program.push_input(mode);
 
-    first_answer(
-        "BOOST keycode",
-        &test_boost_program
-            .execute()
-            .expect("Error while running BOOST program in test mode")
-            .get(0)
-            .unwrap(),
-    );
+    Ok(*program
+        .execute()?
+        .first()
+        .ok_or("The BOOST program did not output anything")?)
+}
+
+pub fn solve(input: &str) -> Result<(Value, Value)> {
+    let source_code = first_line(input)?;
 
-    second_answer(
-        "Coordinates of the distress signal",
-        &sensor_boost_program
-            .execute()
-            .expect("Error while running BOOST program in sensor mode")
-            .get(0)
-            .unwrap(),
-    )
+    Ok((boost(source_code, 1)?.into(), boost(source_code, 2)?.into()))
 }
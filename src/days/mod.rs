@@ -1,9 +1,247 @@
-pub mod day01;
-pub mod day02;
-pub mod day03;
-pub mod day04;
-pub mod day05;
-pub mod day06;
-pub mod day07;
-pub mod day08;
-pub mod day09;
+use crate::{Error, Result, Value};
+
+/// A solved day, as known by the registry.
+pub struct Day {
+    /// The day number, from 1 to 25.
+    pub number: u8,
+
+    /// The puzzle's title.
+    pub title: &'static str,
+
+    /// What the answers of both parts are.
+    pub labels: [&'static str; 2],
+
+    /// Solves the day from its input.
+    pub solve: fn(&str) -> Result<(Value, Value)>,
+
+    /// What the day can do besides printing its answers. Days declare
+    /// them in a `CAPABILITIES` constant; none if they don't.
+    pub capabilities: &'static [Capability],
+}
+
+/// Something a day can do besides printing its answers, for the tools
+/// listing the days.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Capability {
+    /// An answer is drawn, e.g. as text art.
+    Visual,
+
+    /// The solution reads from the terminal while it runs.
+    Interactive,
+}
+
+impl Capability {
+    /// Returns the name of the capability, as listed.
+    pub fn name(self) -> &'static str {
+        match self {
+            Capability::Visual => "visual",
+            Capability::Interactive => "interactive",
+        }
+    }
+}
+
+/// The inputs found for a day. See `list_text`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Inputs {
+    /// Whether the puzzle input is present.
+    pub input: bool,
+
+    /// The number of example inputs present.
+    pub examples: usize,
+}
+
+// The `dayNN` modules and the `DAYS` registry are generated from the files
+// in this directory by the build script.
+include!(concat!(env!("OUT_DIR"), "/days.rs"));
+
+///
+/// Finds a day in the registry from its number.
+///
+/// ```rust
+/// # use lib::days::{day, DAYS};
+/// assert!(DAYS.windows(2).all(|days| days[0].number < days[1].number));
+/// assert_eq!(day(6).map(|day| day.title), Some("Universal Orbit Map"));
+/// assert!(day(0).is_none());
+/// ```
+pub fn day(number: u8) -> Option<&'static Day> {
+    DAYS.iter().find(|day| day.number == number)
+}
+
+///
+/// Parses a selection of days, as a comma-separated list of days and
+/// ranges of days. Ranges can be open-ended (`12-` is days 12 to 25).
+/// Returns the days selected, in order, without duplicates.
+///
+/// ```rust
+/// # use lib::days::parse_selection;
+/// assert_eq!(parse_selection("4").unwrap(), vec![4]);
+/// assert_eq!(parse_selection("1-6,9").unwrap(), vec![1, 2, 3, 4, 5, 6, 9]);
+/// assert_eq!(parse_selection("9, 2-3,3,2").unwrap(), vec![2, 3, 9]);
+/// assert_eq!(parse_selection("22-").unwrap(), vec![22, 23, 24, 25]);
+/// assert_eq!(parse_selection("5-5").unwrap(), vec![5]);
+///
+/// assert_eq!(parse_selection("").unwrap_err().message, "Invalid day: \"\"");
+/// assert_eq!(parse_selection("1,,2").unwrap_err().message, "Invalid day: \"\"");
+/// assert_eq!(parse_selection("three").unwrap_err().message, "Invalid day: \"three\"");
+/// assert_eq!(parse_selection("0").unwrap_err().message, "Invalid day: \"0\"");
+/// assert_eq!(parse_selection("26").unwrap_err().message, "Invalid day: \"26\"");
+/// assert_eq!(parse_selection("-3").unwrap_err().message, "Invalid day: \"\"");
+/// assert_eq!(parse_selection("6-2").unwrap_err().message, "Invalid range: \"6-2\"");
+/// ```
+pub fn parse_selection(selection: &str) -> Result<Vec<u8>> {
+    let day = |day: &str| match day.trim().parse::<u8>() {
+        Ok(number) if (1..=25).contains(&number) => Ok(number),
+        _ => Err(Error {
+            message: format!("Invalid day: {:?}", day.trim()),
+        }),
+    };
+
+    let mut days = vec![];
+
+    for item in selection.split(',') {
+        match item.split_once('-') {
+            Some((first, last)) => {
+                let first = day(first)?;
+                let last = if last.trim().is_empty() {
+                    25
+                } else {
+                    day(last)?
+                };
+
+                if first > last {
+                    return Err(Error {
+                        message: format!("Invalid range: {:?}", item.trim()),
+                    });
+                }
+
+                days.extend(first..=last);
+            }
+            None => days.push(day(item)?),
+        }
+    }
+
+    days.sort();
+    days.dedup();
+
+    Ok(days)
+}
+
+/// Returns the names of the capabilities of a day.
+fn capability_names(day: &Day) -> Vec<&'static str> {
+    day.capabilities
+        .iter()
+        .map(|capability| capability.name())
+        .collect()
+}
+
+///
+/// Lists the given days as an aligned text table, one row per day, with
+/// the inputs found for them.
+///
+/// ```rust
+/// # use crate::lib::days::{list_text, Capability, Day, Inputs};
+/// # use crate::lib::Value;
+/// let solve = |_: &str| Ok((Value::Number(0), Value::Number(0)));
+/// let days = [
+///     Day { number: 1, title: "First", labels: ["", ""], solve, capabilities: &[] },
+///     Day {
+///         number: 42,
+///         title: "Another one",
+///         labels: ["", ""],
+///         solve,
+///         capabilities: &[Capability::Visual, Capability::Interactive],
+///     },
+/// ];
+///
+/// let table = list_text(&days, |day| Inputs { input: true, examples: day.number as usize / 10 });
+/// assert_eq!(
+///     table.lines().collect::<Vec<&str>>(),
+///     vec![
+///         "Day  Title        Input  Examples  Capabilities",
+///         "  1  First        yes           0",
+///         " 42  Another one  yes           4  visual, interactive",
+///     ]
+/// );
+/// ```
+pub fn list_text(days: &[Day], inputs: impl Fn(&Day) -> Inputs) -> String {
+    let title_width = days
+        .iter()
+        .map(|day| day.title.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("Title".len());
+
+    let mut table = format!(
+        "Day  {:title_width$}  Input  Examples  Capabilities\n",
+        "Title",
+        title_width = title_width
+    );
+
+    days.iter().for_each(|day| {
+        let inputs = inputs(day);
+        let row = format!(
+            "{:>3}  {:title_width$}  {:5}  {:>8}  {}",
+            day.number,
+            day.title,
+            if inputs.input { "yes" } else { "no" },
+            inputs.examples,
+            capability_names(day).join(", "),
+            title_width = title_width
+        );
+        table.push_str(row.trim_end());
+        table.push('\n');
+    });
+
+    table
+}
+
+///
+/// Lists the given days as a JSON array, one object per day. The keys
+/// and their order are stable, so the output can be consumed by scripts.
+///
+/// ```rust
+/// # use crate::lib::days::{list_json, Capability, Day, Inputs};
+/// # use crate::lib::Value;
+/// let solve = |_: &str| Ok((Value::Number(0), Value::Number(0)));
+/// let days = [
+///     Day { number: 1, title: "The \"first\" day", labels: ["", ""], solve, capabilities: &[] },
+///     Day {
+///         number: 2,
+///         title: "Second",
+///         labels: ["", ""],
+///         solve,
+///         capabilities: &[Capability::Visual],
+///     },
+/// ];
+///
+/// assert_eq!(
+///     list_json(&days, |day| Inputs { input: day.number == 1, examples: 3 }),
+///     "[{\"day\":1,\"title\":\"The \\\"first\\\" day\",\"input\":true,\"examples\":3,\
+///        \"capabilities\":[]},\
+///       {\"day\":2,\"title\":\"Second\",\"input\":false,\"examples\":3,\
+///        \"capabilities\":[\"visual\"]}]"
+/// );
+/// ```
+pub fn list_json(days: &[Day], inputs: impl Fn(&Day) -> Inputs) -> String {
+    format!(
+        "[{}]",
+        days.iter()
+            .map(|day| {
+                let inputs = inputs(day);
+                format!(
+                    "{{\"day\":{},\"title\":\"{}\",\"input\":{},\"examples\":{},\"capabilities\":[{}]}}",
+                    day.number,
+                    day.title.replace('\\', "\\\\").replace('"', "\\\""),
+                    inputs.input,
+                    inputs.examples,
+                    capability_names(day)
+                        .iter()
+                        .map(|name| format!("\"{}\"", name))
+                        .collect::<Vec<String>>()
+                        .join(",")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",")
+    )
+}
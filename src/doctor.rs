@@ -0,0 +1,188 @@
+//! Checks on the environment the solutions are run in, for the `doctor`
+//! command: each check reports a status and, if something is wrong, a
+//! hint to fix it.
+
+use crate::days::Day;
+use std::fmt::{self, Display, Formatter};
+
+/// The outcome of a check.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+
+    /// Something may not work as expected, but the solutions can run.
+    Warn,
+
+    /// The solutions can't run.
+    Fail,
+}
+
+/// A check on the environment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub message: String,
+
+    /// How to fix the problem, if any.
+    pub hint: Option<&'static str>,
+}
+
+impl Display for Check {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let status = match self.status {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+
+        write!(f, "[{}] {}: {}", status, self.name, self.message)?;
+
+        match self.hint {
+            Some(hint) => write!(f, "\n       {}", hint),
+            None => Ok(()),
+        }
+    }
+}
+
+///
+/// Checks that the input directory is found from the working directory,
+/// and which days have their input file.
+///
+/// ```rust
+/// # use lib::days::Day;
+/// # use lib::doctor::{check_inputs, Status};
+/// # use lib::Value;
+/// let solve = |_: &str| Ok((Value::Number(0), Value::Number(0)));
+/// let days = [
+///     Day { number: 1, title: "", labels: ["", ""], solve, capabilities: &[] },
+///     Day { number: 2, title: "", labels: ["", ""], solve, capabilities: &[] },
+///     Day { number: 3, title: "", labels: ["", ""], solve, capabilities: &[] },
+/// ];
+///
+/// assert_eq!(check_inputs(&days, true, |_| true).status, Status::Pass);
+///
+/// let check = check_inputs(&days, true, |day| day.number == 2);
+/// assert_eq!(check.status, Status::Warn);
+/// assert_eq!(check.message, "no input file for days 1, 3");
+///
+/// assert_eq!(check_inputs(&days, false, |_| false).status, Status::Fail);
+/// ```
+pub fn check_inputs(
+    days: &[Day],
+    input_dir_found: bool,
+    has_input: impl Fn(&Day) -> bool,
+) -> Check {
+    let missing: Vec<String> = days
+        .iter()
+        .filter(|day| !has_input(day))
+        .map(|day| day.number.to_string())
+        .collect();
+
+    let (status, message, hint) = if !input_dir_found {
+        (
+            Status::Fail,
+            "input directory not found".to_string(),
+            Some("run from the repository root, where the input directory is"),
+        )
+    } else if missing.is_empty() {
+        (
+            Status::Pass,
+            format!("all {} days have an input file", days.len()),
+            None,
+        )
+    } else {
+        (
+            Status::Warn,
+            format!("no input file for days {}", missing.join(", ")),
+            Some("save the puzzle inputs as input/day-N.txt"),
+        )
+    };
+
+    Check {
+        name: "Inputs",
+        status,
+        message,
+        hint,
+    }
+}
+
+/// Checks if the output is a terminal.
+pub fn check_terminal(is_terminal: bool) -> Check {
+    Check {
+        name: "Terminal",
+        status: if is_terminal {
+            Status::Pass
+        } else {
+            Status::Warn
+        },
+        message: if is_terminal {
+            "the output is a terminal".to_string()
+        } else {
+            "the output is not a terminal".to_string()
+        },
+        hint: Some("day 8's answer is an image drawn with characters, best read in a terminal")
+            .filter(|_| !is_terminal),
+    }
+}
+
+///
+/// Reports the compile-time features enabled.
+///
+/// ```rust
+/// # use lib::doctor::check_features;
+/// assert_eq!(check_features(&[]).message, "default features only");
+/// assert_eq!(check_features(&["wide-cells"]).message, "enabled: wide-cells");
+/// assert_eq!(
+///     check_features(&["wide-cells", "serde"]).message,
+///     "enabled: wide-cells, serde"
+/// );
+/// ```
+pub fn check_features(enabled: &[&str]) -> Check {
+    Check {
+        name: "Features",
+        status: Status::Pass,
+        message: if enabled.is_empty() {
+            "default features only".to_string()
+        } else {
+            format!("enabled: {}", enabled.join(", "))
+        },
+        hint: None,
+    }
+}
+
+/// Reports the version of the compiler the solutions were built with.
+pub fn check_toolchain(rustc_version: &str) -> Check {
+    Check {
+        name: "Toolchain",
+        status: if rustc_version == "unknown" {
+            Status::Warn
+        } else {
+            Status::Pass
+        },
+        message: format!("built with {}", rustc_version),
+        hint: Some("the version could not be read by the build script")
+            .filter(|_| rustc_version == "unknown"),
+    }
+}
+
+/// Runs all the checks, for this build and the current terminal.
+pub fn checks(days: &[Day], input_dir_found: bool, has_input: impl Fn(&Day) -> bool) -> Vec<Check> {
+    use std::io::IsTerminal;
+
+    let features: Vec<&str> = [
+        ("wide-cells", cfg!(feature = "wide-cells")),
+        ("serde", cfg!(feature = "serde")),
+    ]
+    .iter()
+    .filter(|(_, enabled)| *enabled)
+    .map(|(feature, _)| *feature)
+    .collect();
+
+    vec![
+        check_inputs(days, input_dir_found, has_input),
+        check_terminal(std::io::stdout().is_terminal()),
+        check_features(&features),
+        check_toolchain(env!("RUSTC_VERSION")),
+    ]
+}
@@ -0,0 +1,116 @@
+//! Creation of the directories the solutions expect, for the `init`
+//! command: on a fresh clone, it tells where the puzzle inputs go.
+
+use crate::days::Day;
+use crate::intcode::catalog::STAND_INS;
+use std::fmt::{self, Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// The directories created, relative to the root.
+pub const DIRECTORIES: [&str; 4] = ["input", "input/examples", "answers", "output"];
+
+/// A step of the initialization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    CreateDir(PathBuf),
+    WriteFile(PathBuf, String),
+}
+
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::CreateDir(path) => write!(f, "create {}/", path.display()),
+            Action::WriteFile(path, _) => write!(f, "write {}", path.display()),
+        }
+    }
+}
+
+/// Lists the input files expected for the given days.
+fn placeholders(days: &[Day]) -> String {
+    let mut text = String::from("Save the puzzle inputs in this directory, as:\n\n");
+
+    for day in days {
+        text.push_str(&format!("day-{}.txt  {}\n", day.number, day.title));
+    }
+
+    text
+}
+
+///
+/// Plans the initialization of the given root directory: the directories
+/// and files missing, so nothing existing is overwritten. With `examples`,
+/// the stand-in inputs of the Intcode days are copied to `input/examples`.
+///
+/// ```rust
+/// # use lib::days::DAYS;
+/// # use lib::init::{apply, plan, Action};
+/// let root = std::env::temp_dir().join(format!("aoc-2019-init-{}", std::process::id()));
+///
+/// let actions = plan(&root, &DAYS, true);
+/// assert_eq!(actions[0], Action::CreateDir(root.join("input")));
+/// assert_eq!(actions[4].to_string(), format!("write {}", root.join("input/README-placeholders.txt").display()));
+/// assert_eq!(actions.len(), 9);
+///
+/// apply(&actions).unwrap();
+/// assert!(root.join("input/examples/day-9.txt").is_file());
+/// assert!(plan(&root, &DAYS, true).is_empty());
+///
+/// // Existing files are kept.
+/// std::fs::write(root.join("input/README-placeholders.txt"), "mine").unwrap();
+/// std::fs::remove_dir(root.join("output")).unwrap();
+/// assert_eq!(plan(&root, &DAYS, true), vec![Action::CreateDir(root.join("output"))]);
+///
+/// apply(&plan(&root, &DAYS, true)).unwrap();
+/// assert_eq!(std::fs::read_to_string(root.join("input/README-placeholders.txt")).unwrap(), "mine");
+/// # std::fs::remove_dir_all(&root).unwrap();
+/// ```
+pub fn plan(root: &Path, days: &[Day], examples: bool) -> Vec<Action> {
+    let mut actions: Vec<Action> = DIRECTORIES
+        .iter()
+        .map(|directory| root.join(directory))
+        .filter(|path| !path.exists())
+        .map(Action::CreateDir)
+        .collect();
+
+    let mut files = vec![(
+        root.join("input/README-placeholders.txt"),
+        placeholders(days),
+    )];
+
+    if examples {
+        files.extend(STAND_INS.iter().map(|(day, source_code)| {
+            (
+                root.join(format!("input/examples/day-{}.txt", day)),
+                format!("{}\n", source_code.trim()),
+            )
+        }));
+    }
+
+    actions.extend(
+        files
+            .into_iter()
+            .filter(|(path, _)| !path.exists())
+            .map(|(path, content)| Action::WriteFile(path, content)),
+    );
+
+    actions
+}
+
+/// Applies the planned actions, in order. Files are only created, so a
+/// file created in the meantime is never overwritten.
+pub fn apply(actions: &[Action]) -> io::Result<()> {
+    for action in actions {
+        match action {
+            Action::CreateDir(path) => fs::create_dir_all(path)?,
+            Action::WriteFile(path, content) => OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)?
+                .write_all(content.as_bytes())?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,199 @@
+//! Checks on puzzle inputs, to catch broken downloads before the days
+//! fail to parse them.
+
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Where an input comes from.
+pub enum InputSource {
+    /// A puzzle input file.
+    File(PathBuf),
+
+    /// An input given as is, e.g. on the command line.
+    Literal(String),
+
+    /// An input generated when read, e.g. for stress tests, so it never
+    /// has to be stored.
+    Generated(Box<dyn Fn() -> String>),
+}
+
+impl InputSource {
+    ///
+    /// Reads the input. Files which are not valid UTF-8 are read lossily,
+    /// so `sanitize` can reject them.
+    ///
+    /// ```rust
+    /// # use lib::input::{sanitize, InputSource};
+    /// let literal = InputSource::Literal("12\n14\n".to_string());
+    /// assert_eq!(literal.read().unwrap(), "12\n14\n");
+    ///
+    /// let generated = InputSource::Generated(Box::new(|| "1,".repeat(10_000) + "99"));
+    /// let input = generated.read().unwrap();
+    /// assert_eq!(sanitize(&input).unwrap().text.len(), 20_002);
+    ///
+    /// let missing = InputSource::File("input/day-0.txt".into());
+    /// assert!(missing.read().is_err());
+    /// ```
+    pub fn read(&self) -> io::Result<String> {
+        match self {
+            InputSource::File(path) => {
+                fs::read(path).map(|raw| String::from_utf8_lossy(&raw).into_owned())
+            }
+            InputSource::Literal(input) => Ok(input.clone()),
+            InputSource::Generated(generate) => Ok(generate()),
+        }
+    }
+}
+
+impl Display for InputSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            InputSource::File(path) => write!(f, "input file {}", path.display()),
+            InputSource::Literal(_) => write!(f, "literal input"),
+            InputSource::Generated(_) => write!(f, "generated input"),
+        }
+    }
+}
+
+/// Why an input can't be used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputProblem {
+    /// The input is empty or only made of whitespace, e.g. after a failed
+    /// download.
+    Empty,
+
+    /// The input is a web page, e.g. the puzzle page saved instead of the
+    /// raw input.
+    Html,
+
+    /// The input contains NUL bytes, so it's probably a binary file.
+    NulByte,
+
+    /// The input was not valid UTF-8 when read (lossily) from the file.
+    InvalidUtf8,
+
+    /// The input is a comma-separated list ending with a comma.
+    Truncated,
+}
+
+impl Display for InputProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InputProblem::Empty => "the input is empty, its download may have failed",
+                InputProblem::Html => "the input looks like HTML, not the raw puzzle input",
+                InputProblem::NulByte => "the input contains NUL bytes",
+                InputProblem::InvalidUtf8 => "the input is not valid UTF-8",
+                InputProblem::Truncated =>
+                    "the input ends in the middle of a comma-separated list, it may be truncated",
+            }
+        )
+    }
+}
+
+/// A sanitized input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sanitized<'a> {
+    /// The input, with Unix line endings.
+    pub text: Cow<'a, str>,
+
+    /// Whether the input had Windows line endings, which were normalized.
+    pub windows_line_endings: bool,
+}
+
+///
+/// Checks that an input looks like a raw puzzle input, and normalizes its
+/// line endings.
+///
+/// ```rust
+/// # use lib::input::{sanitize, InputProblem};
+/// let sanitized = sanitize("1,2,3\n").unwrap();
+/// assert_eq!(sanitized.text, "1,2,3\n");
+/// assert!(!sanitized.windows_line_endings);
+/// ```
+///
+/// Empty inputs are rejected, including those only made of whitespace:
+///
+/// ```rust
+/// # use lib::input::{sanitize, InputProblem};
+/// assert_eq!(sanitize("").unwrap_err(), InputProblem::Empty);
+/// assert_eq!(sanitize("\n\n \r\n").unwrap_err(), InputProblem::Empty);
+/// ```
+///
+/// Web pages are rejected:
+///
+/// ```rust
+/// # use lib::input::{sanitize, InputProblem};
+/// assert_eq!(sanitize("<!DOCTYPE html>\n<html>").unwrap_err(), InputProblem::Html);
+/// assert_eq!(sanitize("  <html lang=\"en-us\">").unwrap_err(), InputProblem::Html);
+/// ```
+///
+/// So are binary files, and files which were not UTF-8 (the invalid bytes
+/// being replaced when read):
+///
+/// ```rust
+/// # use lib::input::{sanitize, InputProblem};
+/// assert_eq!(sanitize("12\0\n14\n").unwrap_err(), InputProblem::NulByte);
+///
+/// let raw = String::from_utf8_lossy(b"12\n\xff14\n");
+/// assert_eq!(sanitize(&raw).unwrap_err(), InputProblem::InvalidUtf8);
+/// ```
+///
+/// And comma-separated lists ending with a comma:
+///
+/// ```rust
+/// # use lib::input::{sanitize, InputProblem};
+/// assert_eq!(sanitize("1,9,10,3,\n\n").unwrap_err(), InputProblem::Truncated);
+/// ```
+///
+/// Windows line endings are only normalized:
+///
+/// ```rust
+/// # use lib::input::sanitize;
+/// let sanitized = sanitize("COM)B\r\nB)C\r\n").unwrap();
+/// assert_eq!(sanitized.text, "COM)B\nB)C\n");
+/// assert!(sanitized.windows_line_endings);
+/// ```
+pub fn sanitize(raw: &str) -> Result<Sanitized<'_>, InputProblem> {
+    if raw.trim().is_empty() {
+        return Err(InputProblem::Empty);
+    }
+
+    let start = raw
+        .trim_start()
+        .chars()
+        .take("<!doctype".len())
+        .collect::<String>()
+        .to_lowercase();
+    if start.starts_with("<!doctype") || start.starts_with("<html") {
+        return Err(InputProblem::Html);
+    }
+
+    if raw.contains('\0') {
+        return Err(InputProblem::NulByte);
+    }
+
+    if raw.contains(char::REPLACEMENT_CHARACTER) {
+        return Err(InputProblem::InvalidUtf8);
+    }
+
+    if raw.trim_end().ends_with(',') {
+        return Err(InputProblem::Truncated);
+    }
+
+    let windows_line_endings = raw.contains("\r\n");
+
+    Ok(Sanitized {
+        text: if windows_line_endings {
+            Cow::Owned(raw.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(raw)
+        },
+        windows_line_endings,
+    })
+}
@@ -0,0 +1,318 @@
+//! Control-flow graphs of programs, rendered for Graphviz, to understand
+//! the structure of large programs.
+//!
+//! The instructions are decoded from address 0, following the jumps whose
+//! target is known without running the program (immediate targets), as
+//! `Program::validate` does. They are grouped in basic blocks: runs of
+//! instructions entered only by their first one, and ending with a jump or
+//! a halt instruction, or before another block. Jumps into data or out of
+//! the memory have no edge.
+
+use super::disasm::{self, Line};
+use super::{Cell, JumpCondition, OpCode, ParameterMode};
+use std::collections::{BTreeMap, BTreeSet};
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+
+/// A run of instructions always executed from first to last.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// The addresses of the block, from its first instruction to the end
+    /// of the last one.
+    pub addresses: Range<usize>,
+
+    /// The instructions of the block, as listed by the disassembler, with
+    /// their address.
+    pub instructions: Vec<(usize, String)>,
+}
+
+/// Where an edge of a control-flow graph leads.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// The block starting at this address.
+    Block(usize),
+
+    /// An address only known when running, read from memory by a jump.
+    Computed,
+}
+
+/// How an edge of a control-flow graph is taken.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// The execution continues after the last instruction of the block.
+    FallThrough,
+
+    /// The last instruction of the block jumps.
+    Jump,
+}
+
+/// A way from the end of a block, starting at `from`, to somewhere else.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub from: usize,
+    pub to: Target,
+    pub kind: EdgeKind,
+}
+
+/// A control-flow graph, displayed as Graphviz DOT source. See
+/// `control_flow_graph`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dot {
+    /// The blocks, by address.
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+}
+
+impl Dot {
+    /// Writes the graph to a file, as DOT source.
+    pub fn to_dot_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl Display for Dot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "digraph {{")?;
+        writeln!(f, "    node [shape=box, fontname=monospace];")?;
+
+        for block in &self.blocks {
+            write!(f, "    b{} [label=\"", block.addresses.start)?;
+            for (address, instruction) in &block.instructions {
+                write!(f, "{}: {}\\l", address, instruction)?;
+            }
+            writeln!(f, "\"];")?;
+        }
+
+        for edge in &self.edges {
+            let kind = match edge.kind {
+                EdgeKind::FallThrough => "",
+                EdgeKind::Jump => " [label=\"jump\"]",
+            };
+
+            match edge.to {
+                Target::Block(to) => writeln!(f, "    b{} -> b{}{};", edge.from, to, kind)?,
+                Target::Computed => {
+                    writeln!(
+                        f,
+                        "    computed{} [label=\"computed\", shape=ellipse];",
+                        edge.from
+                    )?;
+                    writeln!(f, "    b{} -> computed{}{};", edge.from, edge.from, kind)?;
+                }
+            }
+        }
+
+        writeln!(f, "}}")
+    }
+}
+
+/// The ways out of an instruction: the next one, and where it jumps.
+struct Successors {
+    next: Option<usize>,
+    jump: Option<Target>,
+}
+
+/// Returns the ways out of the instruction at the given address.
+fn successors(address: usize, line: &Line, length: usize) -> Successors {
+    let next = Some(address + length);
+
+    match line {
+        Line::Instruction {
+            opcode: OpCode::Halt,
+            ..
+        } => Successors {
+            next: None,
+            jump: None,
+        },
+        Line::Instruction {
+            opcode: OpCode::Jump(condition),
+            parameters,
+        } => {
+            let taken = match parameters[0] {
+                (ParameterMode::Immediate, value) => Some(match condition {
+                    JumpCondition::IfTrue => value != 0,
+                    JumpCondition::IfFalse => value == 0,
+                }),
+                _ => None,
+            };
+
+            let target = match parameters[1] {
+                (ParameterMode::Immediate, target) => {
+                    usize::try_from(target).ok().map(Target::Block)
+                }
+                _ => Some(Target::Computed),
+            };
+
+            Successors {
+                next: next.filter(|_| taken != Some(true)),
+                jump: target.filter(|_| taken != Some(false)),
+            }
+        }
+        _ => Successors { next, jump: None },
+    }
+}
+
+///
+/// Builds the control-flow graph of a program. See the module
+/// documentation.
+///
+/// ```rust
+/// # use lib::intcode::analysis::{control_flow_graph, Edge, EdgeKind, Target};
+/// # use lib::intcode::asm::assemble;
+/// // Counts down from its input.
+/// let memory = assemble(
+///     "       in [n]
+///      loop:  jz [n], end
+///             out [n]
+///             add [n], #-1, [n]
+///             jnz #1, loop
+///      end:   hlt
+///      n:     data 0",
+/// )
+/// .unwrap();
+///
+/// let graph = control_flow_graph(&memory);
+/// assert_eq!(
+///     graph.blocks.iter().map(|block| block.addresses.clone()).collect::<Vec<_>>(),
+///     vec![0..2, 2..5, 5..14, 14..15]
+/// );
+/// assert_eq!(graph.blocks[2].instructions[1], (7, "add [15], #-1, [15]".to_string()));
+///
+/// let edge = |from, to, kind| Edge { from, to: Target::Block(to), kind };
+/// assert_eq!(
+///     graph.edges,
+///     vec![
+///         edge(0, 2, EdgeKind::FallThrough),
+///         edge(2, 5, EdgeKind::FallThrough),
+///         edge(2, 14, EdgeKind::Jump),
+///         edge(5, 2, EdgeKind::Jump),
+///     ]
+/// );
+///
+/// assert_eq!(
+///     graph.to_string(),
+///     "digraph {
+///     node [shape=box, fontname=monospace];
+///     b0 [label=\"0: in [15]\\l\"];
+///     b2 [label=\"2: jz [15], #14\\l\"];
+///     b5 [label=\"5: out [15]\\l7: add [15], #-1, [15]\\l11: jnz #1, #2\\l\"];
+///     b14 [label=\"14: hlt\\l\"];
+///     b0 -> b2;
+///     b2 -> b5;
+///     b2 -> b14 [label=\"jump\"];
+///     b5 -> b2 [label=\"jump\"];
+/// }
+/// "
+/// );
+///
+/// // A jump to an address read from memory.
+/// let graph = control_flow_graph(&[5, 3, 3, 99]);
+/// assert_eq!(
+///     graph.edges,
+///     vec![
+///         Edge { from: 0, to: Target::Block(3), kind: EdgeKind::FallThrough },
+///         Edge { from: 0, to: Target::Computed, kind: EdgeKind::Jump },
+///     ]
+/// );
+/// assert!(graph.to_string().contains("computed0 [label=\"computed\", shape=ellipse];"));
+/// ```
+pub fn control_flow_graph(memory: &[Cell]) -> Dot {
+    // The instructions reachable from the start, by address.
+    let mut instructions = BTreeMap::new();
+    let mut leaders = BTreeSet::new();
+    let mut pending = vec![0];
+    leaders.insert(0);
+
+    while let Some(address) = pending.pop() {
+        if instructions.contains_key(&address) {
+            continue;
+        }
+
+        let (line, length) = match memory.get(address..).and_then(disasm::decode) {
+            Some(instruction) => instruction,
+            None => continue,
+        };
+
+        let successors = successors(address, &line, length);
+        let is_jump = matches!(
+            line,
+            Line::Instruction {
+                opcode: OpCode::Jump(_),
+                ..
+            }
+        );
+
+        if let Some(next) = successors.next {
+            if is_jump {
+                leaders.insert(next);
+            }
+            pending.push(next);
+        }
+        if let Some(Target::Block(target)) = successors.jump {
+            leaders.insert(target);
+            pending.push(target);
+        }
+
+        instructions.insert(address, (line, length));
+    }
+
+    let mut blocks = vec![];
+    let mut edges = vec![];
+
+    for start in leaders
+        .iter()
+        .filter(|address| instructions.contains_key(address))
+    {
+        let mut block = BasicBlock {
+            addresses: *start..*start,
+            instructions: vec![],
+        };
+        let mut address = *start;
+
+        let successors = loop {
+            let (line, length) = &instructions[&address];
+            block.instructions.push((address, line.to_listing(None)));
+            block.addresses.end = address + length;
+
+            let successors = successors(address, line, *length);
+            match successors.next {
+                Some(next)
+                    if successors.jump.is_none()
+                        && !leaders.contains(&next)
+                        && instructions.contains_key(&next) =>
+                {
+                    address = next
+                }
+                _ => break successors,
+            }
+        };
+
+        if let Some(next) = successors
+            .next
+            .filter(|next| instructions.contains_key(next))
+        {
+            edges.push(Edge {
+                from: *start,
+                to: Target::Block(next),
+                kind: EdgeKind::FallThrough,
+            });
+        }
+        match successors.jump {
+            Some(Target::Block(target)) if !instructions.contains_key(&target) => {}
+            Some(to) => edges.push(Edge {
+                from: *start,
+                to,
+                kind: EdgeKind::Jump,
+            }),
+            None => {}
+        }
+
+        blocks.push(block);
+    }
+
+    Dot { blocks, edges }
+}
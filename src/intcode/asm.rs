@@ -0,0 +1,306 @@
+//! An assembler, the inverse of the disassembler (see `disasm`), to write
+//! programs without counting offsets by hand.
+//!
+//! Each line holds an instruction: a mnemonic (`add`, `mul`, `in`, `out`,
+//! `jnz`, `jz`, `lt`, `eq`, `arb`, `hlt`) followed by its parameters,
+//! separated by commas and written `[5]` in position mode, `#3` in
+//! immediate mode and `~-2` in relative mode. The `data` directive stores
+//! raw cells, e.g. `data 1, 2, 3`. Comments start with `;`.
+//!
+//! Lines may start with labels (`loop:`), naming the address of their
+//! instruction, or of their first data cell. A label can be used in place
+//! of any number, before or after its definition: `[x]` is the cell at the
+//! label `x`, and a label alone is its address, in immediate mode (e.g.
+//! `jnz [x], loop`). Labels start with a letter or `_`, followed by
+//! letters, digits and `_`.
+//!
+//! A line may also start with the address of its instruction, as listed by
+//! the disassembler (`12: out [7]`); it must match where the instruction
+//! is.
+//!
+//! ```rust
+//! # use lib::intcode::asm::assemble;
+//! # use lib::intcode::disasm::disassemble;
+//! # use lib::intcode::Program;
+//! let memory = assemble(
+//!     "; Outputs the sum of two inputs.
+//!      in [11]
+//!      in [12]
+//!      add [11], [12], [13]  ; Both inputs.
+//!      out [13]
+//!      hlt
+//!      data 0, 0, 0",
+//! )
+//! .unwrap();
+//! assert_eq!(memory, vec![3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0]);
+//!
+//! // The disassembler's listings can be assembled again.
+//! assert_eq!(assemble(&disassemble(&memory)).unwrap(), memory);
+//!
+//! let mut program = Program::from_memory(memory);
+//! program.push_inputs(vec![40, 2]);
+//! assert_eq!(program.execute().unwrap(), vec![42]);
+//! ```
+//!
+//! With labels:
+//!
+//! ```rust
+//! # use lib::intcode::asm::assemble;
+//! # use lib::intcode::disasm::{disassemble_with, Labels};
+//! # use lib::intcode::Program;
+//! // Counts down from its input, then outputs the targets of its jumps.
+//! let source = "
+//!             in [n]
+//!     loop:   jz [n], end         ; Forward reference.
+//!             out [n]
+//!             add [n], #-1, [n]
+//!             jnz #1, loop        ; Backward reference.
+//!     end:    out [targets]
+//!             out [last]
+//!             hlt
+//!     n:      data 0
+//!     targets: data loop
+//!     last:   data end";
+//!
+//! let memory = assemble(source).unwrap();
+//! let mut program = Program::from_memory(memory.clone());
+//! program.push_input(3);
+//! assert_eq!(program.execute().unwrap(), vec![3, 2, 1, 2, 14]);
+//!
+//! // Assembling the listings gives the same memory, and listing it again
+//! // gives the same listings.
+//! for labels in &[Labels::Addresses, Labels::JumpTargets] {
+//!     let listing = disassemble_with(&memory, *labels);
+//!     let reassembled = assemble(&listing).unwrap();
+//!     assert_eq!(reassembled, memory);
+//!     assert_eq!(disassemble_with(&reassembled, *labels), listing);
+//! }
+//! ```
+
+use super::{decode_opcode, disasm, Cell, Error, Result};
+use std::collections::HashMap;
+
+/// The value of an operand: a number, or the address of a label.
+enum Value<'s> {
+    Number(Cell),
+    Label(&'s str),
+}
+
+/// A cell holding the address of a label, set once all labels are known.
+struct Reference<'s> {
+    address: usize,
+    label: &'s str,
+
+    /// Where the label is used, for errors.
+    line: usize,
+    column: usize,
+}
+
+/// Returns the opcode and the number of parameters of a mnemonic.
+fn opcode(mnemonic: &str) -> Option<(Cell, usize)> {
+    (1..=9)
+        .chain(Some(99))
+        .find_map(|code| match decode_opcode(code) {
+            Some((opcode, parameters_count)) if disasm::mnemonic(opcode) == mnemonic => {
+                Some((code, parameters_count))
+            }
+            _ => None,
+        })
+}
+
+/// Returns the first word of the text after `start`, with its offset.
+fn word(text: &str, start: usize) -> Option<(usize, &str)> {
+    let rest = &text[start..];
+    let offset = start + rest.len() - rest.trim_start().len();
+    let word = text[offset..].split_whitespace().next()?;
+    Some((offset, word))
+}
+
+/// Splits the operands of the text after `start` on commas, trimmed, with
+/// their offset.
+fn operands(text: &str, start: usize) -> Vec<(usize, &str)> {
+    if text[start..].trim().is_empty() {
+        return vec![];
+    }
+
+    let mut offset = start;
+    text[start..]
+        .split(',')
+        .map(|operand| {
+            let trimmed = operand.trim();
+            let position = offset + operand.len() - operand.trim_start().len();
+            offset += operand.len() + 1;
+            (position, trimmed)
+        })
+        .collect()
+}
+
+/// Checks if the text is a valid label name.
+fn is_label(text: &str) -> bool {
+    let mut chars = text.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_ascii_alphabetic() || first == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Parses a number or a label.
+fn value(text: &str) -> std::result::Result<Value<'_>, &'static str> {
+    match text.trim() {
+        "" => Err("Missing operand"),
+        text if is_label(text) => Ok(Value::Label(text)),
+        text => text
+            .parse()
+            .map(Value::Number)
+            .map_err(|_| "Invalid number"),
+    }
+}
+
+/// Parses an operand into its mode and its value.
+fn operand(text: &str) -> std::result::Result<(Cell, Value<'_>), &'static str> {
+    let (mode, value_text) = match text.chars().next() {
+        Some('[') => match text.strip_suffix(']') {
+            Some(text) => (0, &text[1..]),
+            None => return Err("Expected a closing bracket"),
+        },
+        Some('#') => (1, &text[1..]),
+        Some('~') => (2, &text[1..]),
+        Some(_) if is_label(text) => (1, text),
+        Some(_) => return Err("Invalid operand: expected [n], #n, ~n or a label"),
+        None => return Err("Missing operand"),
+    };
+
+    value(value_text).map(|value| (mode, value))
+}
+
+///
+/// Assembles source code into the memory of a program, to load with
+/// `Program::from_memory`. See the module documentation for the syntax.
+///
+/// ```rust
+/// # use lib::intcode::asm::assemble;
+/// let error = |source: &str| {
+///     let error = assemble(source).unwrap_err();
+///     (error.message().to_string(), error.to_string())
+/// };
+///
+/// assert_eq!(
+///     error("in [5]\n  mov [1], [2]"),
+///     ("Unknown mnemonic".to_string(), "Unknown mnemonic at line 2, column 3".to_string())
+/// );
+/// assert_eq!(error("add [1], #2").0, "Wrong number of operands");
+/// assert_eq!(error("out [5").1, "Expected a closing bracket at line 1, column 5");
+/// assert_eq!(error("out 5").0, "Invalid operand: expected [n], #n, ~n or a label");
+/// assert_eq!(error("lt #1, #2x, [0]").1, "Invalid number at line 1, column 8");
+/// assert_eq!(error("add [1], , [2]").1, "Missing operand at line 1, column 10");
+/// assert_eq!(error("data 1, 2x").1, "Invalid number at line 1, column 9");
+/// assert_eq!(error("hlt\n2: hlt").1, "Address not matching the instruction at line 2, column 1");
+///
+/// assert_eq!(error("a: hlt\n  a: hlt").1, "Duplicate label at line 2, column 3");
+/// assert_eq!(error("jnz #1, nowhere\nhlt").1, "Undefined label at line 1, column 9");
+/// assert_eq!(error("x-1: hlt").1, "Invalid label at line 1, column 1");
+/// ```
+pub fn assemble(source: &str) -> Result<Vec<Cell>> {
+    let mut memory = vec![];
+    let mut labels: HashMap<&str, usize> = HashMap::new();
+    let mut references = vec![];
+
+    for (index, line) in source.lines().enumerate() {
+        let error = |offset: usize, message| Error::InvalidAssembly {
+            line: index + 1,
+            column: offset + 1,
+            message,
+        };
+
+        let code = line.split(';').next().unwrap_or("");
+        let (mut offset, mut mnemonic) = match word(code, 0) {
+            Some(word) => word,
+            None => continue,
+        };
+
+        // Addresses and labels, before the instruction.
+        while let Some(prefix) = mnemonic.strip_suffix(':') {
+            if prefix.starts_with(|c: char| c.is_ascii_digit()) {
+                if prefix.parse() != Ok(memory.len()) {
+                    return Err(error(offset, "Address not matching the instruction"));
+                }
+            } else if !is_label(prefix) {
+                return Err(error(offset, "Invalid label"));
+            } else if labels.insert(prefix, memory.len()).is_some() {
+                return Err(error(offset, "Duplicate label"));
+            }
+
+            match word(code, offset + mnemonic.len()) {
+                Some(word) => (offset, mnemonic) = word,
+                None => break,
+            }
+        }
+        if mnemonic.ends_with(':') {
+            continue;
+        }
+
+        let mut push = |offset: usize, value| match value {
+            Value::Number(value) => memory.push(value),
+            Value::Label(label) => {
+                references.push(Reference {
+                    address: memory.len(),
+                    label,
+                    line: index + 1,
+                    column: offset + 1,
+                });
+                memory.push(0);
+            }
+        };
+
+        let operands = operands(code, offset + mnemonic.len());
+
+        if mnemonic == "data" {
+            if operands.is_empty() {
+                return Err(error(offset, "Missing operand"));
+            }
+
+            for (offset, text) in operands {
+                push(
+                    offset,
+                    value(text).map_err(|message| error(offset, message))?,
+                );
+            }
+            continue;
+        }
+
+        let (mut code, parameters_count) =
+            opcode(mnemonic).ok_or_else(|| error(offset, "Unknown mnemonic"))?;
+        if operands.len() != parameters_count {
+            return Err(error(offset, "Wrong number of operands"));
+        }
+
+        let mut parameters = Vec::with_capacity(parameters_count);
+        let mut mode_unit = 100;
+        for (offset, text) in operands {
+            let (mode, value) = operand(text).map_err(|message| error(offset, message))?;
+            code += mode * mode_unit;
+            mode_unit *= 10;
+            parameters.push((offset, value));
+        }
+
+        push(offset, Value::Number(code));
+        for (offset, value) in parameters {
+            push(offset, value);
+        }
+    }
+
+    for reference in references {
+        memory[reference.address] = match labels.get(reference.label) {
+            Some(address) => *address as Cell,
+            None => {
+                return Err(Error::InvalidAssembly {
+                    line: reference.line,
+                    column: reference.column,
+                    message: "Undefined label",
+                })
+            }
+        };
+    }
+
+    Ok(memory)
+}
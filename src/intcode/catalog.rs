@@ -0,0 +1,418 @@
+//! Known example programs, with their expected behaviors, so the same
+//! source codes don't have to be copied around.
+
+use crate::intcode::Cell;
+
+/// An example program, alongside the inputs it's given and what it should
+/// produce with them.
+pub struct Example {
+    /// The name of the example, as used by `example`.
+    pub name: &'static str,
+
+    /// The program's source code.
+    pub source_code: &'static str,
+
+    /// The inputs the program is given, in order.
+    pub inputs: &'static [Cell],
+
+    /// The outputs the program should produce with these inputs.
+    pub outputs: &'static [Cell],
+
+    /// Values expected in memory (as `(address, value)`) once the program halted.
+    pub memory: &'static [(usize, Cell)],
+}
+
+/// Day 2: the explained example, storing 3500 at address 0.
+pub const DAY2_EXAMPLE: Example = Example {
+    name: "day2_example",
+    source_code: "1,9,10,3,2,3,11,0,99,30,40,50",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(0, 3500), (3, 70)],
+};
+
+/// Day 2: 1 + 1 = 2.
+pub const DAY2_ADD: Example = Example {
+    name: "day2_add",
+    source_code: "1,0,0,0,99",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(0, 2)],
+};
+
+/// Day 2: 3 × 2 = 6.
+pub const DAY2_MULTIPLY: Example = Example {
+    name: "day2_multiply",
+    source_code: "2,3,0,3,99",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(3, 6)],
+};
+
+/// Day 2: 99 × 99 = 9801, stored after the halt instruction.
+pub const DAY2_SQUARE: Example = Example {
+    name: "day2_square",
+    source_code: "2,4,4,5,99,0",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(5, 9801)],
+};
+
+/// Day 2: a program overwriting its own halt instruction.
+pub const DAY2_SELF_MODIFYING: Example = Example {
+    name: "day2_self_modifying",
+    source_code: "1,1,1,4,99,5,6,0,99",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(0, 30), (4, 2)],
+};
+
+/// Day 5: parameter modes, multiplying 33 by 3 to get a halt instruction.
+pub const DAY5_MODES: Example = Example {
+    name: "day5_modes",
+    source_code: "1002,4,3,4,33",
+    inputs: &[],
+    outputs: &[],
+    memory: &[(4, 99)],
+};
+
+/// Day 5: outputs 1 if the input is equal to 8, using position mode.
+pub const DAY5_EQUAL_8_POSITION: Example = Example {
+    name: "day5_equal_8_position",
+    source_code: "3,9,8,9,10,9,4,9,99,-1,8",
+    inputs: &[8],
+    outputs: &[1],
+    memory: &[],
+};
+
+/// Day 5: outputs 1 if the input is less than 8, using position mode.
+pub const DAY5_LESS_THAN_8_POSITION: Example = Example {
+    name: "day5_less_than_8_position",
+    source_code: "3,9,7,9,10,9,4,9,99,-1,8",
+    inputs: &[8],
+    outputs: &[0],
+    memory: &[],
+};
+
+/// Day 5: outputs 1 if the input is equal to 8, using immediate mode.
+pub const DAY5_EQUAL_8_IMMEDIATE: Example = Example {
+    name: "day5_equal_8_immediate",
+    source_code: "3,3,1108,-1,8,3,4,3,99",
+    inputs: &[7],
+    outputs: &[0],
+    memory: &[],
+};
+
+/// Day 5: outputs 1 if the input is less than 8, using immediate mode.
+pub const DAY5_LESS_THAN_8_IMMEDIATE: Example = Example {
+    name: "day5_less_than_8_immediate",
+    source_code: "3,3,1107,-1,8,3,4,3,99",
+    inputs: &[7],
+    outputs: &[1],
+    memory: &[],
+};
+
+/// Day 5: outputs 0 if the input is zero, 1 else, using position mode jumps.
+pub const DAY5_JUMP_POSITION: Example = Example {
+    name: "day5_jump_position",
+    source_code: "3,12,6,12,15,1,13,14,13,4,13,99,-1,0,1,9",
+    inputs: &[0],
+    outputs: &[0],
+    memory: &[],
+};
+
+/// Day 5: outputs 0 if the input is zero, 1 else, using immediate mode jumps.
+pub const DAY5_JUMP_IMMEDIATE: Example = Example {
+    name: "day5_jump_immediate",
+    source_code: "3,3,1105,-1,9,1101,0,0,12,4,12,99,1",
+    inputs: &[42],
+    outputs: &[1],
+    memory: &[],
+};
+
+/// Day 5: outputs 999 if the input is below 8, 1000 if it's equal to 8,
+/// and 1001 if it's greater than 8.
+pub const DAY5_COMPARE_TO_8: Example = Example {
+    name: "day5_compare_to_8",
+    source_code: "3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99",
+    inputs: &[9],
+    outputs: &[1001],
+    memory: &[],
+};
+
+/// Day 7: an amplifier, receiving a phase setting and an input signal.
+pub const DAY7_AMPLIFIER: Example = Example {
+    name: "day7_amplifier",
+    source_code: "3,15,3,16,1002,16,10,16,1,16,15,15,4,15,99,0,0",
+    inputs: &[4, 0],
+    outputs: &[4],
+    memory: &[],
+};
+
+/// Day 9: a program outputting a copy of itself, using relative mode.
+pub const DAY9_QUINE: Example = Example {
+    name: "day9_quine",
+    source_code: "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99",
+    inputs: &[],
+    outputs: &[
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ],
+    memory: &[],
+};
+
+/// Day 9: outputs a 16-digits number.
+pub const DAY9_LARGE_PRODUCT: Example = Example {
+    name: "day9_large_product",
+    source_code: "1102,34915192,34915192,7,4,7,99,0",
+    inputs: &[],
+    outputs: &[1_219_070_632_396_864],
+    memory: &[],
+};
+
+/// Day 9: outputs the large number in the middle.
+pub const DAY9_LARGE_NUMBER: Example = Example {
+    name: "day9_large_number",
+    source_code: "104,1125899906842624,99",
+    inputs: &[],
+    outputs: &[1_125_899_906_842_624],
+    memory: &[],
+};
+
+/// Offline corpus: every opcode. Outputs 2x², x < 10 and x = 3, 1 if
+/// x < 10 (after jumping over a 0 if x = 3), and 2x² again through
+/// relative mode.
+pub const CORPUS_OPCODES: Example = Example {
+    name: "corpus_opcodes",
+    source_code: include_str!("../../examples/intcode/opcodes.intcode"),
+    inputs: &[3],
+    outputs: &[18, 1, 1, 1, 18],
+    memory: &[],
+};
+
+/// Offline corpus: the same program, taking the other branches.
+pub const CORPUS_OPCODES_OTHER_BRANCHES: Example = Example {
+    name: "corpus_opcodes_other_branches",
+    source_code: include_str!("../../examples/intcode/opcodes.intcode"),
+    inputs: &[12],
+    outputs: &[288, 0, 0, 0, 288],
+    memory: &[],
+};
+
+/// Offline corpus: parameter modes edge cases. Reads and writes with
+/// negative relative offsets (outputs its input + 5), reads an instruction
+/// in position mode, and patches an instruction before executing it.
+pub const CORPUS_MODES: Example = Example {
+    name: "corpus_modes",
+    source_code: include_str!("../../examples/intcode/modes.intcode"),
+    inputs: &[7],
+    outputs: &[12, 109, 42],
+    memory: &[(20, 104)],
+};
+
+/// Offline corpus: writes and reads far past the program, in position and
+/// relative modes, and reads memory never written.
+pub const CORPUS_MEMORY_GROWTH: Example = Example {
+    name: "corpus_memory_growth",
+    source_code: include_str!("../../examples/intcode/memory_growth.intcode"),
+    inputs: &[],
+    outputs: &[42, 2, 0],
+    memory: &[(1000, 42), (5000, 2), (3000, 0)],
+};
+
+/// Offline corpus: outputs its inputs doubled, one at a time, until it
+/// receives 0; to pause and resume.
+pub const CORPUS_PAUSE_RESUME: Example = Example {
+    name: "corpus_pause_resume",
+    source_code: include_str!("../../examples/intcode/pause_resume.intcode"),
+    inputs: &[3, 1, 0],
+    outputs: &[6, 2],
+    memory: &[],
+};
+
+/// Offline corpus: an amplifier-like program, reading a phase setting and
+/// a signal, and outputting twice the signal plus the phase. With a phase
+/// of 5 or more, it does that three times, for feedback loops.
+pub const CORPUS_AMPLIFIER: Example = Example {
+    name: "corpus_amplifier",
+    source_code: include_str!("../../examples/intcode/amplifier.intcode"),
+    inputs: &[1, 5],
+    outputs: &[11],
+    memory: &[],
+};
+
+/// Offline corpus: the amplifier-like program, in a feedback loop.
+pub const CORPUS_AMPLIFIER_FEEDBACK: Example = Example {
+    name: "corpus_amplifier_feedback",
+    source_code: include_str!("../../examples/intcode/amplifier.intcode"),
+    inputs: &[5, 1, 2, 3],
+    outputs: &[7, 9, 11],
+    memory: &[],
+};
+
+/// Offline corpus: a long loop, summing the numbers from 1 to 1000 with
+/// parameters in every mode (on instructions codes of all lengths).
+pub const CORPUS_LOOP: Example = Example {
+    name: "corpus_loop",
+    source_code: include_str!("../../examples/intcode/loop.intcode"),
+    inputs: &[],
+    outputs: &[500_500],
+    memory: &[(101, 500_500)],
+};
+
+///
+/// Offline corpus: a guessing game. The program guesses a number from 1 to
+/// 1023 by binary search, outputting each guess and reading whether the
+/// number is lower (-1), higher (1) or found (0). Here, the number is 700.
+///
+/// It needs at most ten guesses:
+///
+/// ```rust
+/// # use lib::intcode::catalog::CORPUS_GUESSING_GAME;
+/// # use lib::intcode::{Event, Program};
+/// for secret in 1..1024 {
+///     let mut program: Program = CORPUS_GUESSING_GAME.source_code.parse().unwrap();
+///     let mut guesses = vec![];
+///
+///     loop {
+///         match program.run_until_event().unwrap() {
+///             Event::Output(guess) => guesses.push(guess),
+///             Event::NeedsInput => program.push_input((secret - guesses[guesses.len() - 1]).signum()),
+///             Event::Halted | Event::Stopped(_) => break,
+///         }
+///     }
+///
+///     assert_eq!(guesses.last(), Some(&secret));
+///     assert!(guesses.len() <= 10);
+/// }
+/// ```
+pub const CORPUS_GUESSING_GAME: Example = Example {
+    name: "corpus_guessing_game",
+    source_code: include_str!("../../examples/intcode/guessing_game.intcode"),
+    inputs: &[1, -1, 1, -1, 1, 1, 1, 0],
+    outputs: &[512, 768, 640, 704, 672, 688, 696, 700],
+    memory: &[(33, 696)],
+};
+
+/// Offline stand-in for day 2: adds the cells at the noun and the verb,
+/// which gives the moon landing date for 5 and 6.
+pub const STAND_IN_DAY2: Example = Example {
+    name: "stand_in_day2",
+    source_code: include_str!("../../examples/intcode/day2.intcode"),
+    inputs: &[],
+    outputs: &[],
+    memory: &[(0, 2)],
+};
+
+/// Offline stand-in for day 5: passes two tests, and outputs 111 times
+/// the system ID.
+pub const STAND_IN_DAY5: Example = Example {
+    name: "stand_in_day5",
+    source_code: include_str!("../../examples/intcode/day5.intcode"),
+    inputs: &[5],
+    outputs: &[0, 0, 555],
+    memory: &[],
+};
+
+/// Offline stand-in for day 9: outputs its input times 1000000007, using
+/// relative mode.
+pub const STAND_IN_DAY9: Example = Example {
+    name: "stand_in_day9",
+    source_code: include_str!("../../examples/intcode/day9.intcode"),
+    inputs: &[2],
+    outputs: &[2_000_000_014],
+    memory: &[],
+};
+
+///
+/// Stand-ins for the inputs of the Intcode days, to run their logic without
+/// the real inputs. Their answers are not the real ones.
+///
+/// ```rust
+/// # use lib::intcode::catalog::STAND_INS;
+/// # use lib::{solve_day, Value};
+/// let answers: Vec<_> = STAND_INS
+///     .iter()
+///     .map(|(day, source_code)| solve_day(*day, source_code).unwrap())
+///     .collect();
+///
+/// assert_eq!(
+///     answers,
+///     vec![
+///         (Value::Number(2), Value::Number(506)),
+///         (Value::Number(111), Value::Number(555)),
+///         (Value::Number(98), Value::Number(267_421)),
+///         (Value::Number(1_000_000_007), Value::Number(2_000_000_014)),
+///     ]
+/// );
+/// ```
+pub const STAND_INS: [(u8, &str); 4] = [
+    (2, STAND_IN_DAY2.source_code),
+    (5, STAND_IN_DAY5.source_code),
+    (7, CORPUS_AMPLIFIER.source_code),
+    (9, STAND_IN_DAY9.source_code),
+];
+
+///
+/// All the examples of the catalog.
+///
+/// ```rust
+/// # use lib::intcode::catalog::EXAMPLES;
+/// # use lib::intcode::Program;
+/// for example in EXAMPLES.iter() {
+///     let mut program: Program = example.source_code.parse().unwrap();
+///
+///     if let Err(error) = program.assert_outputs(example.inputs, example.outputs) {
+///         panic!("{}: {}", example.name, error);
+///     }
+///
+///     for (address, value) in example.memory {
+///         assert_eq!(program.get(*address), *value, "{}", example.name);
+///     }
+/// }
+/// ```
+pub const EXAMPLES: [Example; 29] = [
+    DAY2_EXAMPLE,
+    DAY2_ADD,
+    DAY2_MULTIPLY,
+    DAY2_SQUARE,
+    DAY2_SELF_MODIFYING,
+    DAY5_MODES,
+    DAY5_EQUAL_8_POSITION,
+    DAY5_LESS_THAN_8_POSITION,
+    DAY5_EQUAL_8_IMMEDIATE,
+    DAY5_LESS_THAN_8_IMMEDIATE,
+    DAY5_JUMP_POSITION,
+    DAY5_JUMP_IMMEDIATE,
+    DAY5_COMPARE_TO_8,
+    DAY7_AMPLIFIER,
+    DAY9_QUINE,
+    DAY9_LARGE_PRODUCT,
+    DAY9_LARGE_NUMBER,
+    CORPUS_OPCODES,
+    CORPUS_OPCODES_OTHER_BRANCHES,
+    CORPUS_MODES,
+    CORPUS_MEMORY_GROWTH,
+    CORPUS_PAUSE_RESUME,
+    CORPUS_AMPLIFIER,
+    CORPUS_AMPLIFIER_FEEDBACK,
+    CORPUS_LOOP,
+    CORPUS_GUESSING_GAME,
+    STAND_IN_DAY2,
+    STAND_IN_DAY5,
+    STAND_IN_DAY9,
+];
+
+///
+/// Returns the source code of an example from its name.
+///
+/// ```rust
+/// # use lib::intcode::catalog::example;
+/// assert_eq!(example("day2_add"), Some("1,0,0,0,99"));
+/// assert_eq!(example("unknown"), None);
+/// ```
+pub fn example(name: &str) -> Option<&'static str> {
+    EXAMPLES
+        .iter()
+        .find(|example| example.name == name)
+        .map(|example| example.source_code)
+}
@@ -0,0 +1,224 @@
+//! Consistency checks between the disassembler, the assembler and the
+//! interpreter, which each know the opcodes: every instruction listed by
+//! the disassembler must assemble back to the same cells, and have the
+//! number of parameters the interpreter reads.
+
+use super::asm::assemble;
+use super::catalog::EXAMPLES;
+use super::disasm::{decode_all, Line, Table, TABLE};
+use super::{Cell, Program};
+use itertools::Itertools;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+
+/// The kinds of inconsistencies found by `consistency_check`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum InconsistencyKind {
+    /// The listing of the instruction assembles to other cells, or can't
+    /// be assembled.
+    Reassembly,
+
+    /// The interpreter reads another number of parameters, or doesn't know
+    /// the instruction.
+    ParameterCount,
+}
+
+/// An instruction on which the disassembler disagrees with the assembler
+/// or the interpreter. See `consistency_check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Inconsistency {
+    /// The address of the instruction.
+    pub address: usize,
+    pub kind: InconsistencyKind,
+
+    /// The instruction, as listed by the disassembler.
+    pub listing: String,
+
+    /// The cells of the instruction, as decoded by the disassembler.
+    pub cells: Vec<Cell>,
+
+    /// The other side's rendering: the reassembled cells or the assembler's
+    /// error, or the interpreter's decoding.
+    pub other: String,
+}
+
+impl Display for Inconsistency {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            InconsistencyKind::Reassembly => write!(
+                f,
+                "{}: `{}` ({}) assembles to {}",
+                self.address,
+                self.listing,
+                self.cells.iter().join(","),
+                self.other
+            ),
+            InconsistencyKind::ParameterCount => write!(
+                f,
+                "{}: `{}` has {} parameters, the interpreter decodes {}",
+                self.address,
+                self.listing,
+                self.cells.len() - 1,
+                self.other
+            ),
+        }
+    }
+}
+
+///
+/// Checks every instruction the disassembler finds in the memory of the
+/// program: its listing must assemble back to the same cells, and the
+/// interpreter must read as many parameters. Returns the inconsistencies
+/// sorted by address; there should be none.
+///
+/// ```rust
+/// # use lib::intcode::catalog::{DAY2_EXAMPLE, DAY9_QUINE};
+/// # use lib::intcode::{consistency_check, Program};
+/// for example in &[DAY2_EXAMPLE, DAY9_QUINE] {
+///     let program: Program = example.source_code.parse().unwrap();
+///     assert!(consistency_check(&program).is_empty());
+/// }
+/// ```
+pub fn consistency_check(program: &Program) -> Vec<Inconsistency> {
+    consistency_check_with(program, &TABLE)
+}
+
+///
+/// Checks the program as `consistency_check`, with the disassembler using
+/// the given table; to make sure a mistake in it would be caught.
+///
+/// ```rust
+/// # use lib::intcode::catalog::{DAY2_EXAMPLE, DAY5_EQUAL_8_POSITION};
+/// # use lib::intcode::consistency::{consistency_check_with, InconsistencyKind};
+/// # use lib::intcode::disasm::{Table, TABLE};
+/// # use lib::intcode::{Cell, OpCode, Operation, Program};
+/// // Additions and multiplications swapped.
+/// fn swapped(opcode: OpCode) -> &'static str {
+///     match opcode {
+///         OpCode::Arithmetic(Operation::Add) => "mul",
+///         OpCode::Arithmetic(Operation::Multiply) => "add",
+///         _ => (TABLE.mnemonic)(opcode),
+///     }
+/// }
+///
+/// let program: Program = DAY2_EXAMPLE.source_code.parse().unwrap();
+/// let table = Table { mnemonic: swapped, ..TABLE };
+/// let inconsistencies = consistency_check_with(&program, &table);
+/// assert_eq!(
+///     inconsistencies.iter().map(|i| i.to_string()).collect::<Vec<_>>(),
+///     vec![
+///         "0: `mul [9], [10], [3]` (1,9,10,3) assembles to 2,9,10,3",
+///         "4: `add [3], [11], [0]` (2,3,11,0) assembles to 1,3,11,0",
+///     ]
+/// );
+///
+/// // Outputs with two parameters.
+/// fn decode(code: Cell) -> Option<(OpCode, usize)> {
+///     match (TABLE.decode)(code)? {
+///         (OpCode::Output, _) => Some((OpCode::Output, 2)),
+///         decoded => Some(decoded),
+///     }
+/// }
+///
+/// let program: Program = DAY5_EQUAL_8_POSITION.source_code.parse().unwrap();
+/// let table = Table { decode, ..TABLE };
+/// let inconsistencies = consistency_check_with(&program, &table);
+/// assert_eq!(
+///     inconsistencies.iter().map(|i| (i.address, i.kind)).collect::<Vec<_>>(),
+///     vec![(6, InconsistencyKind::Reassembly), (6, InconsistencyKind::ParameterCount)]
+/// );
+/// assert_eq!(
+///     inconsistencies[0].to_string(),
+///     "6: `out [9], [99]` (4,9,99) assembles to an error: \
+///      Wrong number of operands at line 1, column 1"
+/// );
+/// assert_eq!(
+///     inconsistencies[1].to_string(),
+///     "6: `out [9], [99]` has 2 parameters, the interpreter decodes 4 with 1"
+/// );
+/// ```
+pub fn consistency_check_with(program: &Program, table: &Table) -> Vec<Inconsistency> {
+    let memory = program.memory();
+    let mut inconsistencies = vec![];
+
+    for (address, line) in decode_all(memory, table) {
+        let parameters_count = match &line {
+            Line::Instruction { parameters, .. } => parameters.len(),
+            Line::Data(_) => continue,
+        };
+
+        let cells = memory[address..=address + parameters_count].to_vec();
+        let listing = line.to_listing_with(None, table);
+        let mut inconsistent = |kind, other| {
+            inconsistencies.push(Inconsistency {
+                address,
+                kind,
+                listing: listing.clone(),
+                cells: cells.clone(),
+                other,
+            })
+        };
+
+        match assemble(&listing) {
+            Ok(reassembled) if reassembled == cells => {}
+            Ok(reassembled) => {
+                inconsistent(InconsistencyKind::Reassembly, reassembled.iter().join(","))
+            }
+            Err(error) => inconsistent(
+                InconsistencyKind::Reassembly,
+                format!("an error: {}", error),
+            ),
+        }
+
+        match program.parse_opcode(cells[0]) {
+            Some((_, count)) if count == parameters_count => {}
+            Some((_, count)) => inconsistent(
+                InconsistencyKind::ParameterCount,
+                format!("{} with {}", cells[0], count),
+            ),
+            None => inconsistent(
+                InconsistencyKind::ParameterCount,
+                format!("{} as no instruction", cells[0]),
+            ),
+        }
+    }
+
+    inconsistencies
+}
+
+///
+/// Runs `consistency_check` on the examples of days 2, 5 and 9, and on the
+/// inputs of these days found in `input/`. Returns the name of each
+/// program checked, with its inconsistencies.
+///
+/// ```rust
+/// # use lib::intcode::self_test;
+/// let results = self_test();
+/// assert!(results.iter().any(|(name, _)| name == "day9_quine"));
+/// assert!(results.iter().all(|(_, inconsistencies)| inconsistencies.is_empty()));
+/// ```
+pub fn self_test() -> Vec<(String, Vec<Inconsistency>)> {
+    let examples = EXAMPLES
+        .iter()
+        .filter(|example| {
+            ["day2_", "day5_", "day9_"]
+                .iter()
+                .any(|day| example.name.starts_with(day))
+        })
+        .map(|example| (example.name.to_string(), example.source_code.to_string()));
+
+    let inputs = [2, 5, 9].iter().filter_map(|day| {
+        let path = format!("input/day-{}.txt", day);
+        fs::read_to_string(&path)
+            .ok()
+            .map(|source_code| (path, source_code))
+    });
+
+    examples
+        .chain(inputs)
+        .filter_map(|(name, source_code)| {
+            let program: Program = source_code.trim().parse().ok()?;
+            Some((name, consistency_check(&program)))
+        })
+        .collect()
+}
@@ -0,0 +1,145 @@
+//! Coverage of the instructions of a program, to find the code a run never
+//! executed.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! // Jumps over an output of 666.
+//! let mut program: Program = "1105,1,5,104,666,104,1,99".parse().unwrap();
+//! program.enable_coverage();
+//! program.execute().unwrap();
+//!
+//! let coverage = program.coverage();
+//! assert_eq!(coverage.executed_addresses(), vec![0, 5, 7]);
+//! assert_eq!(coverage.total_instructions_estimate(), 4);
+//! assert_eq!(coverage.unexecuted_regions(), vec![3..=4]);
+//! assert!(!coverage.is_executed(3));
+//!
+//! assert_eq!(
+//!     coverage.to_string(),
+//!     "3 instructions executed, of about 4\nNever executed: 3-4\n"
+//! );
+//! ```
+
+use super::{decode_opcode, Cell, Program};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::RangeInclusive;
+
+/// The instructions executed by a program, against its image as loaded.
+/// See `Program::coverage`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageReport {
+    /// The length of the instructions executed, by address.
+    executed: BTreeMap<usize, usize>,
+
+    /// The program image, as loaded.
+    image: Vec<Cell>,
+}
+
+impl CoverageReport {
+    /// Returns the addresses of the instructions executed, in order.
+    pub fn executed_addresses(&self) -> Vec<usize> {
+        self.executed.keys().cloned().collect()
+    }
+
+    /// Checks if an instruction was executed at this address.
+    pub fn is_executed(&self, address: usize) -> bool {
+        self.executed.contains_key(&address)
+    }
+
+    /// Estimates the number of instructions in the program image, by
+    /// decoding it from start to end. Data looking like instructions is
+    /// counted too, so it's only an estimate.
+    pub fn total_instructions_estimate(&self) -> usize {
+        let mut address = 0;
+        let mut instructions = 0;
+
+        while let Some(code) = self.image.get(address) {
+            match decode_opcode(*code) {
+                Some((_, parameters_count)) => {
+                    instructions += 1;
+                    address += parameters_count + 1;
+                }
+                None => address += 1,
+            }
+        }
+
+        instructions
+    }
+
+    /// Returns the ranges of addresses of the program image which are not
+    /// part of any executed instruction: code never reached, or data.
+    pub fn unexecuted_regions(&self) -> Vec<RangeInclusive<usize>> {
+        let mut covered = vec![false; self.image.len()];
+        for (address, length) in &self.executed {
+            covered
+                .iter_mut()
+                .skip(*address)
+                .take(*length)
+                .for_each(|cell| *cell = true);
+        }
+
+        let mut regions = vec![];
+        let mut start = None;
+
+        for (address, covered) in covered.iter().chain(&[true]).enumerate() {
+            match (start, covered) {
+                (None, false) => start = Some(address),
+                (Some(first), true) => {
+                    regions.push(first..=address - 1);
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        regions
+    }
+}
+
+impl Display for CoverageReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} instructions executed, of about {}",
+            self.executed.len(),
+            self.total_instructions_estimate()
+        )?;
+
+        let regions = self.unexecuted_regions();
+        if !regions.is_empty() {
+            let regions: Vec<String> = regions
+                .iter()
+                .map(|region| {
+                    if region.start() == region.end() {
+                        region.start().to_string()
+                    } else {
+                        format!("{}-{}", region.start(), region.end())
+                    }
+                })
+                .collect();
+
+            writeln!(f, "Never executed: {}", regions.join(", "))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Program {
+    /// Enables the recording of the addresses of the instructions executed,
+    /// for `coverage`. The record starts over when the program restarts.
+    pub fn enable_coverage(&mut self) {
+        self.coverage = Some(BTreeMap::new());
+        self.update_event();
+    }
+
+    /// Returns the coverage of the program image by the instructions
+    /// executed. Nothing is executed if coverage is not enabled.
+    pub fn coverage(&self) -> CoverageReport {
+        CoverageReport {
+            executed: self.coverage.clone().unwrap_or_default(),
+            image: self.initial_memory.to_vec(),
+        }
+    }
+}
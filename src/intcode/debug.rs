@@ -0,0 +1,328 @@
+//! Debugging programs: breakpoints stopping them before chosen instructions,
+//! watchpoints stopping them after writes to chosen cells, and output
+//! breaks stopping them after chosen outputs.
+//!
+//! A stopped program has the `ExecutionStatus::Stopped` status, and is
+//! still running: executing it again resumes it where it stopped, as after
+//! an output. `execute` and `execute_until_next_output` return the outputs
+//! so far, `execute_until_input_needed` and `run_until_event` tell why the
+//! program stopped. Single steps (`Program::step`) ignore breakpoints, but
+//! not watchpoints.
+//!
+//! ```rust
+//! # use lib::intcode::asm::assemble;
+//! # use lib::intcode::debug::{Breakpoint, Stopped};
+//! # use lib::intcode::{Event, ExecutionStatus, Program};
+//! // Outputs 1, 2 and 3.
+//! let memory = assemble(
+//!     "loop:  out [n]
+//!             add [n], #1, [n]
+//!             lt [n], #4, [c]
+//!             jnz [c], loop
+//!             hlt
+//!      n:     data 1
+//!      c:     data 0",
+//! )
+//! .unwrap();
+//!
+//! let mut program = Program::from_memory(memory.clone());
+//! program.add_breakpoint(Breakpoint::Address(6));
+//!
+//! // The instruction at the breakpoint is not executed yet.
+//! assert_eq!(program.execute().unwrap(), vec![1]);
+//! assert_eq!(program.status(), ExecutionStatus::Stopped(Stopped::Breakpoint(Breakpoint::Address(6))));
+//! assert_eq!((program.pointer(), program.get(14)), (6, 2));
+//! assert!(program.is_running());
+//!
+//! // Resuming executes it, and stops on the next iteration.
+//! assert_eq!(program.execute().unwrap(), vec![1, 2]);
+//! assert_eq!(program.pointer(), 6);
+//!
+//! assert!(program.remove_breakpoint(Breakpoint::Address(6)));
+//! assert!(!program.remove_breakpoint(Breakpoint::Address(6)));
+//! assert_eq!(program.execute().unwrap(), vec![1, 2, 3]);
+//! assert_eq!(program.status(), ExecutionStatus::Halted);
+//!
+//! // Stops after 5 instructions, the second output included.
+//! let mut program = Program::from_memory(memory);
+//! program.add_breakpoint(Breakpoint::Step(5));
+//! program.add_breakpoint(Breakpoint::Address(13));
+//! assert_eq!(program.breakpoints(), &[Breakpoint::Step(5), Breakpoint::Address(13)]);
+//!
+//! assert_eq!(program.run_until_event().unwrap(), Event::Output(1));
+//! assert_eq!(program.run_until_event().unwrap(), Event::Output(2));
+//! assert_eq!(
+//!     program.run_until_event().unwrap(),
+//!     Event::Stopped(Stopped::Breakpoint(Breakpoint::Step(5)))
+//! );
+//! assert_eq!((program.steps_executed(), program.pointer()), (5, 2));
+//!
+//! assert_eq!(program.execute().unwrap(), vec![1, 2, 3]);
+//! assert_eq!(program.status(), ExecutionStatus::Stopped(Stopped::Breakpoint(Breakpoint::Address(13))));
+//! ```
+
+use super::{Cell, Program};
+use std::fmt::{self, Display, Formatter};
+
+/// Where to stop a program. See `Program::add_breakpoint`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Breakpoint {
+    /// Before the instruction at this address.
+    Address(usize),
+
+    /// Once this number of instructions were executed since the program
+    /// started.
+    Step(u64),
+}
+
+/// Outputs stopping a program. See `Program::break_on_output`.
+pub enum OutputBreak {
+    /// The nth output since the program started (starting at 1).
+    Nth(usize),
+
+    /// Any output the predicate is true for.
+    Predicate(Box<dyn Fn(Cell) -> bool>),
+}
+
+/// Why a program stopped. See the module documentation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Stopped {
+    /// The program reached this breakpoint, before the next instruction.
+    Breakpoint(Breakpoint),
+
+    /// The instruction at `pointer` wrote to a watched address, replacing
+    /// the `old` value by the `new` one. The write is done, and the pointer
+    /// is on the next instruction.
+    Watchpoint {
+        address: usize,
+        old: Cell,
+        new: Cell,
+        pointer: usize,
+        opcode: Cell,
+    },
+
+    /// The program outputted a value matching the output break at this
+    /// index, in the order they were registered. `number` is the number
+    /// of outputs since the program started, this one included.
+    Output {
+        output_break: usize,
+        number: usize,
+        value: Cell,
+    },
+}
+
+impl Display for Stopped {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Stopped::Breakpoint(Breakpoint::Address(address)) => {
+                write!(f, "Breakpoint at address {}", address)
+            }
+            Stopped::Breakpoint(Breakpoint::Step(step)) => {
+                write!(f, "Breakpoint after {} steps", step)
+            }
+            Stopped::Watchpoint {
+                address,
+                old,
+                new,
+                pointer,
+                ..
+            } => write!(
+                f,
+                "Watchpoint: [{}] changed from {} to {}, by the instruction at address {}",
+                address, old, new, pointer
+            ),
+            Stopped::Output {
+                output_break,
+                number,
+                value,
+            } => write!(
+                f,
+                "Output break {}: output {} is {}",
+                output_break, number, value
+            ),
+        }
+    }
+}
+
+impl Program {
+    /// Adds a breakpoint, stopping the program when reached. Adding a
+    /// breakpoint twice does nothing.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Removes a breakpoint. Returns false if there was no such breakpoint.
+    pub fn remove_breakpoint(&mut self, breakpoint: Breakpoint) -> bool {
+        let count = self.breakpoints.len();
+        self.breakpoints.retain(|b| *b != breakpoint);
+        self.breakpoints.len() != count
+    }
+
+    /// Returns the breakpoints, in the order they were added.
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    ///
+    /// Watches the cell at the address: the program stops after each
+    /// instruction writing to it, even if the value doesn't change. Writes
+    /// from outside the program, e.g. patches, are not watched. Watching an
+    /// address twice does nothing.
+    ///
+    /// ```rust
+    /// # use lib::intcode::asm::assemble;
+    /// # use lib::intcode::debug::Stopped;
+    /// # use lib::intcode::{ExecutionStatus, Program};
+    /// // Outputs 1, 2 and 3, counting in the cell 14.
+    /// let memory = assemble(
+    ///     "loop:  out [n]
+    ///             add [n], #1, [n]
+    ///             lt [n], #4, [c]
+    ///             jnz [c], loop
+    ///             hlt
+    ///      n:     data 1
+    ///      c:     data 0",
+    /// )
+    /// .unwrap();
+    ///
+    /// let mut program = Program::from_memory(memory);
+    /// program.add_watchpoint(14);
+    /// program.patch(14, 1).unwrap(); // Not watched.
+    ///
+    /// let mut writes = vec![];
+    /// program.execute().unwrap();
+    /// while let ExecutionStatus::Stopped(Stopped::Watchpoint { address, old, new, pointer, opcode }) =
+    ///     program.status()
+    /// {
+    ///     assert_eq!((address, pointer, opcode), (14, 2, 1001));
+    ///     assert_eq!((program.get(14), program.pointer()), (new, 6));
+    ///     writes.push((old, new));
+    ///     program.execute().unwrap();
+    /// }
+    ///
+    /// assert_eq!(writes, vec![(1, 2), (2, 3), (3, 4)]);
+    /// assert_eq!(program.status(), ExecutionStatus::Halted);
+    /// assert_eq!(program.output(), vec![1, 2, 3]);
+    ///
+    /// assert_eq!(program.watchpoints(), &[14]);
+    /// assert!(program.remove_watchpoint(14));
+    /// assert!(program.watchpoints().is_empty());
+    /// ```
+    pub fn add_watchpoint(&mut self, address: usize) {
+        if !self.watchpoints.contains(&address) {
+            self.watchpoints.push(address);
+        }
+    }
+
+    /// Stops watching an address. Returns false if it wasn't watched.
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        let count = self.watchpoints.len();
+        self.watchpoints.retain(|a| *a != address);
+        self.watchpoints.len() != count
+    }
+
+    /// Returns the watched addresses, in the order they were added.
+    pub fn watchpoints(&self) -> &[usize] {
+        &self.watchpoints
+    }
+
+    ///
+    /// Stops the program right after the outputs matching the condition,
+    /// recorded as usual. Several output breaks can be registered; the
+    /// first matching one, in the order they were registered, is reported.
+    ///
+    /// ```rust
+    /// # use lib::intcode::debug::{OutputBreak, Stopped};
+    /// # use lib::intcode::{ExecutionStatus, Program};
+    /// // Outputs its input, plus 1, 2 and 3.
+    /// let source_code = "3,13,104,1,104,2,104,3,4,13,99,0,0,0";
+    ///
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// program.push_input(-7);
+    /// program.break_on_output(OutputBreak::Predicate(Box::new(|value| value == 2)));
+    /// program.break_on_output(OutputBreak::Nth(2));
+    /// program.break_on_output(OutputBreak::Predicate(Box::new(|value| value < 0)));
+    ///
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(1));
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(2));
+    /// assert_eq!(
+    ///     program.status(),
+    ///     ExecutionStatus::Stopped(Stopped::Output { output_break: 0, number: 2, value: 2 })
+    /// );
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2, 3, -7]);
+    /// assert_eq!(
+    ///     program.status(),
+    ///     ExecutionStatus::Stopped(Stopped::Output { output_break: 2, number: 4, value: -7 })
+    /// );
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2, 3, -7]);
+    /// assert_eq!(program.status(), ExecutionStatus::Halted);
+    ///
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// program.push_input(7);
+    /// program.break_on_output(OutputBreak::Nth(3));
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2, 3]);
+    /// assert_eq!(
+    ///     program.status(),
+    ///     ExecutionStatus::Stopped(Stopped::Output { output_break: 0, number: 3, value: 3 })
+    /// );
+    ///
+    /// // Without matching output, the program runs until it halts.
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// program.push_input(7);
+    /// program.break_on_output(OutputBreak::Predicate(Box::new(|value| value < 0)));
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2, 3, 7]);
+    /// assert_eq!(program.status(), ExecutionStatus::Halted);
+    /// ```
+    pub fn break_on_output(&mut self, output_break: OutputBreak) {
+        self.output_breaks.push(output_break);
+    }
+
+    /// Removes all the output breaks.
+    pub fn clear_output_breaks(&mut self) {
+        self.output_breaks.clear();
+    }
+
+    /// Returns the output break matched by the value just outputted, if
+    /// any.
+    pub(super) fn output_break_reached(&self, value: Cell) -> Option<Stopped> {
+        let number = self.outputs_produced();
+
+        self.output_breaks
+            .iter()
+            .position(|output_break| match output_break {
+                OutputBreak::Nth(n) => *n == number,
+                OutputBreak::Predicate(predicate) => predicate(value),
+            })
+            .map(|output_break| Stopped::Output {
+                output_break,
+                number,
+                value,
+            })
+    }
+
+    /// Returns the breakpoint reached before the next instruction, if any,
+    /// unless the program is resuming from a stop before this instruction.
+    pub(super) fn breakpoint_reached(&mut self) -> Option<Breakpoint> {
+        if std::mem::take(&mut self.resuming) {
+            return None;
+        }
+
+        let breakpoint = self
+            .breakpoints
+            .iter()
+            .find(|breakpoint| match breakpoint {
+                Breakpoint::Address(address) => *address == self.pointer,
+                Breakpoint::Step(step) => *step == self.steps,
+            })
+            .cloned();
+
+        self.resuming = breakpoint.is_some();
+        breakpoint
+    }
+}
@@ -0,0 +1,397 @@
+//! An interactive debugger, stepping through programs from a terminal.
+//!
+//! At each prompt, the debugger shows the next instruction, unless the
+//! program halted, then reads a command:
+//!
+//! - `step [n]`: executes the next instruction, or the next `n` ones;
+//! - `continue`: executes the program until it halts, or stops (see
+//!   `debug`);
+//! - `break <addr>`: stops before the instruction at the address;
+//! - `watch <addr>`: stops after each write to the cell at the address;
+//! - `print <addr> [len]`: prints the cell at the address, or `len` cells;
+//! - `set <addr> <val>`: writes the value at the address;
+//! - `disasm [addr]`: lists the instructions from the address, or from the
+//!   next one;
+//! - `out`: prints the outputs so far;
+//! - `info`: prints the pointer, the relative base, the number of steps
+//!   executed, and the memory usage (see `Program::memory_usage`);
+//! - `cost [weighted]`: prints the cost of the instructions executed so
+//!   far, by basic block, with the uniform or the weighted cost model (see
+//!   `profile::CostModel`);
+//! - `quit`: ends the session, as the end of the input.
+//!
+//! Outputs are printed as they happen. When the program needs an input
+//! and none is queued, the debugger asks for it. Invalid commands, and
+//! failures of the program, are reported without ending the session.
+
+use super::analysis::control_flow_graph;
+use super::debug::Breakpoint;
+use super::profile::CostModel;
+use super::{Cell, Error, Event, ExecutionStatus, Program, Result};
+use itertools::Itertools;
+use std::fmt::Display;
+use std::io::{self, BufRead, Write};
+use std::str::FromStr;
+
+/// The commands, as listed by the debugger.
+const COMMANDS: &str = "step [n], continue, break <addr>, watch <addr>, print <addr> [len], \
+                        set <addr> <val>, disasm [addr], out, info, cost [weighted], quit";
+
+/// The number of instructions listed by `disasm`.
+const DISASM_LINES: usize = 8;
+
+/// A command of the debugger.
+enum Command {
+    Step(u64),
+    Continue,
+    Break(usize),
+    Watch(usize),
+    Print(usize, usize),
+    Set(usize, Cell),
+    Disasm(Option<usize>),
+    Out,
+    Info,
+    Cost(CostModel),
+    Quit,
+}
+
+/// Parses a number, or describes why it's invalid.
+fn number<T: FromStr>(word: &str, what: &str) -> std::result::Result<T, String> {
+    word.parse()
+        .map_err(|_| format!("Invalid {}: {}", what, word))
+}
+
+/// Parses a command, or describes why it's invalid. Blank lines are no
+/// commands.
+fn parse_command(line: &str) -> std::result::Result<Option<Command>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let command = match words.as_slice() {
+        [] => return Ok(None),
+        ["step"] => Command::Step(1),
+        ["step", count] => Command::Step(number(count, "count")?),
+        ["continue"] => Command::Continue,
+        ["break", address] => Command::Break(number(address, "address")?),
+        ["watch", address] => Command::Watch(number(address, "address")?),
+        ["print", address] => Command::Print(number(address, "address")?, 1),
+        ["print", address, len] => {
+            Command::Print(number(address, "address")?, number(len, "length")?)
+        }
+        ["set", address, value] => {
+            Command::Set(number(address, "address")?, number(value, "value")?)
+        }
+        ["disasm"] => Command::Disasm(None),
+        ["disasm", address] => Command::Disasm(Some(number(address, "address")?)),
+        ["out"] => Command::Out,
+        ["info"] => Command::Info,
+        ["cost"] => Command::Cost(CostModel::uniform()),
+        ["cost", "weighted"] => Command::Cost(CostModel::weighted()),
+        ["quit"] => Command::Quit,
+        _ => return Err(format!("Unknown command. Commands: {}", COMMANDS)),
+    };
+
+    Ok(Some(command))
+}
+
+/// A debugging session on a program.
+struct Session<'p, I, O> {
+    program: &'p mut Program,
+    input: I,
+    output: O,
+}
+
+impl<I: BufRead, O: Write> Session<'_, I, O> {
+    /// Writes a line.
+    fn write(&mut self, line: impl Display) -> Result<()> {
+        writeln!(self.output, "{}", line).map_err(|_| Error::InvalidOutput("Cannot write output"))
+    }
+
+    /// Writes the prompt, and reads a line, trimmed; or `None` at the end
+    /// of the input.
+    fn read_line(&mut self, prompt: &str) -> Result<Option<String>> {
+        write!(self.output, "{}", prompt)
+            .and_then(|_| self.output.flush())
+            .map_err(|_| Error::InvalidOutput("Cannot write output"))?;
+
+        let mut line = String::new();
+        match self.input.read_line(&mut line)? {
+            0 => Ok(None),
+            _ => Ok(Some(line.trim().to_string())),
+        }
+    }
+
+    /// Asks for an input if the program needs one while none is queued.
+    /// Returns false at the end of the input.
+    fn provide_input(&mut self) -> Result<bool> {
+        while self.program.waiting_for_input() {
+            let line = match self.read_line("input> ")? {
+                Some(line) => line,
+                None => return Ok(false),
+            };
+
+            match line.parse() {
+                Ok(value) => self.program.push_input(value),
+                Err(_) => self.write("Invalid input: expected a number")?,
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Tells if the program halted, so it can't be executed further.
+    fn check_halted(&mut self) -> Result<bool> {
+        match self.program.status() {
+            ExecutionStatus::Halted => self.write("The program halted.").map(|_| true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Tells why the program stopped, if it did, or if it halted.
+    fn report_stop(&mut self) -> Result<()> {
+        match self.program.status() {
+            ExecutionStatus::Stopped(stopped) => self.write(stopped),
+            _ => self.check_halted().map(|_| ()),
+        }
+    }
+
+    /// Executes `count` instructions, ignoring breakpoints. Returns false
+    /// at the end of the input.
+    fn step(&mut self, count: u64) -> Result<bool> {
+        for _ in 0..count {
+            if self.check_halted()? {
+                break;
+            }
+            if !self.provide_input()? {
+                return Ok(false);
+            }
+
+            match self.program.step() {
+                Ok(step) => {
+                    if let Some(output) = step.output {
+                        self.write(format!("Output: {}", output))?;
+                    }
+                }
+                Err(error) => {
+                    self.write(format!("Error: {}", error))?;
+                    break;
+                }
+            }
+
+            if let ExecutionStatus::Stopped(_) | ExecutionStatus::Halted = self.program.status() {
+                self.report_stop()?;
+                break;
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Executes the program until it halts or stops. Returns false at the
+    /// end of the input.
+    fn resume(&mut self) -> Result<bool> {
+        if self.check_halted()? {
+            return Ok(true);
+        }
+
+        loop {
+            match self.program.run_until_event() {
+                Ok(Event::Output(output)) => self.write(format!("Output: {}", output))?,
+                Ok(Event::NeedsInput) => {
+                    if !self.provide_input()? {
+                        break Ok(false);
+                    }
+                }
+                Ok(Event::Halted) | Ok(Event::Stopped(_)) => {
+                    break self.report_stop().map(|_| true)
+                }
+                Err(error) => break self.write(format!("Error: {}", error)).map(|_| true),
+            }
+        }
+    }
+
+    /// Writes `len` cells from the address.
+    fn print(&mut self, address: usize, len: usize) -> Result<()> {
+        let cells = self.program.get_range(address, len);
+        self.write(format!("{}: {}", address, cells.iter().join(" ")))
+    }
+
+    /// Lists instructions from the address.
+    fn disassemble(&mut self, mut address: usize) -> Result<()> {
+        for _ in 0..DISASM_LINES {
+            let (listing, length) = self.program.listing_at(address);
+            self.write(format!("{}: {}", address, listing))?;
+            address += length;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the cost of the instructions executed, by basic block.
+    fn cost(&mut self, model: &CostModel) -> Result<()> {
+        let profile = self.program.profile();
+        let blocks = control_flow_graph(self.program.memory()).blocks;
+        let costs = profile.block_costs(model, &blocks);
+
+        for block in &costs {
+            self.write(format!(
+                "{}..{}: {} executions, cost {}",
+                block.addresses.start, block.addresses.end, block.executions, block.cost
+            ))?;
+        }
+
+        let total = profile.total_cost(model);
+        let outside = total - costs.iter().map(|block| block.cost).sum::<u64>();
+        if outside > 0 {
+            self.write(format!("Outside of the blocks: cost {}", outside))?;
+        }
+        self.write(format!("Total cost: {}", total))
+    }
+
+    /// Executes a command. Returns false to end the session.
+    fn command(&mut self, command: Command) -> Result<bool> {
+        match command {
+            Command::Step(count) => return self.step(count),
+            Command::Continue => return self.resume(),
+            Command::Break(address) => self.program.add_breakpoint(Breakpoint::Address(address)),
+            Command::Watch(address) => self.program.add_watchpoint(address),
+            Command::Print(address, len) => self.print(address, len)?,
+            Command::Set(address, value) => {
+                if let Err(error) = self.program.patch(address, value) {
+                    self.write(format!("Error: {}", error))?
+                }
+            }
+            Command::Disasm(address) => {
+                self.disassemble(address.unwrap_or_else(|| self.program.pointer()))?
+            }
+            Command::Out => {
+                let outputs = self.program.output_joined(", ");
+                self.write(format!("Outputs: {}", outputs))?
+            }
+            Command::Info => {
+                self.write(format!(
+                    "Pointer: {}, relative base: {}, steps: {}",
+                    self.program.pointer(),
+                    self.program.relative_base(),
+                    self.program.steps_executed()
+                ))?;
+                self.write(format!("Memory usage: {}", self.program.memory_usage()))?
+            }
+            Command::Cost(model) => self.cost(&model)?,
+            Command::Quit => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// Reads and executes commands until the end of the input, or `quit`.
+    fn run(&mut self) -> Result<()> {
+        self.write(format!("Commands: {}", COMMANDS))?;
+
+        loop {
+            if self.program.status() != ExecutionStatus::Halted {
+                let pointer = self.program.pointer();
+                let (listing, _) = self.program.listing_at(pointer);
+                self.write(format!("-> {}: {}", pointer, listing))?;
+            }
+
+            let line = match self.read_line("(debug) ")? {
+                Some(line) => line,
+                None => break Ok(()),
+            };
+
+            match parse_command(&line) {
+                Ok(None) => {}
+                Ok(Some(command)) => {
+                    if !self.command(command)? {
+                        break Ok(());
+                    }
+                }
+                Err(error) => self.write(error)?,
+            }
+        }
+    }
+}
+
+impl Program {
+    /// Debugs the program in the terminal, as `run_debugger_with` with
+    /// stdin and stdout.
+    pub fn run_debugger(&mut self) -> Result<()> {
+        self.run_debugger_with(io::stdin().lock(), io::stdout())
+    }
+
+    ///
+    /// Debugs the program interactively, reading commands from `input` and
+    /// writing to `output`. See the `debugger` module for the commands. The
+    /// program stays as the session leaves it, with the profiling enabled
+    /// for `cost`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::io::Cursor;
+    /// // Outputs its input, plus 1, 2 and 3.
+    /// let mut program: Program = "3,13,104,1,104,2,104,3,4,13,99,0,0,0".parse().unwrap();
+    /// let commands = "step\n42\nprint 13\nstep 2\nbreak 8\nwatch 0\njump 4\nstep x\ncontinue\n\
+    ///                 set 13 7\ncontinue\nout\ndisasm 8\nset 1000000000000 1\ninfo\ncost\ncost weighted\n";
+    /// let mut terminal = vec![];
+    ///
+    /// program.run_debugger_with(Cursor::new(commands), &mut terminal).unwrap();
+    /// let terminal = String::from_utf8(terminal).unwrap();
+    /// let lines: Vec<&str> = terminal.lines().skip(1).collect();
+    ///
+    /// assert_eq!(
+    ///     lines,
+    ///     vec![
+    ///         "-> 0: in [13]",
+    ///         "(debug) input> -> 2: out #1",
+    ///         "(debug) 13: 42",
+    ///         "-> 2: out #1",
+    ///         "(debug) Output: 1",
+    ///         "Output: 2",
+    ///         "-> 6: out #3",
+    ///         "(debug) -> 6: out #3",
+    ///         "(debug) -> 6: out #3",
+    ///         "(debug) Unknown command. Commands: step [n], continue, break <addr>, watch <addr>, \
+    ///          print <addr> [len], set <addr> <val>, disasm [addr], out, info, cost [weighted], quit",
+    ///         "-> 6: out #3",
+    ///         "(debug) Invalid count: x",
+    ///         "-> 6: out #3",
+    ///         "(debug) Output: 3",
+    ///         "Breakpoint at address 8",
+    ///         "-> 8: out [13]",
+    ///         "(debug) -> 8: out [13]",
+    ///         "(debug) Output: 7",
+    ///         "The program halted.",
+    ///         "(debug) Outputs: 1, 2, 3, 7",
+    ///         "(debug) 8: out [13]",
+    ///         "10: hlt",
+    ///         "11: data 0",
+    ///         "12: data 0",
+    ///         "13: lt [0], [0], [0]",
+    ///         "17: data 0",
+    ///         "18: data 0",
+    ///         "19: data 0",
+    ///         "(debug) Error: Memory limit exceeded: address 1000000000000, with a limit of \
+    ///          67108864 cells",
+    ///         "(debug) Pointer: 11, relative base: 0, steps: 6",
+    ///         "Memory usage: 14 cells loaded, 14 allocated, 0 sparse; highest address written: 13",
+    ///         "(debug) 0..11: 1 executions, cost 6",
+    ///         "Total cost: 6",
+    ///         "(debug) 0..11: 1 executions, cost 10",
+    ///         "Total cost: 10",
+    ///         "(debug) ",
+    ///     ]
+    /// );
+    /// ```
+    pub fn run_debugger_with(&mut self, input: impl BufRead, output: impl Write) -> Result<()> {
+        if self.profiler.is_none() {
+            self.enable_profiling();
+        }
+
+        Session {
+            program: self,
+            input,
+            output,
+        }
+        .run()
+    }
+}
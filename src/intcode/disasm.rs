@@ -0,0 +1,330 @@
+//! A disassembler, listing the instructions of a program with mnemonics,
+//! e.g. to reverse-engineer the puzzle inputs.
+//!
+//! Each line gives an address, then the mnemonic of the instruction there
+//! (`add`, `mul`, `in`, `out`, `jnz`, `jz`, `lt`, `eq`, `arb`, `hlt`) and
+//! its parameters, written `[5]` in position mode, `#3` in immediate mode
+//! and `~-2` in relative mode. Cells which are not valid instructions
+//! (unknown opcodes or modes, or instructions truncated by the end of the
+//! memory) are listed as `data`, a line for consecutive ones.
+//!
+//! The memory is decoded from start to end, so data looking like
+//! instructions is listed as instructions.
+//!
+//! Listings are valid source code for the assembler (see `asm`).
+
+use super::{
+    decode_opcode, Cell, Comparison, JumpCondition, OpCode, Operation, ParameterMode, Program,
+};
+use itertools::Itertools;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+/// How the disassembler lists the targets of jumps. See `disassemble_with`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Labels {
+    /// Jump targets are addresses, as any other parameter.
+    Addresses,
+
+    /// Jump targets known without running the program (immediate
+    /// parameters) get a label, `l` followed by their address, defined on
+    /// the line before the instruction they point to. Targets in the middle
+    /// of an instruction stay addresses.
+    JumpTargets,
+}
+
+/// What the disassembler knows of the opcodes: how to decode instruction
+/// codes, and the mnemonics. It uses `TABLE`; other tables are only useful
+/// to check `consistency_check_with` catches their mistakes.
+#[derive(Copy, Clone)]
+pub struct Table {
+    /// Decodes an instruction code into its opcode and its number of
+    /// parameters, or `None` if it's not a valid instruction.
+    pub decode: fn(Cell) -> Option<(OpCode, usize)>,
+
+    /// Returns the mnemonic of an opcode.
+    pub mnemonic: fn(OpCode) -> &'static str,
+}
+
+/// The table used by the disassembler, the same as the interpreter's and
+/// the assembler's.
+pub const TABLE: Table = Table {
+    decode: decode_opcode,
+    mnemonic,
+};
+
+/// A line of a listing, before it's written.
+pub(super) enum Line {
+    Instruction {
+        opcode: OpCode,
+        parameters: Vec<(ParameterMode, Cell)>,
+    },
+    Data(Cell),
+}
+
+impl Line {
+    /// Returns the target of a jump instruction, if known from the code.
+    pub(super) fn jump_target(&self) -> Option<Cell> {
+        match self {
+            Line::Instruction {
+                opcode: OpCode::Jump(_),
+                parameters,
+            } => match parameters.get(1) {
+                Some((ParameterMode::Immediate, target)) => Some(*target),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Writes the line as listed, without its address. The target of a
+    /// jump is written as a label if given.
+    pub(super) fn to_listing(&self, label: Option<usize>) -> String {
+        self.to_listing_with(label, &TABLE)
+    }
+
+    /// Writes the line as `to_listing`, with the mnemonics of the table.
+    pub(super) fn to_listing_with(&self, label: Option<usize>, table: &Table) -> String {
+        let mnemonic = table.mnemonic;
+        match self {
+            Line::Data(value) => format!("data {}", value),
+            Line::Instruction { opcode, parameters } => {
+                let operands = parameters
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (mode, value))| match (mode, label) {
+                        (ParameterMode::Immediate, Some(label)) if i == 1 => {
+                            format!("l{}", label)
+                        }
+                        (ParameterMode::Position, _) => format!("[{}]", value),
+                        (ParameterMode::Immediate, _) => format!("#{}", value),
+                        (ParameterMode::Relative, _) => format!("~{}", value),
+                    })
+                    .join(", ");
+
+                if operands.is_empty() {
+                    mnemonic(*opcode).to_string()
+                } else {
+                    format!("{} {}", mnemonic(*opcode), operands)
+                }
+            }
+        }
+    }
+}
+
+/// Returns the mnemonic of an opcode.
+pub(super) fn mnemonic(opcode: OpCode) -> &'static str {
+    match opcode {
+        OpCode::Arithmetic(Operation::Add) => "add",
+        OpCode::Arithmetic(Operation::Multiply) => "mul",
+        OpCode::Input => "in",
+        OpCode::Output => "out",
+        OpCode::Jump(JumpCondition::IfTrue) => "jnz",
+        OpCode::Jump(JumpCondition::IfFalse) => "jz",
+        OpCode::Test(Comparison::LessThan) => "lt",
+        OpCode::Test(Comparison::Equals) => "eq",
+        OpCode::AdjustRelativeBase => "arb",
+        OpCode::Halt => "hlt",
+        OpCode::Custom(_) => "custom",
+    }
+}
+
+/// Decodes the instruction at the start of the cells, with its length;
+/// or `None` if it's not a valid instruction.
+pub(super) fn decode(cells: &[Cell]) -> Option<(Line, usize)> {
+    decode_with(cells, &TABLE)
+}
+
+/// Decodes the instruction at the start of the cells as `decode`, with
+/// the table.
+pub(super) fn decode_with(cells: &[Cell], table: &Table) -> Option<(Line, usize)> {
+    let code = *cells.first()?;
+    let (opcode, parameters_count) = (table.decode)(code)?;
+
+    let mut modes = code / 100;
+    let mut parameters = Vec::with_capacity(parameters_count);
+    for parameter in cells.get(1..=parameters_count)? {
+        parameters.push((ParameterMode::from_digit(modes % 10)?, *parameter));
+        modes /= 10;
+    }
+
+    Some((
+        Line::Instruction { opcode, parameters },
+        parameters_count + 1,
+    ))
+}
+
+/// Decodes the memory from start to end with the table, into lines with
+/// their address.
+pub(super) fn decode_all(memory: &[Cell], table: &Table) -> Vec<(usize, Line)> {
+    let mut lines = vec![];
+    let mut address = 0;
+
+    while address < memory.len() {
+        match decode_with(&memory[address..], table) {
+            Some((line, length)) => {
+                lines.push((address, line));
+                address += length;
+            }
+            None => {
+                lines.push((address, Line::Data(memory[address])));
+                address += 1;
+            }
+        }
+    }
+
+    lines
+}
+
+///
+/// Lists the instructions in the memory of a program, one per line. See
+/// the module documentation for the format.
+///
+/// ```rust
+/// # use lib::intcode::catalog::{DAY2_EXAMPLE, DAY5_EQUAL_8_POSITION, DAY5_MODES, DAY9_QUINE};
+/// # use lib::intcode::disasm::disassemble;
+/// # use lib::intcode::{Cell, Program};
+/// let memory = |example: &lib::intcode::catalog::Example| -> Vec<Cell> {
+///     example.source_code.split(',').map(|cell| cell.parse().unwrap()).collect()
+/// };
+///
+/// assert_eq!(
+///     disassemble(&memory(&DAY2_EXAMPLE)),
+///     " 0: add [9], [10], [3]\n 4: mul [3], [11], [0]\n 8: hlt\n 9: data 30, 40, 50\n"
+/// );
+///
+/// assert_eq!(disassemble(&memory(&DAY5_MODES)), "0: mul [4], #3, [4]\n4: data 33\n");
+/// assert_eq!(
+///     disassemble(&memory(&DAY5_EQUAL_8_POSITION)),
+///     " 0: in [9]\n 2: eq [9], [10], [9]\n 6: out [9]\n 8: hlt\n 9: data -1, 8\n"
+/// );
+///
+/// let program: Program = DAY9_QUINE.source_code.parse().unwrap();
+/// assert_eq!(
+///     program.disassemble(),
+///     " 0: arb #1\n 2: out ~-1\n 4: add [100], #1, [100]\n 8: eq [100], #16, [101]\n\
+///      12: jz [101], #0\n15: hlt\n"
+/// );
+///
+/// // Truncated instructions are data.
+/// assert_eq!(disassemble(&[1101, 1]), "0: data 1101, 1\n");
+/// assert_eq!(disassemble(&[]), "");
+/// ```
+pub fn disassemble(memory: &[Cell]) -> String {
+    disassemble_with(memory, Labels::Addresses)
+}
+
+///
+/// Lists the instructions in the memory of a program, as `disassemble`,
+/// with labels for the jump targets if asked.
+///
+/// ```rust
+/// # use lib::intcode::asm::assemble;
+/// # use lib::intcode::disasm::{disassemble_with, Labels};
+/// let memory = assemble(
+///     "       in [n]
+///      loop:  jz [n], end
+///             out [n]
+///             add [n], #-1, [n]
+///             jnz #1, loop
+///      end:   hlt
+///      n:     data 0",
+/// )
+/// .unwrap();
+///
+/// let listing = disassemble_with(&memory, Labels::JumpTargets);
+/// assert_eq!(
+///     listing,
+///     " 0: in [15]\nl2:\n 2: jz [15], l14\n 5: out [15]\n 7: add [15], #-1, [15]\n\
+///      11: jnz #1, l2\nl14:\n14: hlt\n15: data 0\n"
+/// );
+/// assert_eq!(assemble(&listing).unwrap(), memory);
+/// ```
+pub fn disassemble_with(memory: &[Cell], labels: Labels) -> String {
+    let lines = decode_all(memory, &TABLE);
+
+    let targets: HashSet<usize> = match labels {
+        Labels::Addresses => HashSet::new(),
+        Labels::JumpTargets => lines
+            .iter()
+            .filter_map(|(_, line)| line.jump_target())
+            .filter_map(|target| usize::try_from(target).ok())
+            .filter(|target| {
+                lines
+                    .binary_search_by_key(target, |(address, _)| *address)
+                    .is_ok()
+            })
+            .collect(),
+    };
+
+    let width = memory.len().saturating_sub(1).to_string().len();
+    let mut listing = String::new();
+    let mut data: Vec<Cell> = vec![];
+    let mut data_address = 0;
+
+    let flush_data = |listing: &mut String, data: &mut Vec<Cell>, data_address| {
+        if !data.is_empty() {
+            listing.push_str(&format!(
+                "{:>width$}: data {}\n",
+                data_address,
+                data.iter().join(", "),
+                width = width
+            ));
+            data.clear();
+        }
+    };
+
+    for (address, line) in &lines {
+        if targets.contains(address) {
+            flush_data(&mut listing, &mut data, data_address);
+            listing.push_str(&format!("l{}:\n", address));
+        }
+
+        match line {
+            Line::Data(value) => {
+                if data.is_empty() {
+                    data_address = *address;
+                }
+                data.push(*value);
+            }
+            Line::Instruction { .. } => {
+                flush_data(&mut listing, &mut data, data_address);
+
+                let label = line
+                    .jump_target()
+                    .and_then(|target| usize::try_from(target).ok())
+                    .filter(|target| targets.contains(target));
+
+                listing.push_str(&format!(
+                    "{:>width$}: {}\n",
+                    address,
+                    line.to_listing(label),
+                    width = width
+                ));
+            }
+        }
+    }
+
+    flush_data(&mut listing, &mut data, data_address);
+    listing
+}
+
+impl Program {
+    /// Lists the instructions in the memory of the program, as
+    /// `disasm::disassemble`.
+    pub fn disassemble(&self) -> String {
+        disassemble(self.memory())
+    }
+
+    /// Lists the instruction at the address, without its address, and
+    /// returns its length; a single data cell if it's not a valid
+    /// instruction.
+    pub(super) fn listing_at(&self, address: usize) -> (String, usize) {
+        let cells = self.get_range(address, 4);
+
+        match decode(&cells) {
+            Some((line, length)) => (line.to_listing(None), length),
+            None => (Line::Data(cells[0]).to_listing(None), 1),
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! Expressions on the state of a program, to watch cells addressed
+//! relatively to the relative base or the instruction pointer.
+//!
+//! The syntax is made of `rb` (the relative base), `ip` (the instruction
+//! pointer), integer literals, `+` and `-`, and `[...]` to read the memory
+//! at an address.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! let mut program: Program = "109,4,204,-1,99".parse().unwrap();
+//! program.execute().unwrap();
+//!
+//! assert_eq!(program.watch_expr("rb").unwrap(), 4);
+//! assert_eq!(program.watch_expr("[rb - 1]").unwrap(), -1);
+//! assert_eq!(program.watch_expr("[[ip - 2] + 3]").unwrap(), 204);
+//! ```
+
+use super::{Cell, Error, Program, Result};
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::str::{Chars, FromStr};
+
+/// A parsed expression. See the module documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    RelativeBase,
+    Pointer,
+    Literal(Cell),
+
+    /// The value in memory at the address given by the expression.
+    Deref(Box<Expr>),
+
+    Add(Box<Expr>, Box<Expr>),
+    Subtract(Box<Expr>, Box<Expr>),
+}
+
+impl FromStr for Expr {
+    type Err = Error;
+
+    ///
+    /// Parses an expression.
+    ///
+    /// ```rust
+    /// # use lib::intcode::expr::Expr;
+    /// assert_eq!(
+    ///     "rb+3".parse::<Expr>().unwrap(),
+    ///     Expr::Add(Box::new(Expr::RelativeBase), Box::new(Expr::Literal(3)))
+    /// );
+    /// assert_eq!(
+    ///     "[ [17] ]".parse::<Expr>().unwrap(),
+    ///     Expr::Deref(Box::new(Expr::Deref(Box::new(Expr::Literal(17)))))
+    /// );
+    /// assert_eq!("-2 - -3".parse::<Expr>().unwrap().to_string(), "-2 - -3");
+    ///
+    /// assert_eq!("rb +".parse::<Expr>().unwrap_err().message(), "Expected a value");
+    /// assert_eq!("[rb".parse::<Expr>().unwrap_err().message(), "Expected a closing bracket");
+    /// assert_eq!("rb 3".parse::<Expr>().unwrap_err().message(), "Unexpected trailing characters");
+    /// assert_eq!("sp".parse::<Expr>().unwrap_err().message(), "Unknown register");
+    /// assert_eq!("".parse::<Expr>().unwrap_err().message(), "Expected a value");
+    /// ```
+    fn from_str(expr: &str) -> Result<Self> {
+        let mut chars = expr.chars().peekable();
+        let parsed = parse_sum(&mut chars)?;
+
+        skip_spaces(&mut chars);
+        match chars.peek() {
+            None => Ok(parsed),
+            Some(_) => Err(Error::ParseError("Unexpected trailing characters")),
+        }
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::RelativeBase => write!(f, "rb"),
+            Expr::Pointer => write!(f, "ip"),
+            Expr::Literal(value) => write!(f, "{}", value),
+            Expr::Deref(address) => write!(f, "[{}]", address),
+            Expr::Add(a, b) => write!(f, "{} + {}", a, b),
+            Expr::Subtract(a, b) => write!(f, "{} - {}", a, b),
+        }
+    }
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.peek().map(|c| c.is_whitespace()) == Some(true) {
+        chars.next();
+    }
+}
+
+/// Parses values separated by `+` or `-`.
+fn parse_sum(chars: &mut Peekable<Chars>) -> Result<Expr> {
+    let mut expr = parse_value(chars)?;
+
+    loop {
+        skip_spaces(chars);
+
+        match chars.peek() {
+            Some('+') => {
+                chars.next();
+                expr = Expr::Add(Box::new(expr), Box::new(parse_value(chars)?));
+            }
+            Some('-') => {
+                chars.next();
+                expr = Expr::Subtract(Box::new(expr), Box::new(parse_value(chars)?));
+            }
+            _ => break Ok(expr),
+        }
+    }
+}
+
+/// Parses a register, a literal or a dereference.
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Expr> {
+    skip_spaces(chars);
+
+    match chars.peek() {
+        Some('[') => {
+            chars.next();
+            let address = parse_sum(chars)?;
+
+            skip_spaces(chars);
+            match chars.next() {
+                Some(']') => Ok(Expr::Deref(Box::new(address))),
+                _ => Err(Error::ParseError("Expected a closing bracket")),
+            }
+        }
+        Some(c) if c.is_ascii_digit() || *c == '-' => {
+            let mut literal = chars.next().unwrap().to_string();
+            while let Some(digit) = chars.peek().filter(|c| c.is_ascii_digit()) {
+                literal.push(*digit);
+                chars.next();
+            }
+
+            literal
+                .parse()
+                .map(Expr::Literal)
+                .map_err(|_| Error::ParseError("Invalid number"))
+        }
+        Some(c) if c.is_ascii_alphabetic() => {
+            let mut register = String::new();
+            while let Some(c) = chars.peek().filter(|c| c.is_ascii_alphanumeric()) {
+                register.push(*c);
+                chars.next();
+            }
+
+            match register.as_str() {
+                "rb" => Ok(Expr::RelativeBase),
+                "ip" => Ok(Expr::Pointer),
+                _ => Err(Error::ParseError("Unknown register")),
+            }
+        }
+        _ => Err(Error::ParseError("Expected a value")),
+    }
+}
+
+impl Expr {
+    ///
+    /// Evaluates the expression against the current state of a program.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{expr::Expr, Cell, Program};
+    /// let program: Program = "1,2,3".parse().unwrap();
+    /// let expr = |e: &str| e.parse::<Expr>().unwrap();
+    ///
+    /// assert_eq!(expr("[[1]]").evaluate(&program).unwrap(), 3);
+    /// assert_eq!(expr("[ip + 100]").evaluate(&program).unwrap(), 0);
+    /// assert_eq!(expr("[ip - 1]").evaluate(&program).unwrap_err().message(), "Negative address");
+    ///
+    /// let max = Cell::MAX.to_string();
+    /// let overflow = expr(&format!("rb + 1 + {}", max)).evaluate(&program);
+    /// assert_eq!(overflow.unwrap_err().message(), "Arithmetic overflow");
+    /// let overflow = expr(&format!("-2 - {}", max)).evaluate(&program);
+    /// assert_eq!(overflow.unwrap_err().message(), "Arithmetic overflow");
+    ///
+    /// // The relative base may be negative.
+    /// let mut program: Program = "109,-3,99".parse().unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(expr("rb").evaluate(&program).unwrap(), -3);
+    /// assert_eq!(expr("[rb + 4]").evaluate(&program).unwrap(), -3);
+    /// assert_eq!(expr("[rb]").evaluate(&program).unwrap_err().message(), "Negative address");
+    /// ```
+    pub fn evaluate(&self, program: &Program) -> Result<Cell> {
+        match self {
+            Expr::RelativeBase => Ok(program.relative_base as Cell),
+            Expr::Pointer => Ok(program.pointer as Cell),
+            Expr::Literal(value) => Ok(*value),
+            Expr::Deref(address) => match address.evaluate(program)? {
+                address if address < 0 => Err(Error::InvalidAddress("Negative address")),
+                address => usize::try_from(address)
+                    .map(|address| program.get(address))
+                    .map_err(|_| Error::InvalidAddress("Address too large")),
+            },
+            Expr::Add(a, b) => a
+                .evaluate(program)?
+                .checked_add(b.evaluate(program)?)
+                .ok_or(Error::Overflow),
+            Expr::Subtract(a, b) => a
+                .evaluate(program)?
+                .checked_sub(b.evaluate(program)?)
+                .ok_or(Error::Overflow),
+        }
+    }
+}
+
+impl Program {
+    /// Parses an expression and evaluates it against the current state
+    /// of the program. See the `expr` module.
+    pub fn watch_expr(&self, expr: &str) -> Result<Cell> {
+        expr.parse::<Expr>()?.evaluate(self)
+    }
+}
@@ -0,0 +1,104 @@
+//! Counters of memory accesses, to find the cells a program uses the most,
+//! e.g. its variables and loop counters.
+//!
+//! ```rust
+//! # use lib::intcode::heatmap::format_heatmap;
+//! # use lib::intcode::Program;
+//! // Counts from 0 to 100 in cell 20, checking the count in cell 21.
+//! let mut program: Program = "1001,20,1,20,1008,20,100,21,1006,21,0,99".parse().unwrap();
+//! program.enable_heatmap(1000);
+//! program.execute().unwrap();
+//!
+//! let heatmap = program.memory_heatmap();
+//! assert_eq!(heatmap[0], (20, 200, 100));
+//! assert_eq!(heatmap[1], (21, 100, 100));
+//!
+//! assert_eq!(
+//!     format_heatmap(&heatmap, 1),
+//!     "address      reads     writes\n     20        200        100\n"
+//! );
+//! ```
+
+use super::Program;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+/// The reads and writes counted by address, for at most a given number
+/// of addresses.
+#[derive(Debug, Clone)]
+pub(super) struct Heatmap {
+    accesses: BTreeMap<usize, (u64, u64)>,
+    max_addresses: usize,
+}
+
+impl Heatmap {
+    /// Counts an access, unless the address is not tracked yet and the
+    /// maximal number of addresses is reached.
+    fn count(&mut self, address: usize, reads: u64, writes: u64) {
+        if self.accesses.len() < self.max_addresses || self.accesses.contains_key(&address) {
+            let counters = self.accesses.entry(address).or_insert((0, 0));
+            counters.0 += reads;
+            counters.1 += writes;
+        }
+    }
+
+    pub(super) fn record_read(&mut self, address: usize) {
+        self.count(address, 1, 0);
+    }
+
+    pub(super) fn record_write(&mut self, address: usize) {
+        self.count(address, 0, 1);
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.accesses.clear();
+    }
+}
+
+impl Program {
+    /// Enables the counting of memory reads and writes by address, for at
+    /// most `max_addresses` addresses: once reached, accesses to other
+    /// addresses are not counted. Instructions are not counted as reads,
+    /// only their parameters. The counters start over when the program
+    /// restarts.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1101,1,1,100,1101,2,2,200,99".parse().unwrap();
+    /// program.enable_heatmap(1);
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(program.memory_heatmap(), vec![(100, 0, 1)]);
+    /// ```
+    pub fn enable_heatmap(&mut self, max_addresses: usize) {
+        self.heatmap = Some(Heatmap {
+            accesses: BTreeMap::new(),
+            max_addresses,
+        });
+    }
+
+    /// Returns the `(address, reads, writes)` counters of the memory, the
+    /// cells accessed the most first. Empty if the heatmap is not enabled.
+    pub fn memory_heatmap(&self) -> Vec<(usize, u64, u64)> {
+        let mut heatmap: Vec<(usize, u64, u64)> = self
+            .heatmap
+            .iter()
+            .flat_map(|heatmap| heatmap.accesses.iter())
+            .map(|(address, (reads, writes))| (*address, *reads, *writes))
+            .collect();
+
+        heatmap.sort_by_key(|(address, reads, writes)| (Reverse(reads + writes), *address));
+        heatmap
+    }
+}
+
+/// Formats the `top` first cells of a heatmap as a table.
+pub fn format_heatmap(heatmap: &[(usize, u64, u64)], top: usize) -> String {
+    let mut table = format!("{:>7} {:>10} {:>10}\n", "address", "reads", "writes");
+
+    for (address, reads, writes) in heatmap.iter().take(top) {
+        table.push_str(&format!("{:>7} {:>10} {:>10}\n", address, reads, writes));
+    }
+
+    table
+}
@@ -0,0 +1,266 @@
+//! Input sources for the Input opcode, given to `Program::set_input_source`.
+//!
+//! ```rust
+//! # use lib::intcode::inputs::QueueInput;
+//! # use lib::intcode::Program;
+//! // Outputs the sum of its two inputs.
+//! let mut program: Program = "3,11,3,12,1,11,12,13,4,13,99,0,0,0".parse().unwrap();
+//! program.set_input_source(QueueInput::new(&[40, 2]));
+//! assert_eq!(program.execute().unwrap(), vec![42]);
+//! ```
+
+use super::{Cell, Error, Result};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+use std::sync::mpsc::Receiver;
+
+/// A source of inputs for a program.
+pub trait InputSource {
+    /// Returns the next input. `request_index` is the number of inputs
+    /// the program requested before this one, starting at zero.
+    fn next(&mut self, request_index: usize) -> Result<Cell>;
+
+    /// Checks if an input can be given right away. Executions pausing on
+    /// input instructions use the source only then, and else pause as if
+    /// no input was queued. By default, sources are never ready.
+    fn is_ready(&self) -> bool {
+        false
+    }
+}
+
+/// Closures receiving the request index are input sources too.
+///
+/// ```rust
+/// # use lib::intcode::{Cell, Program};
+/// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+/// program.set_input_source(|n| Ok(n as Cell + 42));
+/// assert_eq!(program.execute().unwrap(), vec![42]);
+/// ```
+impl<F: FnMut(usize) -> Result<Cell>> InputSource for F {
+    fn next(&mut self, request_index: usize) -> Result<Cell> {
+        self(request_index)
+    }
+}
+
+/// Gives the same input, each time.
+///
+/// ```rust
+/// # use lib::intcode::inputs::ConstantInput;
+/// # use lib::intcode::Program;
+/// // Outputs its two inputs.
+/// let mut program: Program = "3,9,4,9,3,9,4,9,99,0".parse().unwrap();
+/// program.set_input_source(ConstantInput(7));
+/// assert_eq!(program.execute().unwrap(), vec![7, 7]);
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct ConstantInput(pub Cell);
+
+impl InputSource for ConstantInput {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        Ok(self.0)
+    }
+}
+
+/// Gives the inputs of a queue, in order, then fails.
+///
+/// ```rust
+/// # use lib::intcode::inputs::QueueInput;
+/// # use lib::intcode::Program;
+/// // Outputs its two inputs.
+/// let mut program: Program = "3,9,4,9,3,9,4,9,99,0".parse().unwrap();
+/// program.set_input_source(QueueInput::new(&[1, 2]));
+/// assert_eq!(program.execute().unwrap(), vec![1, 2]);
+///
+/// program.set_input_source(QueueInput::new(&[1]));
+/// assert_eq!(program.execute().unwrap_err().message(), "No more inputs");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct QueueInput(pub VecDeque<Cell>);
+
+impl QueueInput {
+    pub fn new(inputs: &[Cell]) -> Self {
+        QueueInput(inputs.iter().cloned().collect())
+    }
+}
+
+impl InputSource for QueueInput {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        self.0
+            .pop_front()
+            .ok_or(Error::InvalidInput("No more inputs"))
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_empty()
+    }
+}
+
+/// Reads the next number of a reader, from the words of the last line read,
+/// or else from the next lines.
+fn read_number(reader: &mut impl BufRead, words: &mut VecDeque<String>) -> Result<Cell> {
+    while words.is_empty() {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::InvalidInput("Unexpected end of input"));
+        }
+
+        words.extend(line.split_whitespace().map(String::from));
+    }
+
+    Ok(words.pop_front().unwrap().parse()?)
+}
+
+/// Reads the inputs from a reader, one number at a time, separated by
+/// spaces or new lines.
+///
+/// ```rust
+/// # use lib::intcode::inputs::ReaderInput;
+/// # use lib::intcode::Program;
+/// # use std::io::Cursor;
+/// // Outputs its three inputs.
+/// let mut program: Program = "3,13,4,13,3,13,4,13,3,13,4,13,99,0".parse().unwrap();
+/// program.set_input_source(ReaderInput::new(Cursor::new("3  -4\n\n 5 \n")));
+/// assert_eq!(program.execute().unwrap(), vec![3, -4, 5]);
+///
+/// program.set_input_source(ReaderInput::new(Cursor::new("3\nthree\n")));
+/// assert_eq!(program.execute().unwrap_err().message(), "Invalid number");
+///
+/// program.reset();
+/// program.set_input_source(ReaderInput::new(Cursor::new("3 4")));
+/// assert_eq!(program.execute().unwrap_err().message(), "Unexpected end of input");
+/// ```
+#[derive(Debug)]
+pub struct ReaderInput<R: BufRead> {
+    reader: R,
+
+    /// The words of the last line read, not read yet.
+    words: VecDeque<String>,
+}
+
+impl<R: BufRead> ReaderInput<R> {
+    pub fn new(reader: R) -> Self {
+        ReaderInput {
+            reader,
+            words: VecDeque::new(),
+        }
+    }
+}
+
+impl<R: BufRead> InputSource for ReaderInput<R> {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        read_number(&mut self.reader, &mut self.words)
+    }
+}
+
+/// Reads the inputs from stdin, as `ReaderInput`. Stdin is only locked
+/// while an input is read.
+///
+/// ```rust,no_run
+/// # use lib::intcode::inputs::StdinInput;
+/// # use lib::intcode::Program;
+/// // Outputs its input.
+/// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+/// program.set_input_source(StdinInput::default());
+/// println!("{:?}", program.execute().unwrap());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct StdinInput {
+    /// The words of the last line read, not read yet.
+    words: VecDeque<String>,
+}
+
+impl InputSource for StdinInput {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        read_number(&mut io::stdin().lock(), &mut self.words)
+    }
+}
+
+/// Gives the numbers of a file, in order, then fails. The file is read
+/// once, when opened: a number per line, skipping blank lines and those
+/// starting with `#`. See `Program::set_input_file`.
+#[derive(Debug, Clone)]
+pub struct FileInput(VecDeque<Cell>);
+
+impl FileInput {
+    /// Reads the numbers of a file. Fails if a line is not a number,
+    /// naming this line.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref();
+
+        fs::read_to_string(path)?
+            .lines()
+            .enumerate()
+            .map(|(index, line)| (index + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, line)| {
+                line.parse().map_err(|_| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "{}, line {}: not a number: {}",
+                            path.display(),
+                            number,
+                            line
+                        ),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()
+            .map(FileInput)
+    }
+}
+
+impl InputSource for FileInput {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        self.0
+            .pop_front()
+            .ok_or(Error::InvalidInput("No more inputs in the file"))
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.0.is_empty()
+    }
+}
+
+/// Receives the inputs from a channel, waiting for each of them. Fails
+/// once the channel is closed, e.g. to connect programs running in
+/// different threads.
+///
+/// ```rust
+/// # use lib::intcode::inputs::ChannelInput;
+/// # use lib::intcode::Program;
+/// # use std::sync::mpsc::channel;
+/// # use std::thread;
+/// let (tx, rx) = channel();
+///
+/// let program = thread::spawn(move || {
+///     // Outputs its two inputs.
+///     let mut program: Program = "3,9,4,9,3,9,4,9,99,0".parse().unwrap();
+///     program.set_input_source(ChannelInput(rx));
+///     program.execute().unwrap()
+/// });
+///
+/// tx.send(4).unwrap();
+/// tx.send(2).unwrap();
+/// assert_eq!(program.join().unwrap(), vec![4, 2]);
+///
+/// let (tx, rx) = channel();
+/// drop(tx);
+///
+/// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+/// program.set_input_source(ChannelInput(rx));
+/// assert_eq!(program.execute().unwrap_err().message(), "Cannot receive input");
+/// ```
+#[derive(Debug)]
+pub struct ChannelInput(pub Receiver<Cell>);
+
+impl InputSource for ChannelInput {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        self.0
+            .recv()
+            .map_err(|_| Error::InvalidInput("Cannot receive input"))
+    }
+}
@@ -0,0 +1,72 @@
+//! Interactive sessions with ASCII programs, in a terminal.
+
+use super::{ascii_text, Cell, Error, Event, Program, Result};
+use std::io::{self, BufRead, Write};
+
+impl Program {
+    /// Runs an ASCII program in the terminal, as `run_interactive_with`
+    /// with stdin and stdout.
+    pub fn run_interactive(&mut self) -> Result<Vec<Cell>> {
+        self.run_interactive_with(io::stdin().lock(), io::stdout())
+    }
+
+    ///
+    /// Runs an ASCII program interactively: its outputs are written as they
+    /// happen, as characters (or as numbers on their own lines, if not
+    /// ASCII), and each time it needs an input, a line is read and given
+    /// to it, followed by a new line. Returns the outputs of the program
+    /// when it halts.
+    ///
+    /// At the end of the input, the program stops and stays paused on its
+    /// input instruction; the outputs so far are returned.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{ExecutionStatus, Program};
+    /// # use std::io::Cursor;
+    /// // Prompts, echoes a line, then outputs 1000.
+    /// let source = "104,62,104,10,3,100,4,100,1008,100,10,101,1006,101,4,104,1000,99";
+    /// let mut program: Program = source.parse().unwrap();
+    /// let mut terminal = vec![];
+    ///
+    /// let outputs = program
+    ///     .run_interactive_with(Cursor::new("hi\n"), &mut terminal)
+    ///     .unwrap();
+    /// assert_eq!(String::from_utf8(terminal).unwrap(), ">\nhi\n1000\n");
+    /// assert_eq!(outputs.len(), 6);
+    ///
+    /// let mut program: Program = source.parse().unwrap();
+    /// program.run_interactive_with(Cursor::new(""), vec![]).unwrap();
+    /// assert_eq!(program.status(), ExecutionStatus::WaitingForInput);
+    /// ```
+    pub fn run_interactive_with(
+        &mut self,
+        mut input: impl BufRead,
+        mut output: impl Write,
+    ) -> Result<Vec<Cell>> {
+        let write_error = |_| Error::InvalidOutput("Cannot write output");
+
+        loop {
+            match self.run_until_event()? {
+                Event::Output(value) => output
+                    .write_all(ascii_text(value).as_bytes())
+                    .map_err(write_error)?,
+                Event::NeedsInput => {
+                    output.flush().map_err(write_error)?;
+
+                    let mut line = String::new();
+                    if input.read_line(&mut line)? == 0 {
+                        break;
+                    }
+
+                    let line = line.trim_end_matches(&['\r', '\n'][..]);
+                    self.push_inputs(line.bytes().map(Cell::from));
+                    self.push_input(10);
+                }
+                Event::Halted | Event::Stopped(_) => break,
+            }
+        }
+
+        output.flush().map_err(write_error)?;
+        Ok(self.output().to_vec())
+    }
+}
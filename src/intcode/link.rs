@@ -0,0 +1,96 @@
+//! Links between programs, the outputs of one being the inputs of another,
+//! on a single thread.
+
+use super::inputs::InputSource;
+use super::outputs::OutputSink;
+use super::{Cell, Error, Program, Result};
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+/// A queue of values outputted by a program and not yet read by the
+/// program it's connected to. See `connect`.
+#[derive(Debug, Clone, Default)]
+pub struct Link(Rc<RefCell<VecDeque<Cell>>>);
+
+impl Link {
+    /// Adds a value to the queue, e.g. to start a chain of programs.
+    pub fn push(&self, value: Cell) {
+        self.0.borrow_mut().push_back(value);
+    }
+
+    /// Returns the values waiting to be read, in order.
+    pub fn values(&self) -> Vec<Cell> {
+        self.0.borrow().iter().cloned().collect()
+    }
+
+    /// Returns the number of values waiting to be read.
+    pub fn len(&self) -> usize {
+        self.0.borrow().len()
+    }
+
+    /// Checks if no value is waiting to be read.
+    pub fn is_empty(&self) -> bool {
+        self.0.borrow().is_empty()
+    }
+}
+
+impl OutputSink for Link {
+    fn emit(&mut self, value: Cell) -> Result<()> {
+        self.push(value);
+        Ok(())
+    }
+}
+
+impl InputSource for Link {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        self.0
+            .borrow_mut()
+            .pop_front()
+            .ok_or(Error::InvalidInput("No more inputs"))
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.is_empty()
+    }
+}
+
+///
+/// Connects the outputs of a program to the inputs of another, through a
+/// queue: the link becomes the output sink of the producer, and the input
+/// source of the consumer. When the queue is empty, the consumer pauses
+/// on its input instruction, with `execute_until_input_needed` or
+/// `run_until_event`, until the producer outputs something.
+///
+/// ```rust
+/// # use lib::intcode::{connect, ExecutionStatus, Program};
+/// // Receives a number, outputs it plus one, until it's at least 10.
+/// let source = "3,20,1001,20,1,20,4,20,1007,20,10,21,1005,21,0,99,0,0,0,0,0,0";
+/// let mut ping: Program = source.parse().unwrap();
+/// let mut pong: Program = source.parse().unwrap();
+///
+/// let ping_to_pong = connect(&mut ping, &mut pong);
+/// let pong_to_ping = connect(&mut pong, &mut ping);
+/// pong_to_ping.push(0);
+///
+/// while ping.status() != ExecutionStatus::Halted || pong.status() != ExecutionStatus::Halted {
+///     for program in [&mut ping, &mut pong] {
+///         if program.status() != ExecutionStatus::Halted {
+///             program.execute_until_input_needed().unwrap();
+///         }
+///     }
+/// }
+///
+/// assert_eq!((ping.get(20), pong.get(20)), (11, 10));
+/// assert_eq!((ping.outputs_produced(), pong.outputs_produced()), (6, 5));
+/// assert_eq!(ping_to_pong.values(), vec![11]);
+/// assert!(pong_to_ping.is_empty());
+/// ```
+pub fn connect(producer: &mut Program, consumer: &mut Program) -> Link {
+    let link = Link::default();
+
+    producer.set_output_sink(link.clone());
+    consumer.set_input_source(link.clone());
+
+    link
+}
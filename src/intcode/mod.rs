@@ -0,0 +1,4351 @@
+//! The Intcode interpreter, used by days 2, 5, 7 and 9.
+//!
+//! # Running a program
+//!
+//! Programs are parsed from their comma-separated source code, can be patched
+//! before being executed, and their memory inspected afterwards.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! let mut program: Program = "1,9,10,3,2,3,11,0,99,30,40,50".parse().unwrap();
+//! program.patch(10, 41).unwrap();
+//! program.execute().unwrap();
+//!
+//! assert_eq!(program.get(0), 3550);
+//! ```
+//!
+//! # Inputs and outputs
+//!
+//! Inputs are given by a closure receiving the index of the requested input;
+//! `execute` returns everything the program outputted.
+//!
+//! ```rust
+//! # use lib::intcode::{Error, Program};
+//! // Outputs 999, 1000 or 1001 if the input is below, equal or above 8.
+//! let mut program: Program = lib::intcode::catalog::DAY5_COMPARE_TO_8.source_code.parse().unwrap();
+//! program.set_input(|n| match n {
+//!     0 => Ok(8),
+//!     _ => Err(Error::InvalidInput("Only one input expected")),
+//! });
+//!
+//! assert_eq!(program.execute().unwrap(), vec![1000]);
+//! ```
+//!
+//! # Pausing and resuming
+//!
+//! A program can be driven as a coroutine with `Program::run_until_event`,
+//! which returns each time the program outputs a value, needs an input, or
+//! halts. It can also be executed one output at a time, resuming where it
+//! left.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! let mut program: Program = "104,1,104,2,99".parse().unwrap();
+//!
+//! assert_eq!(program.execute_until_next_output().unwrap(), Some(1));
+//! assert!(program.is_running());
+//! assert_eq!(program.execute_until_next_output().unwrap(), Some(2));
+//! ```
+//!
+//! # Reading outputs as tuples
+//!
+//! Programs drawing things often output fixed-size groups of values.
+//!
+//! ```rust
+//! # use lib::intcode::{tuples, Program};
+//! let mut program: Program = "104,1,104,2,104,3,104,4,99".parse().unwrap();
+//! let points: Vec<_> = tuples::<2>(&mut program).map(Result::unwrap).collect();
+//!
+//! assert_eq!(points, vec![[1, 2], [3, 4]]);
+//! ```
+
+use debug::{Breakpoint, OutputBreak, Stopped};
+use inputs::{ChannelInput, FileInput, InputSource};
+use itertools::Itertools;
+use outputs::{ChannelOutput, ClosedReceiver, OutputSink};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Write};
+use std::num::ParseIntError;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+use std::sync::mpsc::{Receiver, Sender};
+
+pub mod analysis;
+pub mod asm;
+pub mod catalog;
+pub mod consistency;
+pub mod coverage;
+pub mod debug;
+pub mod debugger;
+pub mod disasm;
+pub mod expr;
+pub mod heatmap;
+pub mod inputs;
+pub mod interactive;
+pub mod link;
+pub mod opcodes;
+pub mod outputs;
+pub mod profile;
+pub mod rewind;
+pub mod scope;
+pub mod script;
+#[cfg(feature = "serde")]
+pub mod state;
+pub mod testing;
+pub mod trace;
+pub mod validation;
+pub mod view;
+
+pub use consistency::{consistency_check, self_test, Inconsistency};
+pub use link::connect;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The default memory limit of programs, in cells. See `set_memory_limit`.
+const DEFAULT_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+
+thread_local! {
+    /// Whether the programs created on this thread use sparse memory. See
+    /// `set_sparse_memory_default`.
+    static SPARSE_MEMORY_DEFAULT: std::cell::Cell<bool> = const { std::cell::Cell::new(false) };
+}
+
+///
+/// Makes the programs created from now on, on this thread, use sparse
+/// memory (see `Program::with_sparse_memory`), or not. That's to run code
+/// creating its own programs, like the days, with either memory.
+///
+/// ```rust
+/// # use lib::intcode::catalog::STAND_INS;
+/// # use lib::intcode::{set_sparse_memory_default, Program};
+/// # use lib::solve_day;
+/// # use std::fs;
+/// set_sparse_memory_default(true);
+/// let mut program: Program = "1101,1,1,1000000000,99".parse().unwrap();
+/// program.execute().unwrap();
+/// assert_eq!(program.memory_usage().sparse_cells, 1);
+///
+/// // The answers of the days are the same with both memories, with the
+/// // stand-ins, and with the real inputs if present.
+/// let real_inputs: Vec<(u8, String)> = [2, 5, 7, 9]
+///     .iter()
+///     .filter_map(|day| {
+///         let input = fs::read_to_string(format!("input/day-{}.txt", day)).ok()?;
+///         Some((*day, input))
+///     })
+///     .collect();
+/// let inputs = STAND_INS
+///     .iter()
+///     .cloned()
+///     .chain(real_inputs.iter().map(|(day, input)| (*day, input.as_str())));
+///
+/// for (day, input) in inputs {
+///     set_sparse_memory_default(false);
+///     let dense = solve_day(day, input).unwrap();
+///     set_sparse_memory_default(true);
+///     let sparse = solve_day(day, input).unwrap();
+///
+///     assert_eq!(dense, sparse, "day {}", day);
+/// }
+/// set_sparse_memory_default(false);
+/// ```
+pub fn set_sparse_memory_default(enabled: bool) {
+    SPARSE_MEMORY_DEFAULT.with(|sparse| sparse.set(enabled));
+}
+
+/// The default number of instructions remembered by programs, for errors.
+/// See `set_history_depth`.
+const DEFAULT_HISTORY_DEPTH: usize = 8;
+
+thread_local! {
+    /// The number of instructions remembered by the programs created on
+    /// this thread. See `set_history_depth_default`.
+    static HISTORY_DEPTH_DEFAULT: std::cell::Cell<usize> =
+        const { std::cell::Cell::new(DEFAULT_HISTORY_DEPTH) };
+}
+
+///
+/// Sets how many of the last instructions executed the programs created
+/// from now on, on this thread, remember for their errors (see
+/// `Program::set_history_depth`). That's for the failure reports of code
+/// creating its own programs, like the days.
+///
+/// ```rust
+/// # use lib::intcode::set_history_depth_default;
+/// # use lib::solve_day;
+/// set_history_depth_default(2);
+/// // Reads the system ID, adds 1 and 1, then fails on an unknown opcode.
+/// let error = solve_day(5, "3,7,1101,1,1,8,42,0,0").unwrap_err();
+/// assert_eq!(
+///     error.message,
+///     "Unexpected opcode 42 (memory from address 3: 1,1,8,42,1,2) at address 6, in instruction 42\n\
+///      Last instructions executed:\n  0: 3  -> [7] = 1\n  2: 1101 1,1 -> [8] = 2\n\
+///      Memory usage: 9 cells loaded, 9 allocated, 0 sparse; highest address written: 8"
+/// );
+///
+/// set_history_depth_default(1);
+/// let error = solve_day(5, "3,7,1101,1,1,8,42,0,0").unwrap_err();
+/// assert!(error.message.contains("executed:\n  2: 1101 1,1 -> [8] = 2\nMemory usage"));
+///
+/// set_history_depth_default(0);
+/// let error = solve_day(5, "3,7,1101,1,1,8,42,0,0").unwrap_err();
+/// assert!(!error.message.contains('\n'));
+/// set_history_depth_default(8);
+/// ```
+pub fn set_history_depth_default(depth: usize) {
+    HISTORY_DEPTH_DEFAULT.with(|default| default.set(depth));
+}
+
+/// The default number of outputs programs can accumulate. See `set_max_outputs`.
+const DEFAULT_MAX_OUTPUTS: usize = 10_000_000;
+
+/// The type of a memory cell, and of every value read or written
+/// by a program.
+#[cfg(not(feature = "wide-cells"))]
+pub type Cell = i64;
+
+/// The type of a memory cell, and of every value read or written
+/// by a program.
+///
+/// With the `wide-cells` feature, cells are 128-bits wide, for programs
+/// whose intermediate values exceed 64 bits.
+#[cfg_attr(
+    feature = "wide-cells",
+    doc = r#"
+```rust
+# use lib::intcode::Program;
+let mut program: Program = "1002,5,281474976710656,5,99,281474976710656".parse().unwrap();
+program.execute().unwrap();
+
+assert_eq!(program.get(5), 79_228_162_514_264_337_593_543_950_336);
+```
+"#
+)]
+#[cfg(feature = "wide-cells")]
+pub type Cell = i128;
+
+/// An error while parsing or running a program. It implements
+/// `std::error::Error`, so it converts into a `Box<dyn Error>` with `?`.
+///
+/// ```rust
+/// # use lib::intcode::Program;
+/// # use std::error::Error;
+/// fn sum(a: &str, b: &str) -> Result<i64, Box<dyn Error>> {
+///     // Outputs the sum of its two inputs.
+///     let mut program: Program = "3,11,3,12,1,11,12,13,4,13,99,0,0,0".parse()?;
+///     program.push_inputs(vec![a.parse()?, b.parse()?]);
+///     Ok(*program.execute()?.first().ok_or("No output")? as i64)
+/// }
+///
+/// assert_eq!(sum("40", "2").unwrap(), 42);
+/// assert_eq!(sum("40", "two").unwrap_err().to_string(), "invalid digit found in string");
+/// assert_eq!(
+///     sum("40", "").unwrap_err().to_string(),
+///     "cannot parse integer from empty string"
+/// );
+/// ```
+///
+/// ```rust
+/// # use lib::intcode::{Error, Program};
+/// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+/// let error = program.execute().unwrap_err();
+///
+/// assert!(matches!(error.cause(), Error::InvalidInput(_)));
+/// assert_eq!(error.message(), "No more inputs");
+/// assert_eq!(error.to_string(), "No more inputs at address 0, in instruction 3,5");
+///
+/// let error = "1,x".parse::<Program>().err().unwrap();
+/// assert!(matches!(error, Error::InvalidToken { index: 1, .. }));
+///
+/// let mut program: Program = "42".parse().unwrap();
+/// assert!(matches!(
+///     program.execute().unwrap_err().cause(),
+///     Error::UnknownOpcode { opcode: 42, .. }
+/// ));
+/// ```
+///
+/// Errors while running an instruction carry its context:
+///
+/// ```rust
+/// # use lib::intcode::{Cell, Error, Program};
+/// let context = |source_code: &str| {
+///     let mut program: Program = source_code.parse().unwrap();
+///     match program.execute().unwrap_err() {
+///         Error::Instruction { pointer, opcode, parameters, .. } => (pointer, opcode, parameters),
+///         error => panic!("no context: {}", error),
+///     }
+/// };
+///
+/// assert_eq!(context("1101,1,1,3,4,-1"), (4, 4, vec![-1]));
+/// assert_eq!(context("1101,1,1,5,1108,0,0,0,42"), (8, 42, vec![]));
+/// assert_eq!(
+///     context(&format!("1101,1,1,3,1002,9,2,9,99,{}", Cell::MAX)),
+///     (4, 1002, vec![9, 2, 9])
+/// );
+///
+/// let mut program: Program = "1101,1,1,5,30001,0,0,0,99".parse().unwrap();
+/// let error = program.execute().unwrap_err();
+/// assert!(matches!(
+///     error.cause(),
+///     Error::InvalidParameterMode { pointer: 4, opcode: 30001, parameter: 2 }
+/// ));
+/// assert_eq!(
+///     error.cause().to_string(),
+///     "Invalid parameter mode in instruction 30001 at address 4, for parameter 3"
+/// );
+///
+/// let mut program: Program = "702,0,0,0,99".parse().unwrap();
+/// assert!(matches!(
+///     program.execute().unwrap_err().cause(),
+///     Error::InvalidParameterMode { pointer: 0, opcode: 702, parameter: 0 }
+/// ));
+///
+/// let mut program: Program = "1101,1,1,3".parse().unwrap();
+/// let error = program.execute().unwrap_err();
+/// assert!(matches!(error, Error::RanOffEnd { pointer: 4 }));
+/// assert_eq!(
+///     error.to_string(),
+///     "Ran off the end of the memory at address 4; is the halt instruction (99) missing?"
+/// );
+///
+/// let mut program: Program = "1101,1,1,3,1001,0".parse().unwrap();
+/// let error = program.execute().unwrap_err();
+/// assert!(matches!(
+///     error.cause(),
+///     Error::TruncatedInstruction { pointer: 4, opcode: 1001 }
+/// ));
+/// assert_eq!(
+///     error.cause().to_string(),
+///     "Instruction 1001 at address 4 truncated by the end of the memory; \
+///      is the halt instruction (99) missing?"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// An expression can't be parsed.
+    ParseError(&'static str),
+
+    /// A token of the source code, given with its index, is not a number.
+    InvalidToken { index: usize, token: String },
+
+    /// Assembly source code can't be assembled. (See `asm::assemble`.)
+    InvalidAssembly {
+        /// The line and the column of the mistake, starting at 1.
+        line: usize,
+        column: usize,
+        message: &'static str,
+    },
+
+    /// An instruction has an unknown opcode.
+    UnknownOpcode {
+        /// The opcode, including the parameter modes.
+        opcode: Cell,
+
+        /// The cells around the instruction, with their address.
+        memory: Vec<(usize, Cell)>,
+    },
+
+    /// An instruction lacks a parameter.
+    MissingParameter(&'static str),
+
+    /// A parameter of an instruction has an unknown mode.
+    InvalidParameterMode {
+        /// The address of the instruction.
+        pointer: usize,
+
+        /// The opcode, including the parameter modes.
+        opcode: Cell,
+
+        /// The index of the parameter, starting at zero.
+        parameter: usize,
+    },
+
+    /// An instruction reads or writes at an invalid address.
+    InvalidAddress(&'static str),
+
+    /// A parameter in relative mode points to a negative address, or
+    /// one too large for the memory.
+    InvalidRelativeAddress {
+        /// The address of the instruction.
+        pointer: usize,
+
+        /// The relative base.
+        base: i64,
+
+        /// The parameter, added to the relative base.
+        offset: Cell,
+    },
+
+    /// An arithmetic operation overflowed.
+    Overflow,
+
+    /// The input source can't give an input.
+    InvalidInput(&'static str),
+
+    /// An output can't be given to the output sink, or read.
+    InvalidOutput(&'static str),
+
+    /// A limit set on the program was reached.
+    LimitExceeded(&'static str),
+
+    /// An instruction writes at an address beyond the memory limit. (See
+    /// `Program::set_memory_limit`.)
+    MemoryLimit { address: usize, limit: usize },
+
+    /// The program executed as many instructions as allowed. (See
+    /// `Program::set_max_steps`.)
+    StepLimit { steps: u64 },
+
+    /// The instruction pointer is past the end of the memory, usually
+    /// because the program lacks a halt instruction.
+    RanOffEnd { pointer: usize },
+
+    /// The parameters of an instruction go past the end of the memory.
+    TruncatedInstruction { pointer: usize, opcode: Cell },
+
+    /// A jump, from the instruction at `pointer`, targets a negative
+    /// address, or one outside the memory with strict jumps.
+    InvalidJump { pointer: usize, target: Cell },
+
+    /// A number can't be parsed, e.g. an input read from a reader.
+    InvalidNumber(ParseIntError),
+
+    /// An I/O error, e.g. while reading inputs. Only its kind and its
+    /// message are kept, so errors can be cloned and compared.
+    Io {
+        kind: io::ErrorKind,
+        message: String,
+    },
+
+    /// The input source or the output sink panicked, with this message.
+    CallbackPanicked(String),
+
+    /// A custom opcode can't be registered.
+    CustomOpcode(&'static str),
+
+    /// Any other error, e.g. from an input source or an output sink.
+    Other(String),
+
+    /// An error while running an instruction, with its context.
+    Instruction {
+        /// The address of the instruction.
+        pointer: usize,
+
+        /// The opcode of the instruction, including the parameter modes.
+        opcode: Cell,
+
+        /// The parameters of the instruction, as in memory.
+        parameters: Vec<Cell>,
+
+        /// The last instructions executed before this one, oldest first.
+        /// (See `Program::set_history_depth`.)
+        history: Vec<InstructionEvent>,
+
+        /// The memory used by the program when the error happened; printed
+        /// with the history.
+        memory_usage: MemoryUsage,
+
+        error: Box<Error>,
+    },
+}
+
+impl Error {
+    /// Returns the message of the error, as displayed.
+    pub fn message(&self) -> &str {
+        match self {
+            Error::ParseError(message)
+            | Error::MissingParameter(message)
+            | Error::InvalidAddress(message)
+            | Error::InvalidInput(message)
+            | Error::InvalidOutput(message)
+            | Error::LimitExceeded(message)
+            | Error::CustomOpcode(message) => message,
+            Error::InvalidToken { .. } => "Invalid source code: invalid numbers.",
+            Error::InvalidAssembly { message, .. } => message,
+            Error::UnknownOpcode { .. } => "Unexpected opcode",
+            Error::InvalidParameterMode { .. } => "Invalid parameter mode",
+            Error::Overflow => "Arithmetic overflow",
+            Error::MemoryLimit { .. } => "Memory limit exceeded",
+            Error::StepLimit { .. } => "Step limit exceeded",
+            Error::RanOffEnd { .. } => "Ran off the end of the memory",
+            Error::TruncatedInstruction { .. } => "Instruction truncated by the end of the memory",
+            Error::InvalidJump { .. } => "Invalid jump target",
+            Error::InvalidRelativeAddress { base, offset, .. } => {
+                match add_to_base(*base, *offset) {
+                    Some(address) if address < 0 => "Negative address",
+                    _ => "Address overflow",
+                }
+            }
+            Error::InvalidNumber(_) => "Invalid number",
+            Error::Io { message, .. } => message,
+            Error::CallbackPanicked(_) => "Callback panicked",
+            Error::Other(message) => message,
+            Error::Instruction { error, .. } => error.message(),
+        }
+    }
+
+    ///
+    /// Returns the kind of the error, without its payload nor its context.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{ErrorKind, Program};
+    /// let mut program: Program = "1101,1,1,5,42,0".parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().kind(), ErrorKind::UnknownOpcode);
+    ///
+    /// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+    /// let error = program.execute().unwrap_err();
+    /// assert_eq!(error.kind(), ErrorKind::InvalidInput);
+    ///
+    /// let mut copy: Program = "3,5,4,5,99,0".parse().unwrap();
+    /// assert_eq!(copy.execute().unwrap_err(), error.clone());
+    /// ```
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ParseError(_) => ErrorKind::ParseError,
+            Error::InvalidToken { .. } => ErrorKind::InvalidToken,
+            Error::InvalidAssembly { .. } => ErrorKind::InvalidAssembly,
+            Error::UnknownOpcode { .. } => ErrorKind::UnknownOpcode,
+            Error::MissingParameter(_) => ErrorKind::MissingParameter,
+            Error::InvalidParameterMode { .. } => ErrorKind::InvalidParameterMode,
+            Error::InvalidAddress(_) => ErrorKind::InvalidAddress,
+            Error::InvalidRelativeAddress { .. } => ErrorKind::InvalidRelativeAddress,
+            Error::Overflow => ErrorKind::Overflow,
+            Error::InvalidInput(_) => ErrorKind::InvalidInput,
+            Error::InvalidOutput(_) => ErrorKind::InvalidOutput,
+            Error::LimitExceeded(_) => ErrorKind::LimitExceeded,
+            Error::MemoryLimit { .. } => ErrorKind::MemoryLimit,
+            Error::StepLimit { .. } => ErrorKind::StepLimit,
+            Error::RanOffEnd { .. } => ErrorKind::RanOffEnd,
+            Error::TruncatedInstruction { .. } => ErrorKind::TruncatedInstruction,
+            Error::InvalidJump { .. } => ErrorKind::InvalidJump,
+            Error::InvalidNumber(_) => ErrorKind::InvalidNumber,
+            Error::Io { .. } => ErrorKind::Io,
+            Error::CallbackPanicked(_) => ErrorKind::CallbackPanicked,
+            Error::CustomOpcode(_) => ErrorKind::CustomOpcode,
+            Error::Other(_) => ErrorKind::Other,
+            Error::Instruction { error, .. } => error.kind(),
+        }
+    }
+
+    /// Returns the error without its context, if any.
+    pub fn cause(&self) -> &Error {
+        match self {
+            Error::Instruction { error, .. } => error.cause(),
+            _ => self,
+        }
+    }
+}
+
+/// The kind of an error, i.e. its variant without its payload. See
+/// `Error::kind`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    ParseError,
+    InvalidToken,
+    InvalidAssembly,
+    UnknownOpcode,
+    MissingParameter,
+    InvalidParameterMode,
+    InvalidAddress,
+    InvalidRelativeAddress,
+    Overflow,
+    InvalidInput,
+    InvalidOutput,
+    LimitExceeded,
+    MemoryLimit,
+    StepLimit,
+    RanOffEnd,
+    TruncatedInstruction,
+    InvalidJump,
+    InvalidNumber,
+    Io,
+    CallbackPanicked,
+    CustomOpcode,
+    Other,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidToken { index, token } => {
+                write!(f, "{} Token {}: {:?}", self.message(), index, token)
+            }
+            Error::InvalidAssembly { line, column, .. } => {
+                write!(f, "{} at line {}, column {}", self.message(), line, column)
+            }
+            Error::UnknownOpcode { opcode, memory } => write!(
+                f,
+                "{} {} (memory from address {}: {})",
+                self.message(),
+                opcode,
+                memory.first().map_or(0, |(address, _)| *address),
+                memory.iter().map(|(_, value)| value).join(",")
+            ),
+            Error::InvalidParameterMode {
+                pointer,
+                opcode,
+                parameter,
+            } => write!(
+                f,
+                "{} in instruction {} at address {}, for parameter {}",
+                self.message(),
+                opcode,
+                pointer,
+                parameter + 1
+            ),
+            Error::InvalidNumber(e) => write!(f, "{}: {}", self.message(), e),
+            Error::CallbackPanicked(payload) => write!(f, "{}: {}", self.message(), payload),
+            Error::InvalidJump { pointer, target } => write!(
+                f,
+                "{} {}, from the instruction at address {}",
+                self.message(),
+                target,
+                pointer
+            ),
+            Error::InvalidRelativeAddress {
+                pointer,
+                base,
+                offset,
+            } => write!(
+                f,
+                "{}: relative base {} plus offset {}, in the instruction at address {}",
+                self.message(),
+                base,
+                offset,
+                pointer
+            ),
+            Error::MemoryLimit { address, limit } => write!(
+                f,
+                "{}: address {}, with a limit of {} cells",
+                self.message(),
+                address,
+                limit
+            ),
+            Error::StepLimit { steps } => {
+                write!(f, "{} after {} instructions", self.message(), steps)
+            }
+            Error::RanOffEnd { pointer } => write!(
+                f,
+                "{} at address {}; is the halt instruction (99) missing?",
+                self.message(),
+                pointer
+            ),
+            Error::TruncatedInstruction { pointer, opcode } => write!(
+                f,
+                "Instruction {} at address {} truncated by the end of the memory; \
+                 is the halt instruction (99) missing?",
+                opcode, pointer
+            ),
+            Error::Instruction {
+                pointer,
+                opcode,
+                parameters,
+                history,
+                memory_usage,
+                error,
+            } => {
+                write!(
+                    f,
+                    "{} at address {}, in instruction {}",
+                    error,
+                    pointer,
+                    std::iter::once(opcode).chain(parameters).join(",")
+                )?;
+
+                if !history.is_empty() {
+                    write!(f, "\nLast instructions executed:")?;
+                }
+                for event in history {
+                    write!(
+                        f,
+                        "\n  {}: {} {}",
+                        event.address,
+                        event.instruction,
+                        event.values.iter().join(",")
+                    )?;
+                    if let Some((address, value)) = event.write {
+                        write!(f, " -> [{}] = {}", address, value)?;
+                    }
+                }
+                if !history.is_empty() {
+                    write!(f, "\nMemory usage: {}", memory_usage)?;
+                }
+
+                Ok(())
+            }
+            _ => write!(f, "{}", self.message()),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Instruction { error, .. } => Some(error.as_ref()),
+            Error::InvalidNumber(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseIntError> for Error {
+    fn from(error: ParseIntError) -> Self {
+        Error::InvalidNumber(error)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io {
+            kind: error.kind(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// An instruction of the program, containing the opcode and
+/// the parameters, alongside their modes.
+#[derive(Debug, Copy, Clone)]
+struct Instruction {
+    /// The address of the instruction.
+    address: usize,
+
+    opcode: OpCode,
+
+    /// The parameters, stored inline as no instruction has more than
+    /// three; only the `parameters_count` first ones are used.
+    parameters: [Parameter; 3],
+    parameters_count: usize,
+}
+
+impl Instruction {
+    fn parameters(&self) -> &[Parameter] {
+        &self.parameters[..self.parameters_count]
+    }
+}
+
+/// A parameter, i.e. a piece of data and a ParameterMode to
+/// know how to interpret it.
+/// See `ParameterMode`.
+#[derive(Debug, Copy, Clone)]
+struct Parameter {
+    data: Cell,
+    mode: ParameterMode,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum ParameterMode {
+    /// The parameter's value is the value stored at it's data
+    /// interpreted as a pointer.
+    Position,
+
+    /// The parameter's value is its data, directly.
+    Immediate,
+
+    /// The parameter's value is the value stored at it's
+    /// data interpreted as a relative pointer from the
+    /// current relative base.
+    Relative,
+}
+
+impl ParameterMode {
+    /// Returns the mode of a digit of an instruction code, if valid.
+    fn from_digit(digit: Cell) -> Option<ParameterMode> {
+        match digit {
+            0 => Some(ParameterMode::Position),
+            1 => Some(ParameterMode::Immediate),
+            2 => Some(ParameterMode::Relative),
+            _ => None,
+        }
+    }
+}
+
+/// OpCodes specify the purpose of each instruction in the program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OpCode {
+    /// Calculates the result of an arithmetic operation between
+    /// the two first parameters, and stores it at the address stored
+    /// in the first one.
+    Arithmetic(Operation),
+
+    /// Takes an input (queued, or from the input source) and stores it.
+    Input,
+
+    /// Outputs the value pointed by its parameter.
+    Output,
+
+    /// Jump to the second parameter if the first one passes
+    /// the condition.
+    Jump(JumpCondition),
+
+    /// Stores 1 in the address stored in the third parameter if
+    /// the comparison of the two first parameters is true; 0 else.
+    Test(Comparison),
+
+    /// Adds its parameter to the relative base.
+    AdjustRelativeBase,
+
+    /// Halts the program.
+    Halt,
+
+    /// A custom opcode, with its code. See `Program::register_opcode`.
+    Custom(Cell),
+}
+
+/// The operation of an Arithmetic opcode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Operation {
+    /// In Arithmetic opcode, adds the two parameters.
+    Add,
+
+    /// In Arithmetic opcode, multiply the two parameters.
+    Multiply,
+}
+
+/// The condition of a Jump opcode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum JumpCondition {
+    /// In Jump opcode, jumps if the parameter is not zero.
+    IfTrue,
+
+    /// In Jump opcode, jumps if the parameter is zero.
+    IfFalse,
+}
+
+/// The comparison of a Test opcode.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    /// In Test opcode, checks if the first parameter is less than the second.
+    LessThan,
+
+    /// In Test opcode, checks if both parameters are equal.
+    Equals,
+}
+
+/// Something happening while running a program. See `Program::run_until_event`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// The program outputted this value.
+    Output(Cell),
+
+    /// The program is paused on an input instruction, and no input is queued.
+    NeedsInput,
+
+    /// The program halted.
+    Halted,
+
+    /// The program stopped, e.g. on a breakpoint (see `debug`).
+    Stopped(Stopped),
+}
+
+/// An instruction executed by `Program::step`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    /// The address of the instruction.
+    pub address: usize,
+
+    /// The instruction code, i.e. the opcode and the parameters modes.
+    pub instruction: Cell,
+
+    /// The parameters of the instruction, as stored in memory (before
+    /// the instruction is executed).
+    pub parameters: Vec<Cell>,
+
+    /// The value outputted by the instruction, if any.
+    pub output: Option<Cell>,
+
+    /// True if the instruction halted the program.
+    pub halted: bool,
+}
+
+/// Where a paused program stopped. See `Program::execute_until_input_needed`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExecutionState {
+    /// The program is paused on an input instruction.
+    WaitingForInput,
+
+    /// The program halted.
+    Halted,
+
+    /// The program stopped, e.g. on a breakpoint (see `debug`).
+    Stopped(Stopped),
+}
+
+/// What happens when a program runs into an unknown opcode. See
+/// `Program::set_on_unknown_opcode`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OnUnknownOpcode {
+    /// The cell is skipped, as a one-cell instruction doing nothing.
+    Skip,
+
+    /// The program halts.
+    Halt,
+
+    /// The program fails, with an `Error::UnknownOpcode`.
+    Error,
+}
+
+/// Where a program is in its lifecycle. See `Program::status`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ExecutionStatus {
+    /// The program was not executed since it was loaded or reset.
+    NotStarted,
+
+    /// The program is executing, or paused between two instructions
+    /// (e.g. after an output).
+    Running,
+
+    /// The program is paused on an input instruction, and no input is queued.
+    WaitingForInput,
+
+    /// The program halted. Executing it again restarts it.
+    Halted,
+
+    /// An instruction failed. Executing the program again retries it.
+    Failed,
+
+    /// The program stopped, e.g. on a breakpoint (see `debug`). Executing
+    /// it again resumes it.
+    Stopped(Stopped),
+}
+
+/// A position in the outputs of a program, used to read only the
+/// values outputted since then. See `Program::output_cursor`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct OutputCursor(usize);
+
+/// A hook receiving the address of a suspicious memory write.
+type WriteHook = Box<dyn FnMut(usize)>;
+
+/// An instruction executed, as seen by an observer. See `Program::set_observer`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstructionEvent {
+    /// The number of the step, i.e. of instructions executed since the
+    /// program started, including this one.
+    pub step: u64,
+
+    /// The address of the instruction.
+    pub address: usize,
+
+    /// The instruction code, i.e. the opcode and the parameters modes.
+    pub instruction: Cell,
+
+    /// The opcode of the instruction, decoded. None for an unknown opcode
+    /// skipped (see `set_on_unknown_opcode`).
+    pub opcode: Option<OpCode>,
+
+    /// The parameters of the instruction, as in memory.
+    pub parameters: Vec<Cell>,
+
+    /// The values of the parameters read by the instruction, resolved
+    /// according to their modes.
+    pub values: Vec<Cell>,
+
+    /// The address written by the instruction and the value written, if any.
+    pub write: Option<(usize, Cell)>,
+}
+
+/// A hook receiving each instruction executed.
+type Observer = Box<dyn FnMut(&InstructionEvent)>;
+
+/// How much memory a program uses. See `Program::memory_usage`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The length of the program image, as loaded from the source code.
+    pub program_len: usize,
+
+    /// The highest address written to since the program was loaded, if any.
+    pub highest_written: Option<usize>,
+
+    /// The number of cells of the memory vector, currently allocated.
+    pub cells_allocated: usize,
+
+    /// With sparse memory, the number of cells written past the memory
+    /// vector, stored one by one. See `with_sparse_memory`.
+    pub sparse_cells: usize,
+}
+
+/// Prints the figures on one line, as the debugger's `info` command and
+/// the errors with a history do.
+///
+/// ```rust
+/// # use lib::intcode::Program;
+/// let mut program: Program = "1101,1,1,1000,99".parse().unwrap();
+/// assert_eq!(
+///     program.memory_usage().to_string(),
+///     "5 cells loaded, 5 allocated, 0 sparse; highest address written: none"
+/// );
+///
+/// program.execute().unwrap();
+/// assert_eq!(
+///     program.memory_usage().to_string(),
+///     "5 cells loaded, 1001 allocated, 0 sparse; highest address written: 1000"
+/// );
+/// ```
+impl fmt::Display for MemoryUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} cells loaded, {} allocated, {} sparse; highest address written: ",
+            self.program_len, self.cells_allocated, self.sparse_cells
+        )?;
+
+        match self.highest_written {
+            Some(address) => write!(f, "{}", address),
+            None => write!(f, "none"),
+        }
+    }
+}
+
+/// A snapshot of the state of a program (memory, pointer and relative
+/// base), to compare states or remember the states seen, e.g. in searches.
+/// See `Program::state_key`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProgramState {
+    /// The non-zero cells of the memory, by address, so the memory grown
+    /// by writing zeros doesn't make a difference.
+    memory: Vec<(usize, Cell)>,
+    pointer: usize,
+    relative_base: i64,
+}
+
+/// A transcript of an ASCII session with a program, as recorded by
+/// `Program::record_transcript`.
+struct Transcript {
+    file: File,
+
+    /// The input line being typed, written once complete.
+    pending_input: String,
+
+    /// True if the last thing written ends a line.
+    at_line_start: bool,
+}
+
+impl Transcript {
+    fn record_output(&mut self, value: Cell) -> io::Result<()> {
+        let text = ascii_text(value);
+
+        self.file.write_all(text.as_bytes())?;
+        self.at_line_start = text.ends_with('\n');
+
+        if self.at_line_start {
+            self.file.flush()?;
+        }
+
+        Ok(())
+    }
+
+    fn record_input(&mut self, value: Cell) -> io::Result<()> {
+        match value {
+            10 => {
+                let separator = if self.at_line_start { "" } else { "\n" };
+                writeln!(self.file, "{}> {}", separator, self.pending_input)?;
+                self.pending_input.clear();
+                self.at_line_start = true;
+                self.file.flush()
+            }
+            0..=127 => {
+                self.pending_input.push(value as u8 as char);
+                Ok(())
+            }
+            _ => {
+                self.pending_input.push_str(&value.to_string());
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Where the inputs and outputs of a program are logged, as recorded by
+/// `Program::record_io_log`.
+enum IoLog {
+    Writer(Box<dyn Write>),
+    Memory(String),
+}
+
+impl IoLog {
+    fn record(&mut self, direction: &str, step: u64, value: Cell) -> io::Result<()> {
+        let line = format!("{:<3} #{}  {}\n", direction, step, value);
+
+        match self {
+            IoLog::Writer(writer) => writer.write_all(line.as_bytes()),
+            IoLog::Memory(log) => {
+                log.push_str(&line);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Calls the input source or the output sink, turning its panics into
+/// errors, so the program stays usable and the thread running it alive.
+fn catch_callback_panic<T>(callback: impl FnOnce() -> Result<T>) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(callback)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|message| message.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown payload".to_string());
+
+        Err(Error::CallbackPanicked(message))
+    })
+}
+
+/// Returns the text of an output of an ASCII program: its character, or
+/// the number on its own line if it's not ASCII.
+fn ascii_text(value: Cell) -> String {
+    match value {
+        0..=127 => (value as u8 as char).to_string(),
+        _ => format!("{}\n", value),
+    }
+}
+
+/// The Intcode program interpreter.
+///
+/// For references, see [days two](https://adventofcode.com/2019/day/2),
+/// [five](https://adventofcode.com/2019/day/5) and
+/// [nine](https://adventofcode.com/2019/day/9) of 2019's Advent of Code.
+pub struct Program {
+    /// The program's memory. It stores both the instructions
+    /// (source code) to execute, and the data (“variables”)
+    /// in one unique self-modifiable chain.
+    memory: Vec<Cell>,
+
+    /// With sparse memory, the cells written past `memory`, which is
+    /// then never expanded. See `with_sparse_memory`.
+    sparse: Option<BTreeMap<usize, Cell>>,
+
+    /// The memory as loaded, restored when the program is reset.
+    initial_memory: Rc<Vec<Cell>>,
+
+    /// The patches applied to the program, in order, re-applied
+    /// when the program is restarted.
+    patches: Vec<(usize, Cell)>,
+
+    /// The current pointer in the program's execution.
+    pointer: usize,
+
+    /// The current relative base for relative mode.
+    relative_base: i64,
+
+    /// An input source for the Input opcode, if set, called with a
+    /// number incremented each time an input is required (starts at 0)
+    /// and no input is queued. Shared with the forks of the program.
+    input_source: Option<Rc<RefCell<dyn InputSource>>>,
+
+    /// The number of times an input was requested.
+    /// (See `input_source`.)
+    input_count: usize,
+
+    /// Inputs given in advance, used before the input source.
+    /// (See `push_input`.)
+    queued_input: VecDeque<Cell>,
+
+    /// The input given when none is available, if set.
+    /// (See `set_input_default`.)
+    input_default: Option<Cell>,
+
+    /// The number of times the default input was given.
+    idle_reads: usize,
+
+    /// The outputs from the Output opcode, except those drained, unless
+    /// they are given to an output sink. Kept contiguous, so they can be
+    /// borrowed as a slice (see `push_output`).
+    output: VecDeque<Cell>,
+
+    /// The number of outputs drained (see `take_output`), or given to
+    /// the output sink.
+    drained_output: usize,
+
+    /// The sink receiving the outputs, if set. (See `set_output_sink`.)
+    output_sink: Option<Box<dyn OutputSink>>,
+
+    /// The value outputted by the last instruction executed, if any.
+    emitted: Option<Cell>,
+
+    /// The number of outputs the program can accumulate.
+    /// (See `set_max_outputs`.)
+    max_outputs: usize,
+
+    /// Where the program is in its lifecycle.
+    status: ExecutionStatus,
+
+    /// The length of the program image, as loaded.
+    program_len: usize,
+
+    /// The highest address written to, if any.
+    highest_written: Option<usize>,
+
+    /// The number of cells the program can grow its memory to.
+    /// (See `set_memory_limit`.)
+    memory_limit: usize,
+
+    /// The number of instructions executed since the program started.
+    steps: u64,
+
+    /// The number of instructions the program can execute before failing,
+    /// if limited. (See `set_max_steps`.)
+    max_steps: Option<u64>,
+
+    /// True if jumps must land in the memory. (See `set_strict_jumps`.)
+    strict_jumps: bool,
+
+    /// If loop detection is enabled, the interval between two checks, and
+    /// the hashes of the states seen. (See `enable_loop_detection`.)
+    loop_detection: Option<(u64, HashSet<u64>)>,
+
+    /// A hook called with the address of the first write landing
+    /// more than the given distance past the program image.
+    far_write_warning: Option<(usize, WriteHook)>,
+
+    /// The transcript of the session, if recorded.
+    transcript: Option<Transcript>,
+
+    /// Where the inputs and outputs are logged, if recorded.
+    /// (See `record_io_log`.)
+    io_log: Option<IoLog>,
+
+    /// Where the outputs are echoed as they happen, if enabled.
+    /// (See `set_output_echo`.)
+    output_echo: Option<Box<dyn Write>>,
+
+    /// A hook called after each instruction, with its event. (See
+    /// `set_observer`.)
+    observer: Option<Observer>,
+
+    /// The event of the instruction being executed, collected for the
+    /// observer or the history.
+    event: Option<InstructionEvent>,
+
+    /// The last instructions executed, oldest first, and how many are
+    /// kept. (See `set_history_depth`.)
+    history: VecDeque<InstructionEvent>,
+    history_depth: usize,
+
+    /// The memory accesses counted, if enabled. (See `enable_heatmap`.)
+    heatmap: Option<heatmap::Heatmap>,
+
+    /// The length of the instructions executed, by address, if coverage
+    /// is enabled. (See `enable_coverage`.)
+    coverage: Option<BTreeMap<usize, usize>>,
+
+    /// The instructions counted and timed, if profiling is enabled. (See
+    /// `enable_profiling`.)
+    profiler: Option<profile::Profiler>,
+
+    /// The instructions already decoded, by address, unless the cache is
+    /// disabled. Writes invalidate the instructions they land in. Only the
+    /// instructions within `memory` are cached, not those in sparse cells.
+    decode_cache: Option<Vec<Option<Instruction>>>,
+
+    /// The custom opcodes, by code, with their number of parameters.
+    /// (See `register_opcode`.)
+    custom_opcodes: BTreeMap<Cell, (usize, opcodes::OpcodeHandler)>,
+
+    /// What happens on unknown opcodes. (See `set_on_unknown_opcode`.)
+    on_unknown_opcode: OnUnknownOpcode,
+
+    /// The breakpoints, in the order they were added. (See
+    /// `add_breakpoint`.)
+    breakpoints: Vec<Breakpoint>,
+
+    /// True if the next instruction is executed without checking the
+    /// breakpoints, e.g. when resuming from one.
+    resuming: bool,
+
+    /// The watched addresses, in the order they were added. (See
+    /// `add_watchpoint`.)
+    watchpoints: Vec<usize>,
+
+    /// The write of the instruction being executed to a watched address, if
+    /// any: the address, and the values before and after.
+    watched_write: Option<(usize, Cell, Cell)>,
+
+    /// The conditions on outputs stopping the program, in the order they
+    /// were registered. (See `break_on_output`.)
+    output_breaks: Vec<OutputBreak>,
+
+    /// The changes of the last instructions executed, if recorded. (See
+    /// `enable_recording`.)
+    recording: Option<rewind::Recording>,
+}
+
+impl FromStr for Program {
+    type Err = Error;
+
+    fn from_str(source_code: &str) -> Result<Self> {
+        Program::parse_source(source_code, Syntax::Default)
+    }
+}
+
+/// How strictly source code is parsed. See `Program::parse_source`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Syntax {
+    /// Numbers separated by commas, and nothing else, except whitespace
+    /// around the whole source code.
+    Strict,
+
+    /// Numbers separated by commas, with whitespace around them. Empty
+    /// fields are skipped. That's how `parse` reads source code.
+    Default,
+
+    /// As `Default`, but new lines separate numbers too, and comments go
+    /// from `#` to the end of the line, e.g. for hand-written programs.
+    Tolerant,
+}
+
+/// The number of cells displayed on each row by `Display`.
+const DISPLAY_ROW_LEN: usize = 10;
+
+impl fmt::Display for Program {
+    ///
+    /// Prints the memory in rows of ten cells, each one starting with the
+    /// address of its first cell. The cell at the instruction pointer is
+    /// marked with `>`, and the one at the relative base with `@`. Runs of
+    /// two or more rows filled with zeros are elided.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "109,2,1101,1,1,50,99".parse().unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(
+    ///     program.to_string(),
+    ///     "ip: 7, rb: 2\n\
+    ///      \x200:   109     2 @1101     1     1    50    99 >   0     0     0\n\
+    ///      ..: 40 zeros\n\
+    ///      50:     2\n"
+    /// );
+    /// ```
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "ip: {}, rb: {}", self.pointer, self.relative_base)?;
+
+        // Sparse cells are displayed on their own rows, the zeros between
+        // them being elided.
+        let rows: Vec<(usize, &[Cell])> = self
+            .memory
+            .chunks(DISPLAY_ROW_LEN)
+            .enumerate()
+            .map(|(row, cells)| (row * DISPLAY_ROW_LEN, cells))
+            .chain(
+                self.sparse
+                    .iter()
+                    .flatten()
+                    .map(|(address, cell)| (*address, std::slice::from_ref(cell))),
+            )
+            .collect();
+
+        let cell_width = rows
+            .iter()
+            .flat_map(|(_, cells)| cells.iter())
+            .map(|cell| cell.to_string().len())
+            .max()
+            .unwrap_or(0);
+        let address_width = rows.last().map_or(1, |(start, _)| start.to_string().len());
+
+        let write_row = |f: &mut fmt::Formatter<'_>, start: usize, cells: &[Cell]| {
+            write!(f, "{:>width$}:", start, width = address_width)?;
+
+            for (address, cell) in (start..).zip(cells) {
+                let marker = if address == self.pointer {
+                    '>'
+                } else if address as i64 == self.relative_base {
+                    '@'
+                } else {
+                    ' '
+                };
+
+                write!(f, " {}{:>width$}", marker, cell, width = cell_width)?;
+            }
+
+            writeln!(f)
+        };
+
+        // Writes a run of zeros, made of blank rows and of cells missing
+        // between rows. A single blank row is written as is.
+        let write_zeros =
+            |f: &mut fmt::Formatter<'_>, blank: &[(usize, &[Cell])], gap: usize| match blank {
+                [(start, cells)] if gap == 0 => write_row(f, *start, cells),
+                _ => match gap + blank.iter().map(|(_, cells)| cells.len()).sum::<usize>() {
+                    0 => Ok(()),
+                    zeros => writeln!(
+                        f,
+                        "{:>width$}: {} zeros",
+                        "..",
+                        zeros,
+                        width = address_width
+                    ),
+                },
+            };
+
+        let is_marked =
+            |address: usize| address == self.pointer || address as i64 == self.relative_base;
+
+        let mut blank = vec![];
+        let mut gap = 0;
+        let mut next_address = 0;
+
+        for (start, cells) in rows {
+            gap += start - next_address;
+            next_address = start + cells.len();
+
+            if cells.iter().all(|cell| *cell == 0) && !(start..next_address).any(is_marked) {
+                blank.push((start, cells));
+                continue;
+            }
+
+            write_zeros(f, &blank, gap)?;
+            write_row(f, start, cells)?;
+
+            blank.clear();
+            gap = 0;
+        }
+
+        write_zeros(f, &blank, gap)
+    }
+}
+
+impl Program {
+    ///
+    /// Loads a program from its memory, e.g. for programs built in code.
+    /// Inputs are only the queued ones until an input source is set.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program = Program::from_memory(vec![1, 0, 0, 0, 99]);
+    /// program.execute().unwrap();
+    /// assert_eq!(program.get(0), 2);
+    ///
+    /// let mut program = Program::from_memory(vec![104, 42, 99]);
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// ```
+    pub fn from_memory(memory: Vec<Cell>) -> Program {
+        let history_depth = HISTORY_DEPTH_DEFAULT.with(|default| default.get());
+
+        Program {
+            program_len: memory.len(),
+            initial_memory: Rc::new(memory.clone()),
+            sparse: match SPARSE_MEMORY_DEFAULT.with(|sparse| sparse.get()) {
+                true => Some(BTreeMap::new()),
+                false => None,
+            },
+            patches: vec![],
+            memory,
+            pointer: 0,
+            relative_base: 0,
+            input_source: None,
+            input_count: 0,
+            queued_input: VecDeque::new(),
+            input_default: None,
+            idle_reads: 0,
+            output: VecDeque::new(),
+            drained_output: 0,
+            output_sink: None,
+            emitted: None,
+            max_outputs: DEFAULT_MAX_OUTPUTS,
+            status: ExecutionStatus::NotStarted,
+            highest_written: None,
+            memory_limit: DEFAULT_MEMORY_LIMIT,
+            steps: 0,
+            max_steps: None,
+            strict_jumps: false,
+            loop_detection: None,
+            far_write_warning: None,
+            transcript: None,
+            io_log: None,
+            output_echo: None,
+            observer: None,
+            event: match history_depth {
+                0 => None,
+                _ => Some(InstructionEvent::default()),
+            },
+            history: VecDeque::new(),
+            history_depth,
+            heatmap: None,
+            coverage: None,
+            profiler: None,
+            decode_cache: Some(vec![]),
+            custom_opcodes: BTreeMap::new(),
+            on_unknown_opcode: OnUnknownOpcode::Error,
+            breakpoints: vec![],
+            resuming: false,
+            watchpoints: vec![],
+            watched_write: None,
+            output_breaks: vec![],
+            recording: None,
+        }
+    }
+
+    ///
+    /// Parses source code with the given syntax. Errors give the index of
+    /// the first invalid token, and the token itself.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program, Syntax};
+    /// let memory = |source_code: &str, syntax: Syntax| {
+    ///     Program::parse_source(source_code, syntax).map(|program| program.memory().to_vec())
+    /// };
+    /// let error = |source_code: &str, syntax: Syntax| match memory(source_code, syntax) {
+    ///     Err(Error::InvalidToken { index, token }) => (index, token),
+    ///     result => panic!("{:?}", result),
+    /// };
+    ///
+    /// assert_eq!(memory("1,-2,99\n", Syntax::Strict).unwrap(), vec![1, -2, 99]);
+    /// assert_eq!(error("1,,99", Syntax::Strict), (1, "".to_string()));
+    /// assert_eq!(error("1, 2", Syntax::Strict), (1, " 2".to_string()));
+    /// assert_eq!(error("1,2,", Syntax::Strict), (2, "".to_string()));
+    ///
+    /// assert_eq!(memory(" 1, ,2 ,\n", Syntax::Default).unwrap(), vec![1, 2]);
+    /// assert_eq!(error("1,x", Syntax::Default), (1, "x".to_string()));
+    /// assert_eq!(error("1\n2", Syntax::Default), (0, "1\n2".to_string()));
+    ///
+    /// let source_code = "# Outputs 42\n104, 42,  # the value\n\n99,\n";
+    /// assert_eq!(memory(source_code, Syntax::Tolerant).unwrap(), vec![104, 42, 99]);
+    /// assert_eq!(error("1\n2 3", Syntax::Tolerant), (1, "2 3".to_string()));
+    ///
+    /// assert_eq!(
+    ///     Program::parse_source("1,x", Syntax::Strict).err().unwrap().to_string(),
+    ///     "Invalid source code: invalid numbers. Token 1: \"x\""
+    /// );
+    /// ```
+    pub fn parse_source(source_code: &str, syntax: Syntax) -> Result<Program> {
+        let tokens: Vec<&str> = match syntax {
+            Syntax::Strict => source_code.trim().split(',').collect(),
+            Syntax::Default => source_code
+                .split(',')
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .collect(),
+            Syntax::Tolerant => source_code
+                .lines()
+                .flat_map(|line| line.split('#').next().unwrap_or("").split(','))
+                .map(str::trim)
+                .filter(|token| !token.is_empty())
+                .collect(),
+        };
+
+        tokens
+            .iter()
+            .enumerate()
+            .map(|(index, token)| {
+                token.parse().map_err(|_| Error::InvalidToken {
+                    index,
+                    token: token.to_string(),
+                })
+            })
+            .collect::<Result<_>>()
+            .map(Program::from_memory)
+    }
+
+    /// Patches the program, replacing the value at
+    /// the given address by the given new value.
+    /// Patches are kept when the program is restarted,
+    /// but not by `reset`.
+    ///
+    /// Patching past the end of the memory expands it, as writes do, and
+    /// fails the same way past the memory limit.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program = Program::from_memory(vec![]);
+    /// program.patch(0, 7).unwrap();
+    /// assert_eq!(program.memory(), &[7]);
+    ///
+    /// program.patch(1, 8).unwrap();
+    /// assert_eq!(program.memory(), &[7, 8]);
+    ///
+    /// program.patch(1_000_000, 9).unwrap();
+    /// assert_eq!(program.memory_len(), 1_000_001);
+    /// assert_eq!(program.get_range(999_999, 2), vec![0, 9]);
+    ///
+    /// program.set_memory_limit(1_000_001);
+    /// let error = program.patch(2_000_000_000, 10).unwrap_err();
+    /// assert_eq!(error.message(), "Memory limit exceeded");
+    /// assert_eq!(program.memory_len(), 1_000_001);
+    /// ```
+    pub fn patch(&mut self, address: usize, value: Cell) -> Result<()> {
+        self.check_memory_limit(address)?;
+
+        self.patches.retain(|(patched, _)| *patched != address);
+        self.patches.push((address, value));
+        self.store(address, value);
+        Ok(())
+    }
+
+    ///
+    /// Applies several patches, in order (see `patch`), stopping at the
+    /// first one failing. Returns the program, so patches can be chained
+    /// before executing it.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1,0,0,0,99".parse().unwrap();
+    /// program
+    ///     .patch_many(&[(1, 5), (2, 6), (6, 40)])
+    ///     .and_then(|program| program.patch_many(&[(5, 2)]))
+    ///     .unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(program.get(0), 42);
+    /// assert_eq!(program.memory(), &[42, 5, 6, 0, 99, 2, 40]);
+    /// ```
+    pub fn patch_many(&mut self, patches: &[(usize, Cell)]) -> Result<&mut Self> {
+        for &(address, value) in patches {
+            self.patch(address, value)?;
+        }
+
+        Ok(self)
+    }
+
+    ///
+    /// Returns the value stored into the program's memory at the given
+    /// address. Cells out of the current memory read as 0, as they do
+    /// for the program itself.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let program: Program = "1,2,3".parse().unwrap();
+    /// assert_eq!((program.get(0), program.get(2)), (1, 3));
+    /// assert_eq!((program.get(3), program.get(1_000_000)), (0, 0));
+    /// ```
+    pub fn get(&self, address: usize) -> Cell {
+        self.cell(address).unwrap_or(0)
+    }
+
+    ///
+    /// Returns the value stored into the program's memory at the given
+    /// address, if it's within the current memory (see `memory_len`).
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1,2,3".parse().unwrap();
+    /// assert_eq!((program.try_get(0), program.try_get(2)), (Some(1), Some(3)));
+    /// assert_eq!(program.try_get(3), None);
+    ///
+    /// program.patch(5, 6).unwrap();
+    /// assert_eq!((program.try_get(3), program.try_get(5), program.try_get(6)), (Some(0), Some(6), None));
+    ///
+    /// let program = program.with_sparse_memory();
+    /// assert_eq!((program.try_get(4), program.try_get(5), program.try_get(6)), (Some(0), Some(6), None));
+    /// ```
+    pub fn try_get(&self, address: usize) -> Option<Cell> {
+        self.cell(address)
+    }
+
+    /// Returns the value at the given address, if it's within the memory.
+    fn cell(&self, address: usize) -> Option<Cell> {
+        match (self.memory.get(address), &self.sparse) {
+            (Some(value), _) => Some(*value),
+            (None, Some(cells)) => match cells.get(&address) {
+                Some(value) => Some(*value),
+                None if address < self.memory_len() => Some(0),
+                None => None,
+            },
+            (None, None) => None,
+        }
+    }
+
+    ///
+    /// Returns `len` cells of memory starting at the given address. As
+    /// with `get`, cells out of the current memory read as 0; the memory
+    /// is not expanded.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Cell, Program};
+    /// let program: Program = "1,2,3,4,99".parse().unwrap();
+    /// assert_eq!(program.get_range(1, 3), vec![2, 3, 4]);
+    /// assert_eq!(program.get_range(3, 4), vec![4, 99, 0, 0]);
+    /// assert_eq!(program.get_range(1000, 2), vec![0, 0]);
+    /// assert_eq!(program.get_range(2, 0), Vec::<Cell>::new());
+    /// assert_eq!(program.memory_len(), 5);
+    /// ```
+    pub fn get_range(&self, start: usize, len: usize) -> Vec<Cell> {
+        (start..start.saturating_add(len))
+            .map(|address| self.cell(address).unwrap_or(0))
+            .collect()
+    }
+
+    ///
+    /// Returns the whole memory of the program, including memory grown
+    /// during its execution.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1101,1,1,7,99".parse().unwrap();
+    /// assert_eq!(program.memory_len(), 5);
+    ///
+    /// program.execute().unwrap();
+    /// assert_eq!(program.memory(), &[1101, 1, 1, 7, 99, 0, 0, 2]);
+    /// assert_eq!(program.memory_len(), 8);
+    /// ```
+    ///
+    /// With sparse memory, the cells written past the loaded program are
+    /// not included; see `sparse_cells`.
+    pub fn memory(&self) -> &[Cell] {
+        &self.memory
+    }
+
+    ///
+    /// Returns the cells written past the loaded program with sparse
+    /// memory, by address; with `memory`, that's the whole state of the
+    /// memory. Nothing without sparse memory.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Cell, Program};
+    /// let program: Program = "1101,1,1,1000,1101,2,2,50,99".parse().unwrap();
+    /// let mut program = program.with_sparse_memory();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(program.memory(), &[1101, 1, 1, 1000, 1101, 2, 2, 50, 99]);
+    /// assert_eq!(program.sparse_cells().collect::<Vec<_>>(), vec![(50, 4), (1000, 2)]);
+    /// assert_eq!(program.memory_len(), 1001);
+    ///
+    /// let mut program: Program = "1101,1,1,10,99".parse().unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.memory_len(), 11);
+    /// assert_eq!(program.sparse_cells().count(), 0);
+    /// ```
+    pub fn sparse_cells(&self) -> impl Iterator<Item = (usize, Cell)> + '_ {
+        self.sparse
+            .iter()
+            .flat_map(|cells| cells.iter().map(|(address, value)| (*address, *value)))
+    }
+
+    /// Returns the current size of the memory, in cells, i.e. the address
+    /// following the highest allocated cell.
+    pub fn memory_len(&self) -> usize {
+        match self
+            .sparse
+            .as_ref()
+            .and_then(|cells| cells.keys().next_back())
+        {
+            Some(address) => self.memory.len().max(address.saturating_add(1)),
+            None => self.memory.len(),
+        }
+    }
+
+    ///
+    /// Stores the cells written past the loaded program in a sparse map,
+    /// instead of expanding the memory up to the highest address written.
+    /// Cells never written still read as 0. That's for programs writing
+    /// far away in the memory, which would else allocate all cells before.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Writes 42 one billion cells away, then outputs it.
+    /// let program: Program = "1101,20,22,1000000000,4,1000000000,99".parse().unwrap();
+    /// let mut program = program.with_sparse_memory();
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// assert_eq!(program.get(1_000_000_000), 42);
+    /// assert_eq!(program.get(999_999_999), 0);
+    /// assert_eq!(program.memory_len(), 1_000_000_001);
+    /// assert_eq!(program.memory_usage().cells_allocated, 7);
+    /// assert_eq!(program.memory_usage().sparse_cells, 1);
+    ///
+    /// // Writes `out #7, hlt` there, then jumps to it.
+    /// let program: Program = "1101,0,104,1000000000,1101,0,7,1000000001,\
+    ///                         1101,0,99,1000000002,1105,1,1000000000"
+    ///     .parse()
+    ///     .unwrap();
+    /// assert_eq!(program.with_sparse_memory().execute().unwrap(), vec![7]);
+    ///
+    /// // Up to the last address.
+    /// let mut program = Program::from_memory(vec![99]).with_sparse_memory();
+    /// program.patch(usize::MAX, 1).unwrap();
+    /// assert_eq!(program.get(usize::MAX), 1);
+    /// ```
+    ///
+    /// Programs behave the same way with both memories:
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::EXAMPLES;
+    /// # use lib::intcode::Program;
+    /// for example in EXAMPLES.iter() {
+    ///     let mut dense: Program = example.source_code.parse().unwrap();
+    ///     let sparse: Program = example.source_code.parse().unwrap();
+    ///     let mut sparse = sparse.with_sparse_memory();
+    ///
+    ///     for program in [&mut dense, &mut sparse] {
+    ///         program.assert_outputs(example.inputs, example.outputs).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(dense.to_source(), sparse.to_source(), "{}", example.name);
+    /// }
+    /// ```
+    pub fn with_sparse_memory(mut self) -> Self {
+        self.sparse = Some(BTreeMap::new());
+        self
+    }
+
+    /// Fails if writing at the address would grow the memory past the
+    /// memory limit.
+    fn check_memory_limit(&self, address: usize) -> Result<()> {
+        if self.sparse.is_none() && address >= self.memory.len() && address >= self.memory_limit {
+            return Err(Error::MemoryLimit {
+                address,
+                limit: self.memory_limit,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Sets the value at the address for the program, failing if the memory
+    /// would have to grow past the memory limit.
+    fn set(&mut self, address: usize, value: Cell) -> Result<()> {
+        self.check_memory_limit(address)?;
+
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.record_write(address);
+        }
+        if let Some(event) = &mut self.event {
+            event.write = Some((address, value));
+        }
+        if self.watchpoints.contains(&address) {
+            let old = self.cell(address).unwrap_or(0);
+            self.watched_write = Some((address, old, value));
+        }
+        self.undo_write(address);
+
+        self.store(address, value);
+        Ok(())
+    }
+
+    /// Sets the value at the address, expanding the
+    /// memory if needed.
+    fn store(&mut self, address: usize, value: Cell) {
+        self.track_write(address);
+        self.invalidate_decoded(address);
+
+        if let (None, Some(cells)) = (self.memory.get(address), &mut self.sparse) {
+            cells.insert(address, value);
+            return;
+        }
+
+        // If the address is out of the current allocated memory, we
+        // have to expand it. (Pushing the zeros one by one instead was
+        // about five times slower for a write at address 1,000,000.)
+        if self.memory.len() <= address {
+            self.memory.resize(address + 1, 0);
+        }
+
+        self.memory[address] = value;
+    }
+
+    /// Updates the memory usage figures after a write at the given address,
+    /// and calls the far-write warning hook if needed.
+    fn track_write(&mut self, address: usize) {
+        self.highest_written = Some(self.highest_written.map_or(address, |h| h.max(address)));
+
+        let far_write = match &self.far_write_warning {
+            Some((distance, _)) => address >= self.program_len.saturating_add(*distance),
+            None => false,
+        };
+
+        if far_write {
+            if let Some((_, mut hook)) = self.far_write_warning.take() {
+                hook(address);
+            }
+        }
+    }
+
+    /// Returns the memory usage figures of the program.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{MemoryUsage, Program};
+    /// let mut program: Program = "1101,1,1,1000,99".parse().unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(
+    ///     program.memory_usage(),
+    ///     MemoryUsage {
+    ///         program_len: 5,
+    ///         highest_written: Some(1000),
+    ///         cells_allocated: 1001,
+    ///         sparse_cells: 0,
+    ///     }
+    /// );
+    ///
+    /// let mut program = program.with_sparse_memory();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.memory_usage().cells_allocated, 5);
+    /// assert_eq!(program.memory_usage().sparse_cells, 1);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            program_len: self.program_len,
+            highest_written: self.highest_written,
+            cells_allocated: self.memory.len(),
+            sparse_cells: self.sparse.as_ref().map_or(0, BTreeMap::len),
+        }
+    }
+
+    ///
+    /// Returns the source code of the program in its current state: the
+    /// whole memory, comma-separated. Memory grown during the execution is
+    /// included, zeros too, so parsing the source gives the same memory.
+    ///
+    /// With sparse memory, the cells past the memory vector are written up
+    /// to the last non-zero one, as the source can't skip cells: a non-zero
+    /// cell written far away still makes a source as long.
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::DAY9_QUINE;
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = DAY9_QUINE.source_code.parse().unwrap();
+    /// assert_eq!(program.to_source(), DAY9_QUINE.source_code);
+    ///
+    /// program.execute().unwrap();
+    /// let source = program.to_source();
+    /// assert!(source.ends_with(",16,1"));
+    ///
+    /// let reparsed: Program = source.parse().unwrap();
+    /// assert_eq!(reparsed.to_source(), source);
+    ///
+    /// // Zeros written far away in sparse memory are left out.
+    /// let program: Program = "1101,0,0,1000000000,99".parse().unwrap();
+    /// let mut program = program.with_sparse_memory();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.to_source(), "1101,0,0,1000000000,99");
+    /// ```
+    pub fn to_source(&self) -> String {
+        let end = self
+            .sparse
+            .iter()
+            .flat_map(|cells| cells.iter().rev())
+            .find(|(_, value)| **value != 0)
+            .map_or(self.memory.len(), |(address, _)| address + 1);
+        let sparse = (self.memory.len()..end).map(|address| self.get(address));
+        self.memory.iter().cloned().chain(sparse).join(",")
+    }
+
+    /// Writes the source code of the program in its current state to a
+    /// file, as `to_source`.
+    pub fn write_to(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        writeln!(file, "{}", self.to_source())
+    }
+
+    /// Sets a hook called the first time a write lands more than `distance`
+    /// cells past the end of the program image—a common symptom of an
+    /// addressing bug in hand-written programs. The hook receives the
+    /// written address, and is called at most once.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::cell::RefCell;
+    /// # use std::rc::Rc;
+    /// let warnings = Rc::new(RefCell::new(vec![]));
+    /// let hook_warnings = Rc::clone(&warnings);
+    ///
+    /// let mut program: Program = "1101,1,1,500,1101,1,1,600,99".parse().unwrap();
+    /// program.set_far_write_warning(100, move |address| hook_warnings.borrow_mut().push(address));
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(*warnings.borrow(), vec![500]);
+    /// ```
+    pub fn set_far_write_warning(&mut self, distance: usize, hook: impl FnMut(usize) + 'static) {
+        self.far_write_warning = Some((distance, Box::new(hook)));
+    }
+
+    ///
+    /// Sets the number of cells the program can grow its memory to (64 Mi
+    /// cells by default). A write past the limit fails instead of allocating
+    /// memory, so a program writing at a wild address stops with an error
+    /// rather than exhausting the memory of the machine.
+    ///
+    /// The limit doesn't apply to patches, nor to sparse memory, which
+    /// doesn't grow.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// let mut program: Program = "1101,1,1,999,99".parse().unwrap();
+    /// program.set_memory_limit(1000);
+    /// program.execute().unwrap();
+    /// assert_eq!(program.memory_len(), 1000);
+    ///
+    /// let mut program: Program = "1101,1,1,1000,99".parse().unwrap();
+    /// program.set_memory_limit(1000);
+    /// let error = program.execute().unwrap_err();
+    /// assert_eq!(error.message(), "Memory limit exceeded");
+    /// assert_eq!(
+    ///     error.cause(),
+    ///     &Error::MemoryLimit {
+    ///         address: 1000,
+    ///         limit: 1000
+    ///     }
+    /// );
+    ///
+    /// let mut program: Program = "1101,1,1,1099511627776,99".parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Memory limit exceeded");
+    /// ```
+    pub fn set_memory_limit(&mut self, cells: usize) {
+        self.memory_limit = cells;
+    }
+
+    ///
+    /// Sets the number of instructions the program can execute before
+    /// failing with "Step limit exceeded", to stop programs stuck in an
+    /// infinite loop. The count starts over when the program restarts.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// let mut program: Program = "1105,1,0".parse().unwrap();
+    /// program.set_max_steps(1000);
+    ///
+    /// let error = program.execute().unwrap_err();
+    /// assert_eq!(error.message(), "Step limit exceeded");
+    /// assert_eq!(error.cause(), &Error::StepLimit { steps: 1000 });
+    /// assert_eq!(program.steps_executed(), 1000);
+    ///
+    /// let mut program: Program = "104,1,104,2,99".parse().unwrap();
+    /// program.set_max_steps(3);
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2]);
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2]);
+    /// ```
+    pub fn set_max_steps(&mut self, steps: u64) {
+        self.max_steps = Some(steps);
+    }
+
+    ///
+    /// Makes jumps outside the memory fail, instead of failing once
+    /// running off its end. Jumps to negative addresses always fail.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// let jump_error = |source_code: &str, strict: bool| {
+    ///     let mut program: Program = source_code.parse().unwrap();
+    ///     program.set_strict_jumps(strict);
+    ///     program.execute().unwrap_err().cause().clone()
+    /// };
+    ///
+    /// // Immediate, position and relative targets.
+    /// for source_code in &["1105,1,-1", "105,1,3,-1", "109,4,2105,1,1,-1"] {
+    ///     assert!(matches!(jump_error(source_code, false), Error::InvalidJump { target: -1, .. }));
+    /// }
+    /// assert_eq!(
+    ///     jump_error("109,4,2105,1,1,-1", false).to_string(),
+    ///     "Invalid jump target -1, from the instruction at address 2"
+    /// );
+    ///
+    /// assert_eq!(jump_error("1106,0,1000000", false), Error::RanOffEnd { pointer: 1000000 });
+    /// assert_eq!(
+    ///     jump_error("1106,0,1000000", true),
+    ///     Error::InvalidJump { pointer: 0, target: 1000000 }
+    /// );
+    /// ```
+    pub fn set_strict_jumps(&mut self, strict: bool) {
+        self.strict_jumps = strict;
+    }
+
+    ///
+    /// Sets what happens when the program runs into an unknown opcode, e.g.
+    /// to run through data as if it was code: by default, the program
+    /// fails. Skipped cells count as steps.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, OnUnknownOpcode, Program};
+    /// let mut program: Program = "104,1,42,104,2,99".parse().unwrap();
+    ///
+    /// let error = program.execute().unwrap_err();
+    /// assert!(matches!(error.cause(), Error::UnknownOpcode { opcode: 42, .. }));
+    /// assert_eq!(
+    ///     error.cause().to_string(),
+    ///     "Unexpected opcode 42 (memory from address 0: 104,1,42,104,2,99)"
+    /// );
+    ///
+    /// program.set_on_unknown_opcode(OnUnknownOpcode::Skip);
+    /// program.reset();
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2]);
+    ///
+    /// program.set_on_unknown_opcode(OnUnknownOpcode::Halt);
+    /// assert_eq!(program.execute().unwrap(), vec![1]);
+    /// assert_eq!(program.pointer(), 2);
+    /// ```
+    pub fn set_on_unknown_opcode(&mut self, on_unknown_opcode: OnUnknownOpcode) {
+        self.on_unknown_opcode = on_unknown_opcode;
+    }
+
+    ///
+    /// Enables the detection of infinite loops: every `interval` steps, the
+    /// state of the program (pointer, relative base, memory, inputs read and
+    /// outputs produced) is hashed, and if it was already seen, the program
+    /// fails with "Loop detected". A smaller interval catches loops sooner,
+    /// but hashes the memory more often.
+    ///
+    /// Loops producing outputs or reading inputs are never detected, as
+    /// their state is never the same.
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::CORPUS_LOOP;
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1105,1,0".parse().unwrap();
+    /// program.enable_loop_detection(10);
+    /// assert_eq!(program.execute().unwrap_err().message(), "Loop detected");
+    ///
+    /// // Flips the sign of a cell forever.
+    /// let mut program: Program = "102,-1,7,7,1105,1,0,1".parse().unwrap();
+    /// program.enable_loop_detection(3);
+    /// assert_eq!(program.execute().unwrap_err().message(), "Loop detected");
+    ///
+    /// // Long, but finite.
+    /// let mut program: Program = CORPUS_LOOP.source_code.parse().unwrap();
+    /// program.enable_loop_detection(1);
+    /// assert_eq!(program.execute().unwrap(), vec![500_500]);
+    /// ```
+    pub fn enable_loop_detection(&mut self, interval: u64) {
+        self.loop_detection = Some((interval.max(1), HashSet::new()));
+    }
+
+    /// Hashes the state of the program, for loop detection.
+    fn state_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+
+        self.pointer.hash(&mut hasher);
+        self.relative_base.hash(&mut hasher);
+        self.memory.hash(&mut hasher);
+        self.sparse.hash(&mut hasher);
+        self.input_count.hash(&mut hasher);
+        self.outputs_produced().hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    ///
+    /// Returns a snapshot of the memory, pointer and relative base of the
+    /// program, equal for programs in the same state whatever the path they
+    /// took. Its hash is the same from one run to another with the same
+    /// hasher, e.g. `DefaultHasher::new()`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::collections::HashSet;
+    /// // Both write 4 over their first instruction, one adding, the other
+    /// // multiplying.
+    /// let mut adding: Program = "1101,2,2,0,99".parse().unwrap();
+    /// let mut multiplying: Program = "1102,2,2,0,99".parse().unwrap();
+    /// assert_ne!(adding.state_key(), multiplying.state_key());
+    ///
+    /// adding.execute().unwrap();
+    /// multiplying.execute().unwrap();
+    /// assert_eq!(adding.state_key(), multiplying.state_key());
+    ///
+    /// let mut seen = HashSet::new();
+    /// assert!(seen.insert(adding.state_key()));
+    /// assert!(!seen.insert(multiplying.state_key()));
+    ///
+    /// // The memory representation doesn't change the state.
+    /// let mut dense: Program = "1101,2,2,1000,99".parse().unwrap();
+    /// let mut sparse = dense.fork().with_sparse_memory();
+    /// dense.execute().unwrap();
+    /// sparse.execute().unwrap();
+    /// assert_eq!(dense.state_key(), sparse.state_key());
+    /// ```
+    pub fn state_key(&self) -> ProgramState {
+        ProgramState {
+            memory: self
+                .memory
+                .iter()
+                .cloned()
+                .enumerate()
+                .chain(self.sparse.iter().flatten().map(|(a, v)| (*a, *v)))
+                .filter(|(_, value)| *value != 0)
+                .collect(),
+            pointer: self.pointer,
+            relative_base: self.relative_base,
+        }
+    }
+
+    /// Returns the number of instructions executed since the program
+    /// started.
+    pub fn steps_executed(&self) -> u64 {
+        self.steps
+    }
+
+    ///
+    /// Enables or disables the cache of decoded instructions (enabled by
+    /// default). Programs executing the same instructions many times are
+    /// faster with it; writes to a decoded instruction invalidate it, so
+    /// self-modifying programs still work.
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::EXAMPLES;
+    /// # use lib::intcode::Program;
+    /// // Outputs the parameter of its first instruction, then increments
+    /// // it, three times.
+    /// let mut program: Program = "104,5,101,1,1,1,101,-1,14,14,1005,14,0,99,3".parse().unwrap();
+    /// assert_eq!(program.execute().unwrap(), vec![5, 6, 7]);
+    ///
+    /// for example in EXAMPLES.iter() {
+    ///     let mut cached: Program = example.source_code.parse().unwrap();
+    ///     let mut uncached: Program = example.source_code.parse().unwrap();
+    ///     uncached.set_decode_cache(false);
+    ///
+    ///     for program in [&mut cached, &mut uncached] {
+    ///         program.assert_outputs(example.inputs, example.outputs).unwrap();
+    ///     }
+    ///
+    ///     assert_eq!(cached.to_source(), uncached.to_source(), "{}", example.name);
+    /// }
+    /// ```
+    pub fn set_decode_cache(&mut self, enabled: bool) {
+        self.decode_cache = if enabled { Some(vec![]) } else { None };
+    }
+
+    /// Removes the decoded instructions containing the given address from
+    /// the cache.
+    fn invalidate_decoded(&mut self, address: usize) {
+        if let Some(cache) = &mut self.decode_cache {
+            // Instructions are at most four cells long.
+            for start in address.saturating_sub(3)..address.saturating_add(1).min(cache.len()) {
+                if let Some(instruction) = cache[start] {
+                    if start + instruction.parameters_count >= address {
+                        cache[start] = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Retrieves the value of a parameter, according to its mode.
+    ///
+    /// instruction: the instruction where the parameter is.
+    /// parameter: the parameter index in the instruction (starts at zero).
+    fn get_parameter(
+        &mut self,
+        instruction: &Instruction,
+        parameter: usize,
+    ) -> Result<Option<Cell>> {
+        match instruction.parameters().get(parameter) {
+            Some(parameter) => match parameter.mode {
+                ParameterMode::Immediate => Ok(Some(parameter.data)),
+                _ => {
+                    let address = self.effective_address(instruction.address, parameter)?;
+                    if let Some(heatmap) = &mut self.heatmap {
+                        heatmap.record_read(address);
+                    }
+
+                    Ok(Some(self.get(address)))
+                }
+            }
+            .inspect(|value| {
+                if let (Some(event), Some(value)) = (&mut self.event, value) {
+                    event.values.push(*value);
+                }
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// Interprets a parameter of an instruction as an address, taking into
+    /// account the relative mode. Parameters in immediate mode are not
+    /// addresses.
+    fn get_address(&self, instruction: &Instruction, parameter: &Parameter) -> Result<usize> {
+        match parameter.mode {
+            ParameterMode::Immediate => Err(Error::InvalidAddress(
+                "Invalid write parameter: immediate mode",
+            )),
+            _ => self.effective_address(instruction.address, parameter),
+        }
+    }
+
+    /// Computes the address a parameter in position or relative mode
+    /// points to, for the instruction at `pointer`. Addresses can't be
+    /// negative, nor overflow.
+    fn effective_address(&self, pointer: usize, parameter: &Parameter) -> Result<usize> {
+        let address = match parameter.mode {
+            ParameterMode::Relative => add_to_base(self.relative_base, parameter.data)
+                .and_then(|address| usize::try_from(address).ok())
+                .ok_or(Error::InvalidRelativeAddress {
+                    pointer,
+                    base: self.relative_base,
+                    offset: parameter.data,
+                })?,
+            _ => usize::try_from(parameter.data).map_err(|_| {
+                Error::InvalidAddress(if parameter.data < 0 {
+                    "Negative address"
+                } else {
+                    "Address overflow"
+                })
+            })?,
+        };
+
+        Ok(address)
+    }
+
+    ///
+    /// Sets the input source of the program. It's a closure receiving
+    /// a number: the nth time an input is asked by the program (starts at
+    /// zero) and returning a Cell. It can keep its own state.
+    /// The count starts over with each run (see `inputs_consumed`), but
+    /// the state of the closure doesn't.
+    /// If not set, only the queued inputs are used (see `push_input`).
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs its three inputs.
+    /// let mut program: Program = "3,13,4,13,3,13,4,13,3,13,4,13,99,0".parse().unwrap();
+    ///
+    /// let mut next = 1;
+    /// program.set_input(move |_| {
+    ///     next *= 10;
+    ///     Ok(next)
+    /// });
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![10, 100, 1000]);
+    /// ```
+    pub fn set_input(&mut self, input: impl FnMut(usize) -> Result<Cell> + 'static) {
+        self.set_input_source(input);
+    }
+
+    /// Sets the input source of the program, e.g. one of those of the
+    /// `inputs` module, used when no input is queued. To read the inputs
+    /// from stdin, set a `StdinInput`.
+    pub fn set_input_source(&mut self, input: impl InputSource + 'static) {
+        self.input_source = Some(Rc::new(RefCell::new(input)));
+    }
+
+    /// Sets the sink receiving the outputs of the program, as they happen,
+    /// instead of collecting them (see the `outputs` module). Output limits
+    /// don't apply to sinks, as nothing accumulates.
+    pub fn set_output_sink(&mut self, sink: impl OutputSink + 'static) {
+        self.output_sink = Some(Box::new(sink));
+    }
+
+    /// Receives the inputs of the program from a channel, as a
+    /// `ChannelInput`.
+    pub fn set_input_channel(&mut self, receiver: Receiver<Cell>) {
+        self.set_input_source(ChannelInput(receiver));
+    }
+
+    ///
+    /// Reads the inputs of the program from a file, as a `FileInput`: a
+    /// number per line, skipping blank lines and `#` comments. The file
+    /// is read right away; errors name the line which is not a number.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::fs;
+    /// let path = std::env::temp_dir().join(format!("aoc-2019-inputs-{}.txt", std::process::id()));
+    ///
+    /// // Outputs its two inputs.
+    /// let mut program: Program = "3,9,4,9,3,9,4,9,99,0".parse().unwrap();
+    ///
+    /// fs::write(&path, "# Inputs\n4\n\n  2\n").unwrap();
+    /// program.set_input_file(&path).unwrap();
+    /// assert_eq!(program.execute().unwrap(), vec![4, 2]);
+    ///
+    /// fs::write(&path, "4\n").unwrap();
+    /// program.set_input_file(&path).unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "No more inputs in the file");
+    ///
+    /// fs::write(&path, "4\n\nfour\n").unwrap();
+    /// let error = program.set_input_file(&path).unwrap_err();
+    /// assert!(error.to_string().ends_with("line 3: not a number: four"));
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn set_input_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.set_input_source(FileInput::open(path)?);
+        Ok(())
+    }
+
+    ///
+    /// Sends the outputs of the program to a channel, as a `ChannelOutput`.
+    /// With the input channel, a program can run in its own thread.
+    ///
+    /// ```rust
+    /// # use lib::intcode::outputs::ClosedReceiver;
+    /// # use lib::intcode::{Cell, Program};
+    /// # use std::sync::mpsc::{channel, Receiver, Sender};
+    /// # use std::thread;
+    /// // Outputs its input, doubled, until it receives 0; then outputs 0.
+    /// fn spawn_doubler(input: Receiver<Cell>, output: Sender<Cell>) -> thread::JoinHandle<()> {
+    ///     thread::spawn(move || {
+    ///         let mut program: Program = "3,15,1002,15,2,16,4,16,1005,15,0,99,0,0,0,0,0"
+    ///             .parse()
+    ///             .unwrap();
+    ///         program.set_input_channel(input);
+    ///         program.set_output_channel(output, ClosedReceiver::Fail);
+    ///         program.execute().unwrap();
+    ///     })
+    /// }
+    ///
+    /// let (to_producer, producer_input) = channel();
+    /// let (producer_output, consumer_input) = channel();
+    /// let (consumer_output, from_consumer) = channel();
+    ///
+    /// let producer = spawn_doubler(producer_input, producer_output);
+    /// let consumer = spawn_doubler(consumer_input, consumer_output);
+    ///
+    /// for value in &[1, 2, 3, 0] {
+    ///     to_producer.send(*value).unwrap();
+    /// }
+    ///
+    /// producer.join().unwrap();
+    /// consumer.join().unwrap();
+    /// assert_eq!(from_consumer.iter().collect::<Vec<_>>(), vec![4, 8, 12, 0]);
+    /// ```
+    pub fn set_output_channel(&mut self, sender: Sender<Cell>, on_closed: ClosedReceiver) {
+        self.set_output_sink(ChannelOutput::new(sender, on_closed));
+    }
+
+    ///
+    /// Copies the program in its current state, e.g. to explore several
+    /// paths from a decision point: memory, pointers, outputs, and inputs
+    /// (queued or already given) are copied, so both programs continue
+    /// independently from there.
+    ///
+    /// The input source is shared between the program and its fork (it's
+    /// called with each program's own input count), so the inputs of a
+    /// queue or a channel are given to the first one asking. The far write warning,
+    /// the transcript, the I/O log, the output echo, the output breaks and the
+    /// output sink are not copied: the fork collects its outputs.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Event, Program};
+    /// // Outputs its input, doubled, until it receives 0.
+    /// let mut program: Program = "3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// program.push_input(3);
+    /// assert_eq!(program.run_until_event().unwrap(), Event::Output(6));
+    /// assert_eq!(program.run_until_event().unwrap(), Event::NeedsInput);
+    ///
+    /// let mut fork = program.fork();
+    ///
+    /// program.push_input(1);
+    /// fork.push_input(5);
+    /// assert_eq!(program.run_until_event().unwrap(), Event::Output(2));
+    /// assert_eq!(fork.run_until_event().unwrap(), Event::Output(10));
+    ///
+    /// fork.push_input(0);
+    /// assert_eq!(fork.run_until_event().unwrap(), Event::Halted);
+    /// assert!(program.is_running());
+    ///
+    /// assert_eq!(program.output(), vec![6, 2]);
+    /// assert_eq!(fork.output(), vec![6, 10]);
+    /// ```
+    pub fn fork(&self) -> Program {
+        Program {
+            memory: self.memory.clone(),
+            sparse: self.sparse.clone(),
+            initial_memory: Rc::clone(&self.initial_memory),
+            patches: self.patches.clone(),
+            pointer: self.pointer,
+            relative_base: self.relative_base,
+            input_source: self.input_source.clone(),
+            input_count: self.input_count,
+            queued_input: self.queued_input.clone(),
+            input_default: self.input_default,
+            idle_reads: self.idle_reads,
+            output: self.output.clone(),
+            drained_output: self.drained_output,
+            output_sink: None,
+            emitted: self.emitted,
+            max_outputs: self.max_outputs,
+            status: self.status,
+            program_len: self.program_len,
+            highest_written: self.highest_written,
+            memory_limit: self.memory_limit,
+            steps: self.steps,
+            max_steps: self.max_steps,
+            strict_jumps: self.strict_jumps,
+            loop_detection: self.loop_detection.clone(),
+            far_write_warning: None,
+            transcript: None,
+            io_log: None,
+            output_echo: None,
+            observer: None,
+            event: self.event.as_ref().and(Some(InstructionEvent::default())),
+            history: self.history.clone(),
+            history_depth: self.history_depth,
+            heatmap: self.heatmap.clone(),
+            coverage: self.coverage.clone(),
+            profiler: self.profiler.clone(),
+            decode_cache: self.decode_cache.clone(),
+            custom_opcodes: self.custom_opcodes.clone(),
+            on_unknown_opcode: self.on_unknown_opcode,
+            breakpoints: self.breakpoints.clone(),
+            resuming: self.resuming,
+            watchpoints: self.watchpoints.clone(),
+            watched_write: None,
+            output_breaks: vec![],
+            recording: self.recording.clone(),
+        }
+    }
+
+    ///
+    /// Queues an input, given to the program the next time it asks for
+    /// one, before the input source is used. Without an input source, the
+    /// program fails if it asks for an input while none is queued.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs its input.
+    /// let mut program: Program = "3,5,4,5,99,0".parse().unwrap();
+    /// program.push_input(42);
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    ///
+    /// assert_eq!(program.execute().unwrap_err().message(), "No more inputs");
+    /// ```
+    pub fn push_input(&mut self, value: Cell) {
+        self.queued_input.push_back(value);
+    }
+
+    ///
+    /// Queues several inputs, in order, as `push_input`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{ExecutionState, Program};
+    /// // Outputs the sum of each pair of inputs, until the sum is zero.
+    /// let mut program: Program = "3,17,3,18,1,17,18,19,4,19,1005,19,0,99,0,0,0,0,0,0"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// program.push_inputs(vec![1, 2, 3]);
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::WaitingForInput);
+    /// assert_eq!(program.output(), vec![3]);
+    ///
+    /// program.push_inputs(vec![4, 5]);
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::WaitingForInput);
+    /// assert_eq!(program.output(), vec![3, 7]);
+    ///
+    /// program.push_inputs(vec![6, -5, 5]);
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::Halted);
+    /// assert_eq!(program.output(), vec![3, 7, 11, 0]);
+    /// ```
+    pub fn push_inputs(&mut self, values: impl IntoIterator<Item = Cell>) {
+        self.queued_input.extend(values);
+    }
+
+    ///
+    /// Gives an input to the program, and resumes it until it halts, e.g.
+    /// after its input source failed: a program failing to get an input
+    /// stays on its input instruction, as if it wasn't executed.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// // Outputs the sum of its two inputs.
+    /// let source_code = "3,11,3,12,1,11,12,13,4,13,99,0,0,0";
+    ///
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// program.set_input(|n| match n {
+    ///     0 => Ok(40),
+    ///     _ => Err(Error::InvalidInput("Not yet")),
+    /// });
+    ///
+    /// assert_eq!(program.execute().unwrap_err().message(), "Not yet");
+    /// assert_eq!((program.pointer(), program.steps_executed()), (2, 1));
+    /// assert_eq!(program.provide_input(2).unwrap(), vec![42]);
+    ///
+    /// let mut uninterrupted: Program = source_code.parse().unwrap();
+    /// assert_eq!(program.output(), uninterrupted.execute_with_inputs(&[40, 2]).unwrap());
+    /// ```
+    ///
+    /// Panics of the input source are failures too:
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// # std::panic::set_hook(Box::new(|_| {}));
+    /// // Outputs the sum of its two inputs.
+    /// let mut program: Program = "3,11,3,12,1,11,12,13,4,13,99,0,0,0".parse().unwrap();
+    /// program.set_input(|n| match n {
+    ///     0 => Ok(40),
+    ///     _ => panic!("Input {} unavailable", n),
+    /// });
+    ///
+    /// let error = program.execute().unwrap_err();
+    /// assert_eq!(error.cause(), &Error::CallbackPanicked("Input 1 unavailable".to_string()));
+    /// assert_eq!(program.pointer(), 2);
+    /// assert_eq!(program.provide_input(2).unwrap(), vec![42]);
+    /// ```
+    pub fn provide_input(&mut self, value: Cell) -> Result<Vec<Cell>> {
+        self.push_input(value);
+        self.execute()
+    }
+
+    ///
+    /// Sets an input given to the program when it asks for one while none
+    /// is available, instead of failing or pausing: none is queued, and
+    /// the input source is not set or not ready. With a default, sources
+    /// which can't tell if they are ready (closures, channels) are never
+    /// used. `idle_reads` counts the defaults given.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs its inputs, forever.
+    /// let mut program: Program = "3,7,4,7,1105,1,0,0".parse().unwrap();
+    /// program.set_input_default(-1);
+    /// program.push_input(5);
+    ///
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(5));
+    /// assert_eq!(program.idle_reads(), 0);
+    ///
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(-1));
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(-1));
+    /// assert_eq!(program.idle_reads(), 2);
+    ///
+    /// program.push_input(6);
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(6));
+    /// assert_eq!(program.idle_reads(), 2);
+    /// ```
+    pub fn set_input_default(&mut self, value: Cell) {
+        self.input_default = Some(value);
+    }
+
+    /// Returns the number of times the default input was given since the
+    /// program started. See `set_input_default`.
+    pub fn idle_reads(&self) -> usize {
+        self.idle_reads
+    }
+
+    /// Checks if the input source is set and ready to give an input.
+    fn input_source_ready(&self) -> bool {
+        self.input_source
+            .as_ref()
+            .is_some_and(|source| source.borrow().is_ready())
+    }
+
+    /// Requests an input from the queued inputs, or else from the
+    /// input source set, or else gives the default input, if set.
+    fn request_input(&mut self) -> Result<Cell> {
+        let default = self.input_default.filter(|_| !self.input_source_ready());
+
+        let input = match (self.queued_input.pop_front(), default, &self.input_source) {
+            (Some(value), _, _) => Ok(value),
+            (None, Some(value), _) => {
+                self.idle_reads += 1;
+                Ok(value)
+            }
+            (None, None, Some(source)) => {
+                catch_callback_panic(|| source.borrow_mut().next(self.input_count))
+            }
+            (None, None, None) => Err(Error::InvalidInput("No more inputs")),
+        };
+        if let Ok(value) = input {
+            self.undo_input(value);
+            self.input_count += 1;
+            self.record(|transcript| transcript.record_input(value));
+            self.log_io("in", value);
+        }
+
+        input
+    }
+
+    /// Records the session of an ASCII program into a transcript file: every
+    /// character outputted, and every line inputted prefixed by `> `, in order.
+    /// Errors writing the transcript don't stop the program: a warning is
+    /// printed, and the recording stops.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::fs;
+    /// let path = std::env::temp_dir().join("intcode-transcript-doctest.txt");
+    ///
+    /// // Echoes a line.
+    /// let mut program: Program = "3,100,4,100,1008,100,10,101,1006,101,0,99".parse().unwrap();
+    /// program.set_input(|n| Ok("hi\n".as_bytes()[n] as _));
+    /// program.record_transcript(&path).unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(fs::read_to_string(&path).unwrap(), "hi\n> hi\n\n");
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn record_transcript(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.transcript = Some(Transcript {
+            file: File::create(path)?,
+            pending_input: String::new(),
+            at_line_start: true,
+        });
+
+        Ok(())
+    }
+
+    /// Writes into the transcript, if recorded.
+    fn record(&mut self, write: impl FnOnce(&mut Transcript) -> io::Result<()>) {
+        if let Some(transcript) = &mut self.transcript {
+            if let Err(e) = write(transcript) {
+                eprintln!("Unable to write the transcript, recording stopped: {}", e);
+                self.transcript = None;
+            }
+        }
+    }
+
+    /// Echoes each output to stderr as it happens, if enabled, e.g. to watch
+    /// a long execution. See `set_output_echo_to`.
+    pub fn set_output_echo(&mut self, enabled: bool) {
+        self.output_echo = if enabled {
+            Some(Box::new(io::stderr()))
+        } else {
+            None
+        };
+    }
+
+    ///
+    /// Echoes each output to a writer as it happens, on its own line,
+    /// prefixed by the number of the step and the address of the
+    /// instruction outputting it. The outputs are still collected, or
+    /// given to the output sink. Errors writing the echo don't stop the
+    /// program: a warning is printed, and the echo stops.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::fs::{self, File};
+    /// let path = std::env::temp_dir().join(format!("aoc-2019-echo-{}.txt", std::process::id()));
+    ///
+    /// let mut program: Program = "104,1,1101,20,22,9,4,9,99,0".parse().unwrap();
+    /// program.set_output_echo_to(File::create(&path).unwrap());
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![1, 42]);
+    /// assert_eq!(fs::read_to_string(&path).unwrap(), "[step 1 @ 0] 1\n[step 3 @ 6] 42\n");
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn set_output_echo_to(&mut self, writer: impl Write + 'static) {
+        self.output_echo = Some(Box::new(writer));
+    }
+
+    ///
+    /// Logs every input read and output produced by the program into a
+    /// writer, in order, a line each, with the number of the step reading
+    /// or producing it. Works with any input source and output sink.
+    /// Errors writing the log don't stop the program: a warning is
+    /// printed, and the recording stops.
+    pub fn record_io_log(&mut self, writer: impl Write + 'static) {
+        self.io_log = Some(IoLog::Writer(Box::new(writer)));
+    }
+
+    ///
+    /// Logs the inputs and outputs of the program in memory, as
+    /// `record_io_log`, to be read with `io_log`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Echoes its inputs, until it receives 0.
+    /// let mut program: Program = "3,9,4,9,1005,9,0,99,0,0".parse().unwrap();
+    /// program.push_inputs(vec![5, 139, 0]);
+    /// program.record_io_log_in_memory();
+    ///
+    /// program.execute().unwrap();
+    /// assert_eq!(
+    ///     program.io_log().unwrap(),
+    ///     "in  #1  5\nout #2  5\nin  #4  139\nout #5  139\nin  #7  0\nout #8  0\n"
+    /// );
+    /// ```
+    pub fn record_io_log_in_memory(&mut self) {
+        self.io_log = Some(IoLog::Memory(String::new()));
+    }
+
+    /// Returns the inputs and outputs logged, if logged in memory. See
+    /// `record_io_log_in_memory`.
+    pub fn io_log(&self) -> Option<&str> {
+        match &self.io_log {
+            Some(IoLog::Memory(log)) => Some(log),
+            _ => None,
+        }
+    }
+
+    /// Writes an input or an output into the I/O log, if recorded.
+    fn log_io(&mut self, direction: &str, value: Cell) {
+        if let Some(log) = &mut self.io_log {
+            if let Err(e) = log.record(direction, self.steps, value) {
+                eprintln!("Unable to write the I/O log, recording stopped: {}", e);
+                self.io_log = None;
+            }
+        }
+    }
+
+    /// Returns the values outputted by the program since it started, except
+    /// those drained by `take_output` or `pop_output`.
+    pub fn output(&self) -> &[Cell] {
+        self.output.as_slices().0
+    }
+
+    ///
+    /// Removes the values outputted by the program from its outputs, and
+    /// returns them. Long-running programs can be drained regularly, so
+    /// their outputs don't grow forever.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,104,4,99".parse().unwrap();
+    /// let mut received = vec![];
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// program.execute_until_next_output().unwrap();
+    /// received.extend(program.take_output());
+    /// assert!(program.output().is_empty());
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// received.extend(program.pop_output());
+    /// assert_eq!(program.pop_output(), None);
+    ///
+    /// program.execute().unwrap();
+    /// received.extend(program.take_output());
+    ///
+    /// assert_eq!(received, vec![1, 2, 3, 4]);
+    /// assert_eq!((program.output_len(), program.outputs_produced()), (0, 4));
+    /// ```
+    pub fn take_output(&mut self) -> Vec<Cell> {
+        self.drained_output += self.output.len();
+        std::mem::take(&mut self.output).into()
+    }
+
+    ///
+    /// Sets a hook called after each instruction executed, before the
+    /// pointer moves to the next one, with the address of the instruction,
+    /// its opcode, the values of the parameters it read, and what it wrote.
+    /// Tracing and other instrumentation can be built on it; without an
+    /// observer, nothing is collected.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{InstructionEvent, OpCode, Operation, Program};
+    /// # use std::cell::RefCell;
+    /// # use std::rc::Rc;
+    /// let events = Rc::new(RefCell::new(vec![]));
+    /// let observed = Rc::clone(&events);
+    ///
+    /// let mut program: Program = "1101,2,3,7,4,7,99,0".parse().unwrap();
+    /// program.set_observer(move |event| observed.borrow_mut().push(event.clone()));
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(
+    ///     *events.borrow(),
+    ///     vec![
+    ///         InstructionEvent {
+    ///             step: 1,
+    ///             address: 0,
+    ///             instruction: 1101,
+    ///             opcode: Some(OpCode::Arithmetic(Operation::Add)),
+    ///             parameters: vec![2, 3, 7],
+    ///             values: vec![2, 3],
+    ///             write: Some((7, 5)),
+    ///         },
+    ///         InstructionEvent {
+    ///             step: 2,
+    ///             address: 4,
+    ///             instruction: 4,
+    ///             opcode: Some(OpCode::Output),
+    ///             parameters: vec![7],
+    ///             values: vec![5],
+    ///             write: None,
+    ///         },
+    ///         InstructionEvent {
+    ///             step: 3,
+    ///             address: 6,
+    ///             instruction: 99,
+    ///             opcode: Some(OpCode::Halt),
+    ///             parameters: vec![],
+    ///             values: vec![],
+    ///             write: None,
+    ///         },
+    ///     ]
+    /// );
+    /// ```
+    pub fn set_observer(&mut self, observer: impl FnMut(&InstructionEvent) + 'static) {
+        self.observer = Some(Box::new(observer));
+        self.update_event();
+    }
+
+    ///
+    /// Sets how many of the last instructions executed are remembered (8 by
+    /// default), with the values of their parameters and what they wrote.
+    /// Errors while running an instruction carry them, to see what led
+    /// there. With 0, nothing is remembered.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, InstructionEvent, JumpCondition, OpCode, Program};
+    /// // Outputs 1, but a corrupted jump lands in data.
+    /// let mut program: Program = "1105,1,4,42,104,1,99".parse().unwrap();
+    /// program.patch(2, 3).unwrap();
+    ///
+    /// let error = program.execute().unwrap_err();
+    /// assert!(matches!(error.cause(), Error::UnknownOpcode { opcode: 42, .. }));
+    /// match &error {
+    ///     Error::Instruction { history, .. } => assert_eq!(
+    ///         history,
+    ///         &vec![InstructionEvent {
+    ///             step: 1,
+    ///             address: 0,
+    ///             instruction: 1105,
+    ///             opcode: Some(OpCode::Jump(JumpCondition::IfTrue)),
+    ///             parameters: vec![1, 3],
+    ///             values: vec![1, 3],
+    ///             write: None,
+    ///         }]
+    ///     ),
+    ///     error => panic!("no context: {}", error),
+    /// }
+    /// assert!(error.to_string().ends_with(
+    ///     "Last instructions executed:\n  0: 1105 1,3\n\
+    ///      Memory usage: 7 cells loaded, 7 allocated, 0 sparse; highest address written: 2"
+    /// ));
+    ///
+    /// program.reset_keep_patches();
+    /// program.set_history_depth(0);
+    /// assert!(matches!(
+    ///     program.execute().unwrap_err(),
+    ///     Error::Instruction { history, .. } if history.is_empty()
+    /// ));
+    /// ```
+    ///
+    /// The programs created afterwards on the thread can remember another
+    /// number of instructions by default, with `set_history_depth_default`.
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
+        while self.history.len() > depth {
+            self.history.pop_front();
+        }
+
+        self.update_event();
+    }
+
+    /// Collects the instructions executed as events if they are observed,
+    /// kept in the history, or instrumented; else, collects nothing.
+    fn update_event(&mut self) {
+        let observed = self.observer.is_some()
+            || self.history_depth > 0
+            || self.coverage.is_some()
+            || self.profiler.is_some();
+
+        if !observed {
+            self.event = None;
+        } else if self.event.is_none() {
+            self.event = Some(InstructionEvent::default());
+        }
+    }
+
+    ///
+    /// Sets the number of outputs the program can accumulate (10 million by
+    /// default). Outputting past the limit fails with "Output limit
+    /// exceeded", so a program stuck outputting forever stops before
+    /// exhausting the memory. The outputs produced before are kept.
+    ///
+    /// Drained outputs don't count (see `take_output`).
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs 7 forever.
+    /// let mut program: Program = "104,7,1105,1,0".parse().unwrap();
+    /// program.set_max_outputs(3);
+    ///
+    /// assert_eq!(program.execute().unwrap_err().message(), "Output limit exceeded");
+    /// assert_eq!(program.output(), &[7, 7, 7]);
+    ///
+    /// program.take_output();
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(7));
+    /// ```
+    pub fn set_max_outputs(&mut self, outputs: usize) {
+        self.max_outputs = outputs;
+    }
+
+    /// Removes the oldest value outputted by the program from its outputs,
+    /// and returns it, if any. See `take_output`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs 1 to 100.
+    /// let mut program: Program = "1001,14,1,14,4,14,1007,14,100,15,1005,15,0,99,0,0".parse().unwrap();
+    ///
+    /// let mut received = vec![];
+    /// for _ in 0..50 {
+    ///     program.execute_until_next_output().unwrap();
+    ///     program.execute_until_next_output().unwrap();
+    ///     received.extend(program.pop_output());
+    /// }
+    /// assert_eq!(program.output(), (51..=100).collect::<Vec<_>>().as_slice());
+    ///
+    /// received.extend(std::iter::from_fn(|| program.pop_output()));
+    /// assert_eq!(received, (1..=100).collect::<Vec<_>>());
+    /// assert!(program.execute().unwrap().is_empty());
+    /// ```
+    pub fn pop_output(&mut self) -> Option<Cell> {
+        let output = self.output.pop_front()?;
+        self.drained_output += 1;
+        Some(output)
+    }
+
+    ///
+    /// Returns the last value outputted by the program, if any, without
+    /// copying the outputs.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Event, Program};
+    /// let mut program: Program = "104,1,104,2,3,0,104,3,99".parse().unwrap();
+    /// assert_eq!(program.last_output(), None);
+    ///
+    /// let mut seen = vec![];
+    /// loop {
+    ///     match program.run_until_event().unwrap() {
+    ///         Event::Output(_) => seen.push((program.output_len(), program.last_output())),
+    ///         Event::NeedsInput => program.push_input(0),
+    ///         Event::Halted | Event::Stopped(_) => break,
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(seen, vec![(1, Some(1)), (2, Some(2)), (3, Some(3))]);
+    /// ```
+    pub fn last_output(&self) -> Option<Cell> {
+        self.output.back().cloned()
+    }
+
+    /// Returns the number of values in the outputs of the program, not
+    /// counting those drained.
+    pub fn output_len(&self) -> usize {
+        self.output.len()
+    }
+
+    /// Same as output, but concatenates all output into a String, without
+    /// separators (see `output_joined` to tell the values apart).
+    pub fn output_str(&self) -> String {
+        self.output.iter().map(|o| o.to_string()).collect()
+    }
+
+    ///
+    /// Returns the outputs as a String, separated by `separator`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,10,104,-1,99".parse().unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(program.output_str(), "10-1");
+    /// assert_eq!(program.output_joined(" "), "10 -1");
+    /// assert_eq!(program.output_csv(), "10,-1");
+    /// assert_eq!(program.output_lines(), "10\n-1");
+    ///
+    /// let mut program: Program = "104,-3,99".parse().unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.output_csv(), "-3");
+    ///
+    /// let mut program: Program = "99".parse().unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.output_csv(), "");
+    /// ```
+    pub fn output_joined(&self, separator: &str) -> String {
+        self.output.iter().join(separator)
+    }
+
+    /// Returns the outputs as a String, separated by commas.
+    pub fn output_csv(&self) -> String {
+        self.output_joined(",")
+    }
+
+    /// Returns the outputs as a String, one per line.
+    pub fn output_lines(&self) -> String {
+        self.output_joined("\n")
+    }
+
+    ///
+    /// Returns the outputs grouped into chunks of `n` values, e.g. for
+    /// programs outputting coordinates. Fails if the outputs can't be
+    /// split evenly.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,104,4,99".parse().unwrap();
+    /// program.execute().unwrap();
+    ///
+    /// assert_eq!(program.output_chunks(2).unwrap(), vec![vec![1, 2], vec![3, 4]]);
+    /// assert_eq!(program.output_chunks(3).unwrap_err().message(), "Outputs not divisible into chunks");
+    /// ```
+    pub fn output_chunks(&self, n: usize) -> Result<Vec<Vec<Cell>>> {
+        if n == 0 || !self.output.len().is_multiple_of(n) {
+            return Err(Error::InvalidOutput("Outputs not divisible into chunks"));
+        }
+
+        Ok(self
+            .output()
+            .chunks(n)
+            .map(|chunk| chunk.to_vec())
+            .collect())
+    }
+
+    ///
+    /// Executes the program until it outputs `n` more values, and returns
+    /// them, or None if it halted first. If it halts in the middle of a
+    /// chunk, it fails.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,104,4,104,5,104,6,99".parse().unwrap();
+    /// assert_eq!(program.next_output_chunk(3).unwrap(), Some(vec![1, 2, 3]));
+    /// assert_eq!(program.next_output_chunk(3).unwrap(), Some(vec![4, 5, 6]));
+    /// assert_eq!(program.next_output_chunk(3).unwrap(), None);
+    ///
+    /// let mut program: Program = "104,1,104,2,104,3,99".parse().unwrap();
+    /// assert_eq!(program.next_output_chunk(2).unwrap(), Some(vec![1, 2]));
+    /// assert_eq!(program.next_output_chunk(2).unwrap_err().message(), "Incomplete output chunk");
+    /// ```
+    pub fn next_output_chunk(&mut self, n: usize) -> Result<Option<Vec<Cell>>> {
+        let chunk = self.outputs().take(n).collect::<Result<Vec<Cell>>>()?;
+
+        match chunk.len() {
+            0 => Ok(None),
+            len if len == n => Ok(Some(chunk)),
+            _ => Err(Error::InvalidOutput("Incomplete output chunk")),
+        }
+    }
+
+    /// Returns a cursor positioned after the last value outputted so far.
+    /// Each reader can keep its own cursor, and use `outputs_after` to
+    /// get what the program outputted since.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,99".parse().unwrap();
+    /// let mut first_reader = program.output_cursor();
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// let second_reader = program.output_cursor();
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// assert_eq!(program.outputs_after(&first_reader), &[1, 2]);
+    /// first_reader = program.output_cursor();
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// assert_eq!(program.outputs_after(&first_reader), &[3]);
+    /// assert_eq!(program.outputs_after(&second_reader), &[2, 3]);
+    /// ```
+    pub fn output_cursor(&self) -> OutputCursor {
+        OutputCursor(self.outputs_produced())
+    }
+
+    ///
+    /// Returns the values outputted since the cursor was created, except
+    /// those drained since.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,99".parse().unwrap();
+    /// program.execute_until_next_output().unwrap();
+    /// let cursor = program.output_cursor();
+    ///
+    /// program.execute_until_next_output().unwrap();
+    /// program.take_output();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.outputs_after(&cursor), &[3]);
+    /// ```
+    pub fn outputs_after(&self, cursor: &OutputCursor) -> &[Cell] {
+        let start = cursor.0.saturating_sub(self.drained_output);
+        &self.output()[start.min(self.output.len())..]
+    }
+
+    ///
+    /// Restores the program to its state when loaded: memory, pointers,
+    /// outputs and inputs count. Patches are dropped, but not queued inputs.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "1,0,0,0,99".parse().unwrap();
+    /// program.patch(1, 4).unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.get(0), 100);
+    ///
+    /// program.reset();
+    /// assert_eq!(program.get(0), 1);
+    /// program.execute().unwrap();
+    /// assert_eq!(program.get(0), 2);
+    /// ```
+    pub fn reset(&mut self) {
+        self.patches.clear();
+        self.reset_keep_patches();
+    }
+
+    ///
+    /// Restores the program to its state when loaded, as `reset`, then
+    /// re-applies the patches. That's what happens when a halted program is
+    /// executed again, so each execution gives the same results.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Adds the two cells after the halt instruction, and outputs the result.
+    /// let mut program: Program = "1,7,8,7,4,7,99,2,3".parse().unwrap();
+    /// program.patch(8, 40).unwrap();
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// ```
+    pub fn reset_keep_patches(&mut self) {
+        self.memory = self.initial_memory.to_vec();
+        if let Some(cells) = &mut self.sparse {
+            cells.clear();
+        }
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+        self.pointer = 0;
+        self.relative_base = 0;
+        self.history.clear();
+        self.output.clear();
+        self.drained_output = 0;
+        self.emitted = None;
+        self.input_count = 0;
+        self.idle_reads = 0;
+        self.highest_written = None;
+        self.steps = 0;
+        self.resuming = false;
+        if let Some((_, states)) = &mut self.loop_detection {
+            states.clear();
+        }
+        if let Some(heatmap) = &mut self.heatmap {
+            heatmap.clear();
+        }
+        if let Some(recording) = &mut self.recording {
+            recording.clear();
+        }
+        if let Some(executed) = &mut self.coverage {
+            executed.clear();
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.clear();
+        }
+        self.status = ExecutionStatus::NotStarted;
+
+        for (address, value) in self.patches.clone() {
+            self.store(address, value);
+        }
+    }
+
+    /// Starts the program, from the beginning if it's not running,
+    /// or else where it left.
+    fn start(&mut self) {
+        if !self.is_running() {
+            self.reset_keep_patches();
+        }
+
+        self.status = ExecutionStatus::Running;
+    }
+
+    ///
+    /// Returns where the program is in its lifecycle.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{ExecutionStatus, Program};
+    /// // Outputs its input, plus one.
+    /// let mut program: Program = "3,9,1001,9,1,9,4,9,99,0".parse().unwrap();
+    /// assert_eq!(program.status(), ExecutionStatus::NotStarted);
+    ///
+    /// program.run_until_event().unwrap();
+    /// assert_eq!(program.status(), ExecutionStatus::WaitingForInput);
+    ///
+    /// program.push_input(41);
+    /// program.run_until_event().unwrap();
+    /// assert_eq!(program.status(), ExecutionStatus::Running);
+    ///
+    /// program.run_until_event().unwrap();
+    /// assert_eq!(program.status(), ExecutionStatus::Halted);
+    ///
+    /// program.reset();
+    /// assert_eq!(program.status(), ExecutionStatus::NotStarted);
+    ///
+    /// let mut program: Program = "98".parse().unwrap();
+    /// assert!(program.execute().is_err());
+    /// assert_eq!(program.status(), ExecutionStatus::Failed);
+    /// ```
+    pub fn status(&self) -> ExecutionStatus {
+        self.status
+    }
+
+    /// Checks if the program was started and didn't halt: executing it
+    /// again resumes it where it stopped.
+    pub fn is_running(&self) -> bool {
+        match self.status {
+            ExecutionStatus::Running
+            | ExecutionStatus::WaitingForInput
+            | ExecutionStatus::Failed
+            | ExecutionStatus::Stopped(_) => true,
+            ExecutionStatus::NotStarted | ExecutionStatus::Halted => false,
+        }
+    }
+
+    ///
+    /// Returns the address of the next instruction to execute.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "109,4,1101,1,1,20,104,7,99".parse().unwrap();
+    /// assert_eq!(program.pointer(), 0);
+    ///
+    /// program.step().unwrap();
+    /// assert_eq!((program.pointer(), program.relative_base()), (2, 4));
+    ///
+    /// program.step().unwrap();
+    /// assert_eq!((program.pointer(), program.outputs_produced()), (6, 0));
+    ///
+    /// program.step().unwrap();
+    /// assert_eq!((program.pointer(), program.outputs_produced()), (8, 1));
+    /// ```
+    pub fn pointer(&self) -> usize {
+        self.pointer
+    }
+
+    ///
+    /// Returns the current relative base, used by parameters in relative
+    /// mode. It can be negative, as long as the addresses computed from it
+    /// are not.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "109,-5,99".parse().unwrap();
+    /// program.execute().unwrap();
+    /// assert_eq!(program.relative_base(), -5);
+    ///
+    /// let mut program: Program = "109,1000000,21101,1,1,0,204,0,99".parse().unwrap();
+    /// assert_eq!(program.execute().unwrap(), vec![2]);
+    /// assert_eq!(program.relative_base(), 1_000_000);
+    /// ```
+    ///
+    /// Invalid addresses in relative mode are errors, giving the relative
+    /// base and the offset.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Error, Program};
+    /// let mut program: Program = "109,-5,204,1,99".parse().unwrap();
+    /// let error = program.execute().unwrap_err();
+    /// assert_eq!(
+    ///     error.cause(),
+    ///     &Error::InvalidRelativeAddress { pointer: 2, base: -5, offset: 1 }
+    /// );
+    /// assert!(error
+    ///     .to_string()
+    ///     .starts_with("Negative address: relative base -5 plus offset 1, in the instruction at address 2"));
+    ///
+    /// let mut program: Program = format!("109,{},204,1,99", i64::MAX).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Address overflow");
+    ///
+    /// // The base itself can't overflow either.
+    /// let mut program: Program = format!("109,{},109,1,99", i64::MAX).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Relative base overflow");
+    /// let mut program: Program = format!("109,{},109,-1,99", i64::MIN).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Relative base overflow");
+    ///
+    /// // Up to the memory limit, large addresses are fine.
+    /// let mut program: Program = "109,990,21101,1,1,9,204,9,99".parse().unwrap();
+    /// program.set_memory_limit(1000);
+    /// assert_eq!(program.execute().unwrap(), vec![2]);
+    ///
+    /// let mut program: Program = "109,990,21101,1,1,10,204,10,99".parse().unwrap();
+    /// program.set_memory_limit(1000);
+    /// assert_eq!(program.execute().unwrap_err().message(), "Memory limit exceeded");
+    /// ```
+    pub fn relative_base(&self) -> i64 {
+        self.relative_base
+    }
+
+    ///
+    /// Returns the number of inputs read by the program since it started,
+    /// i.e. the request index given to the input source for the next one.
+    /// Resets, and executions of a halted program, start it over at zero,
+    /// so index-sensitive input sources see the same indexes on each run.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Cell, Program};
+    /// // Outputs the sum of its two inputs.
+    /// let mut program: Program = "3,11,3,12,1,11,12,13,4,13,99,0,0,0".parse().unwrap();
+    /// program.set_input(|n| Ok(if n == 0 { 40 } else { 2 }));
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// assert_eq!((program.inputs_consumed(), program.outputs_produced()), (2, 1));
+    ///
+    /// program.reset();
+    /// assert_eq!((program.inputs_consumed(), program.outputs_produced()), (0, 0));
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// assert_eq!(program.execute().unwrap(), vec![42]);
+    /// ```
+    pub fn inputs_consumed(&self) -> usize {
+        self.input_count
+    }
+
+    /// Returns the number of values outputted by the program so far,
+    /// including those drained.
+    pub fn outputs_produced(&self) -> usize {
+        self.drained_output + self.output.len()
+    }
+
+    ///
+    /// Executes the program, and returns the output of
+    /// its execution.
+    ///
+    /// Arithmetic overflows are errors, instead of panicking or wrapping:
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Cell, Program};
+    /// let mut program: Program = format!("1002,5,2,5,99,{}", Cell::MAX / 2 + 1).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Arithmetic overflow");
+    ///
+    /// let mut program: Program = format!("1001,5,1,5,99,{}", Cell::MAX).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Arithmetic overflow");
+    ///
+    /// let mut program: Program = format!("109,{},109,1,99", i64::MAX).parse().unwrap();
+    /// assert_eq!(program.execute().unwrap_err().message(), "Relative base overflow");
+    /// ```
+    ///
+    /// So are parameters written to in immediate mode; they can be in
+    /// position or relative mode only.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// for source_code in &["11101,1,1,0,99", "103,0,99", "11108,1,1,0,99"] {
+    ///     let mut program: Program = source_code.parse().unwrap();
+    ///     program.push_input(1);
+    ///
+    ///     let error = program.execute().unwrap_err();
+    ///     assert_eq!(error.message(), "Invalid write parameter: immediate mode");
+    /// }
+    ///
+    /// let mut program: Program = "109,100,21101,20,22,0,203,1,204,0,204,1,99".parse().unwrap();
+    /// program.push_input(7);
+    /// assert_eq!(program.execute().unwrap(), vec![42, 7]);
+    /// ```
+    ///
+    /// Negative addresses are errors too, in position and relative modes.
+    /// The relative base itself may be negative.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// for source_code in &["4,-1,99", "1101,1,1,-5,99", "109,-3,204,1,99", "109,2,21101,1,1,-5,99"] {
+    ///     let mut program: Program = source_code.parse().unwrap();
+    ///     assert_eq!(program.execute().unwrap_err().message(), "Negative address");
+    /// }
+    ///
+    /// let mut program: Program = "109,-1,204,5,99".parse().unwrap();
+    /// assert_eq!(program.execute().unwrap(), vec![99]);
+    /// ```
+    pub fn execute(&mut self) -> Result<Vec<Cell>> {
+        self.execute0(false)?;
+        Ok(self.output().to_vec())
+    }
+
+    ///
+    /// Executes the program with the given inputs, in order, after those
+    /// already queued, and returns its outputs. Fails if the program asks
+    /// for more inputs; extra inputs are ignored. The inputs replace the
+    /// input source of the program.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs the sum of its two inputs.
+    /// let source_code = "3,11,3,12,1,11,12,13,4,13,99,0,0,0";
+    ///
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// assert_eq!(program.execute_with_inputs(&[40, 2]).unwrap(), vec![42]);
+    /// assert_eq!(program.execute_with_inputs(&[40, 2, 7]).unwrap(), vec![42]);
+    ///
+    /// let mut program: Program = source_code.parse().unwrap();
+    /// assert_eq!(
+    ///     program.execute_with_inputs(&[40]).unwrap_err().message(),
+    ///     "More inputs requested than provided"
+    /// );
+    /// ```
+    pub fn execute_with_inputs(&mut self, inputs: &[Cell]) -> Result<Vec<Cell>> {
+        let mut inputs: VecDeque<Cell> = inputs.iter().cloned().collect();
+        self.set_input(move |_| {
+            inputs
+                .pop_front()
+                .ok_or(Error::InvalidInput("More inputs requested than provided"))
+        });
+
+        self.execute()
+    }
+
+    ///
+    /// Executes the program until the next output, then pauses it and
+    /// returns this output, or None if the program halted without
+    /// outputting anything else. To resume the program, call this same
+    /// function again.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "99".parse().unwrap();
+    /// assert_eq!(program.execute_until_next_output().unwrap(), None);
+    ///
+    /// let mut program: Program = "104,1,99".parse().unwrap();
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(1));
+    /// assert_eq!(program.execute_until_next_output().unwrap(), None);
+    /// assert!(!program.is_running());
+    ///
+    /// let mut program: Program = "104,1,98".parse().unwrap();
+    /// assert_eq!(program.execute_until_next_output().unwrap(), Some(1));
+    /// assert!(program.execute_until_next_output().is_err());
+    /// ```
+    pub fn execute_until_next_output(&mut self) -> Result<Option<Cell>> {
+        self.execute0(true)?;
+        Ok(self.emitted)
+    }
+
+    ///
+    /// Returns an iterator over the outputs of the program, executing it
+    /// until the next output at each iteration, and ending when it halts.
+    /// If the program fails, the error is yielded, and the iterator ends.
+    /// The program stays paused after the last output read, so it can be
+    /// resumed if the iteration stops early.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,104,3,99".parse().unwrap();
+    /// let outputs: Vec<_> = program.outputs().map(Result::unwrap).collect();
+    /// assert_eq!(outputs, vec![1, 2, 3]);
+    ///
+    /// let mut program: Program = "104,1,104,2,104,3,99".parse().unwrap();
+    /// let first: Vec<_> = program.outputs().take(2).map(Result::unwrap).collect();
+    /// assert_eq!(first, vec![1, 2]);
+    /// assert!(program.is_running());
+    /// assert_eq!(program.execute().unwrap(), vec![1, 2, 3]);
+    ///
+    /// let mut program: Program = "104,1,104,2,98,104,3,99".parse().unwrap();
+    /// let mut outputs = program.outputs();
+    /// assert_eq!(outputs.next().unwrap().unwrap(), 1);
+    /// assert_eq!(outputs.next().unwrap().unwrap(), 2);
+    /// assert_eq!(outputs.next().unwrap().unwrap_err().message(), "Unexpected opcode");
+    /// assert!(outputs.next().is_none());
+    /// ```
+    pub fn outputs(&mut self) -> impl Iterator<Item = Result<Cell>> + '_ {
+        let mut finished = false;
+
+        std::iter::from_fn(move || {
+            if finished {
+                return None;
+            }
+
+            match self.execute_until_next_output() {
+                Ok(Some(output)) => Some(Ok(output)),
+                Ok(None) => {
+                    finished = true;
+                    None
+                }
+                Err(e) => {
+                    finished = true;
+                    Some(Err(e))
+                }
+            }
+        })
+    }
+
+    ///
+    /// Executes the program until it halts, or until it asks for an input
+    /// while none is queued (see `push_input`). In the latter case, the
+    /// program is paused on the input instruction (and still running), and
+    /// resumes there when this is called again. The input source is only
+    /// used if it's ready (see `InputSource::is_ready`), e.g. a `Link`.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{ExecutionState, Program};
+    /// // Outputs the two inputs it receives.
+    /// let mut program: Program = "3,9,4,9,3,9,4,9,99,0".parse().unwrap();
+    ///
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::WaitingForInput);
+    /// assert!(program.is_running());
+    /// assert!(program.output().is_empty());
+    ///
+    /// program.push_input(5);
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::WaitingForInput);
+    /// assert_eq!(program.output(), vec![5]);
+    ///
+    /// program.push_input(7);
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::Halted);
+    /// assert!(!program.is_running());
+    /// assert_eq!(program.output(), vec![5, 7]);
+    /// ```
+    pub fn execute_until_input_needed(&mut self) -> Result<ExecutionState> {
+        self.start();
+
+        loop {
+            if self.waiting_for_input() {
+                self.status = ExecutionStatus::WaitingForInput;
+                break Ok(ExecutionState::WaitingForInput);
+            }
+
+            if !self.forward()? {
+                break Ok(match self.status {
+                    ExecutionStatus::Stopped(stopped) => ExecutionState::Stopped(stopped),
+                    _ => ExecutionState::Halted,
+                });
+            }
+        }
+    }
+
+    ///
+    /// Executes a single instruction, and describes it. Stepping until the
+    /// program halts is the same as calling `execute`, and both can be
+    /// mixed on a program. Breakpoints are ignored.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Program, Step};
+    /// let mut program: Program = "104,1,1001,7,3,7,104,2,99".parse().unwrap();
+    ///
+    /// assert_eq!(
+    ///     program.step().unwrap(),
+    ///     Step { address: 0, instruction: 104, parameters: vec![1], output: Some(1), halted: false }
+    /// );
+    /// assert_eq!(
+    ///     program.step().unwrap(),
+    ///     Step { address: 2, instruction: 1001, parameters: vec![7, 3, 7], output: None, halted: false }
+    /// );
+    /// assert_eq!(program.execute().unwrap(), vec![1, 5]);
+    ///
+    /// let mut program: Program = "104,1,1001,7,3,7,104,2,99".parse().unwrap();
+    /// while !program.step().unwrap().halted {}
+    /// assert_eq!(program.output(), vec![1, 5]);
+    /// assert!(!program.is_running());
+    /// ```
+    ///
+    /// Each jump condition and comparison, after one step:
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let step = |source_code: &str| {
+    ///     let mut program: Program = source_code.parse().unwrap();
+    ///     program.step().unwrap();
+    ///     (program.pointer(), program.get(0))
+    /// };
+    ///
+    /// assert_eq!(step("1105,1,7"), (7, 1105));
+    /// assert_eq!(step("1105,0,7"), (3, 1105));
+    /// assert_eq!(step("1106,0,7"), (7, 1106));
+    /// assert_eq!(step("1106,1,7"), (3, 1106));
+    ///
+    /// assert_eq!(step("1107,1,2,0"), (4, 1));
+    /// assert_eq!(step("1107,2,1,0"), (4, 0));
+    /// assert_eq!(step("1108,3,3,0"), (4, 1));
+    /// assert_eq!(step("1108,3,4,0"), (4, 0));
+    /// ```
+    pub fn step(&mut self) -> Result<Step> {
+        self.start();
+        self.resuming = true;
+
+        let address = self.pointer;
+        let instruction = self.current().ok_or(Error::RanOffEnd {
+            pointer: self.pointer,
+        })?;
+        let parameters_count = self
+            .parse_opcode(instruction)
+            .map_or(0, |(_, parameters_count)| parameters_count);
+        let parameters = (1..=parameters_count)
+            .filter_map(|i| self.offset(i))
+            .collect();
+
+        self.forward()?;
+        let halted = self.status == ExecutionStatus::Halted;
+
+        Ok(Step {
+            address,
+            instruction,
+            parameters,
+            output: self.emitted,
+            halted,
+        })
+    }
+
+    ///
+    /// Executes the program until something happens: an output, an input
+    /// needed while none is queued (see `push_input`), or the end of the
+    /// program. This is the preferred way to drive a program interacting
+    /// with its caller, as a coroutine.
+    ///
+    /// As with `execute_until_input_needed`, a program needing an input is
+    /// paused on the input instruction, and the input source is only used
+    /// if it's ready.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Event, Program};
+    /// // Outputs its input, doubled, until it receives 0.
+    /// let mut program: Program = "3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(program.run_until_event().unwrap(), Event::NeedsInput);
+    /// program.push_input(21);
+    /// assert_eq!(program.run_until_event().unwrap(), Event::Output(42));
+    /// assert_eq!(program.run_until_event().unwrap(), Event::NeedsInput);
+    /// program.push_input(0);
+    /// assert_eq!(program.run_until_event().unwrap(), Event::Halted);
+    /// ```
+    pub fn run_until_event(&mut self) -> Result<Event> {
+        self.start();
+
+        loop {
+            if self.waiting_for_input() {
+                self.status = ExecutionStatus::WaitingForInput;
+                break Ok(Event::NeedsInput);
+            }
+
+            if !self.forward()? {
+                break Ok(match self.status {
+                    ExecutionStatus::Stopped(stopped) => Event::Stopped(stopped),
+                    _ => Event::Halted,
+                });
+            }
+
+            if let Some(output) = self.emitted {
+                break Ok(Event::Output(output));
+            }
+        }
+    }
+
+    ///
+    /// Polls the program for its next output, as `run_until_event`, for
+    /// cooperative loops driving several programs: the program runs until
+    /// it outputs a value, needs an input, or halts. Unlike
+    /// `run_until_event`, a halted program is not started again: it keeps
+    /// telling it halted. Inputs are only read from the queue, and from
+    /// input sources ready to give them (see `InputSource::is_ready`).
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Event, Program};
+    /// // Outputs its input, doubled, until it receives 0.
+    /// let mut program: Program = "3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let mut inputs = vec![0, 5, 1];
+    /// let mut outputs = vec![];
+    /// loop {
+    ///     match program.try_next_output().unwrap() {
+    ///         Event::Output(value) => outputs.push(value),
+    ///         Event::NeedsInput => program.push_input(inputs.pop().unwrap()),
+    ///         Event::Halted | Event::Stopped(_) => break,
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(outputs, vec![2, 10]);
+    /// assert_eq!(program.try_next_output().unwrap(), Event::Halted);
+    /// ```
+    pub fn try_next_output(&mut self) -> Result<Event> {
+        if self.status == ExecutionStatus::Halted {
+            return Ok(Event::Halted);
+        }
+
+        self.run_until_event()
+    }
+
+    ///
+    /// Runs the program until it halts or stops, calling `on_output` with
+    /// each value outputted, and `on_input` each time an input is needed. All outputs
+    /// produced before an input request are delivered before `on_input` is
+    /// called, so inputs can be computed from them.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// // Outputs its input, doubled, until it receives 0.
+    /// let mut program: Program = "3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0"
+    ///     .parse()
+    ///     .unwrap();
+    ///
+    /// let last_output = std::cell::Cell::new(1);
+    /// let mut seen = vec![];
+    /// program
+    ///     .run_reactive(
+    ///         |output| last_output.set(output),
+    ///         || {
+    ///             seen.push(last_output.get());
+    ///             if last_output.get() < 100 { last_output.get() + 1 } else { 0 }
+    ///         },
+    ///     )
+    ///     .unwrap();
+    ///
+    /// assert_eq!(seen, vec![1, 4, 10, 22, 46, 94, 190]);
+    /// assert!(!program.is_running());
+    /// ```
+    pub fn run_reactive(
+        &mut self,
+        mut on_output: impl FnMut(Cell),
+        mut on_input: impl FnMut() -> Cell,
+    ) -> Result<()> {
+        loop {
+            match self.run_until_event()? {
+                Event::Output(value) => on_output(value),
+                Event::NeedsInput => self.push_input(on_input()),
+                Event::Halted | Event::Stopped(_) => break Ok(()),
+            }
+        }
+    }
+
+    /// Checks if the next instruction is an input, while no input is
+    /// available, and there is no default input.
+    fn waiting_for_input(&self) -> bool {
+        self.current().map(|opcode| opcode % 100 == 3) == Some(true)
+            && self.queued_input.is_empty()
+            && !self.input_source_ready()
+            && self.input_default.is_none()
+    }
+
+    fn execute0(&mut self, until_next_output: bool) -> Result<()> {
+        self.start();
+
+        loop {
+            if !self.forward()? {
+                break Ok(());
+            }
+
+            if self.emitted.is_some() && until_next_output {
+                break Ok(());
+            }
+        }
+    }
+
+    /// Returns the value at the current internal pointer position.
+    fn current(&self) -> Option<Cell> {
+        self.cell(self.pointer)
+    }
+
+    /// Returns the value `add` addresses after the current
+    /// internal pointer position.
+    fn offset(&self, add: usize) -> Option<Cell> {
+        self.cell(self.pointer + add)
+    }
+
+    /// Checks the target of a jump from the instruction at `origin`, and
+    /// returns it as an address.
+    fn jump_target(&self, origin: usize, target: Cell) -> Result<usize> {
+        match usize::try_from(target) {
+            Ok(address) if !self.strict_jumps || address < self.memory_len() => Ok(address),
+            _ => Err(Error::InvalidJump {
+                pointer: origin,
+                target,
+            }),
+        }
+    }
+
+    /// Computes the result of an operation from its operands, failing
+    /// if it overflows.
+    fn compute_operation(&self, operation: Operation, a: Cell, b: Cell) -> Result<Cell> {
+        match operation {
+            Operation::Add => a.checked_add(b),
+            Operation::Multiply => a.checked_mul(b),
+        }
+        .ok_or(Error::Overflow)
+    }
+
+    /// Checks if a jump condition is met by a value.
+    fn check_condition(&self, condition: JumpCondition, value: Cell) -> bool {
+        match condition {
+            JumpCondition::IfTrue => value != 0,
+            JumpCondition::IfFalse => value == 0,
+        }
+    }
+
+    /// Compares two values.
+    fn compare(&self, comparison: Comparison, a: Cell, b: Cell) -> bool {
+        match comparison {
+            Comparison::LessThan => a < b,
+            Comparison::Equals => a == b,
+        }
+    }
+
+    /// Processes one instruction in the program, as `forward0`, and updates
+    /// the status of the program. Returns false if the program halted, or
+    /// stopped (before the instruction on breakpoints, after it on
+    /// watchpoints and output breaks).
+    fn forward(&mut self) -> Result<bool> {
+        self.emitted = None;
+        let address = self.pointer;
+
+        if let Some(breakpoint) = self.breakpoint_reached() {
+            self.status = ExecutionStatus::Stopped(Stopped::Breakpoint(breakpoint));
+            return Ok(false);
+        }
+
+        self.watched_write = None;
+        let opcode = match self.watchpoints.is_empty() {
+            true => None,
+            false => self.current(),
+        };
+
+        if self.event.is_some() {
+            let instruction = self.current().unwrap_or(0);
+            let decoded = self.parse_opcode(instruction);
+            let parameters_count = decoded.map_or(0, |(_, count)| count);
+
+            if let Some(event) = &mut self.event {
+                event.address = address;
+                event.instruction = instruction;
+                event.opcode = decoded.map(|(opcode, _)| opcode);
+                event.parameters.clear();
+                event.values.clear();
+                event.write = None;
+            }
+            for i in 1..=parameters_count {
+                if let (Some(parameter), Some(event)) = (self.cell(address + i), &mut self.event) {
+                    event.parameters.push(parameter);
+                }
+            }
+        }
+
+        self.begin_undo();
+        let result = self.forward0().map_err(|e| self.in_instruction(address, e));
+        if result.is_ok() {
+            self.commit_undo();
+        }
+
+        // The instruction is observed before the pointer moves to the next
+        // one, so the coverage, the profiler, the observer and the history
+        // all see the same event.
+        if let (Ok(_), Some(event)) = (&result, &mut self.event) {
+            event.step = self.steps;
+            let event = &*event;
+
+            if let Some(opcode) = event.opcode {
+                if let Some(executed) = &mut self.coverage {
+                    executed.insert(event.address, event.parameters.len() + 1);
+                }
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.record(event.address, opcode);
+                }
+            }
+
+            if let Some(observer) = &mut self.observer {
+                observer(event);
+            }
+
+            if self.history_depth > 0 {
+                // The oldest event is reused, to avoid allocating.
+                let mut oldest = if self.history.len() >= self.history_depth {
+                    self.history.pop_front().unwrap_or_default()
+                } else {
+                    InstructionEvent::default()
+                };
+                oldest.clone_from(event);
+                self.history.push_back(oldest);
+            }
+        }
+
+        let result = result.map(|(running, next)| {
+            self.pointer = next;
+            running
+        });
+
+        if let (Some(output), Some(echo)) = (self.emitted, &mut self.output_echo) {
+            if let Err(e) = writeln!(echo, "[step {} @ {}] {}", self.steps, address, output) {
+                eprintln!("Unable to echo the outputs, echo stopped: {}", e);
+                self.output_echo = None;
+            }
+        }
+
+        self.status = match result {
+            Ok(true) => ExecutionStatus::Running,
+            Ok(false) => ExecutionStatus::Halted,
+            Err(_) => ExecutionStatus::Failed,
+        };
+
+        if let (Ok(true), Some((written, old, new))) = (&result, self.watched_write.take()) {
+            self.status = ExecutionStatus::Stopped(Stopped::Watchpoint {
+                address: written,
+                old,
+                new,
+                pointer: address,
+                opcode: opcode.unwrap_or(0),
+            });
+            return Ok(false);
+        }
+
+        if let (Ok(true), Some(value)) = (&result, self.emitted) {
+            if let Some(stopped) = self.output_break_reached(value) {
+                self.status = ExecutionStatus::Stopped(stopped);
+                return Ok(false);
+            }
+        }
+
+        result
+    }
+
+    /// Adds the context of the instruction at the given address to an
+    /// error, unless there is no instruction there.
+    fn in_instruction(&self, address: usize, error: Error) -> Error {
+        match self.cell(address) {
+            Some(opcode) => Error::Instruction {
+                pointer: address,
+                opcode,
+                parameters: self
+                    .parse_opcode(opcode)
+                    .map(|(_, count)| (1..=count).filter_map(|i| self.cell(address + i)).collect())
+                    .unwrap_or_default(),
+                history: self.history.iter().cloned().collect(),
+                memory_usage: self.memory_usage(),
+                error: Box::new(error),
+            },
+            None => error,
+        }
+    }
+
+    /// Processes one instruction in the program, without moving the
+    /// internal pointer. Returns whether the program is still running, and
+    /// the address of the next instruction.
+    fn forward0(&mut self) -> Result<(bool, usize)> {
+        if self
+            .max_steps
+            .is_some_and(|max_steps| self.steps >= max_steps)
+        {
+            return Err(Error::StepLimit { steps: self.steps });
+        }
+
+        if let Some((interval, _)) = self.loop_detection {
+            if self.steps.is_multiple_of(interval) {
+                let state = self.state_hash();
+
+                if let Some((_, states)) = &mut self.loop_detection {
+                    if !states.insert(state) {
+                        return Err(Error::LimitExceeded("Loop detected"));
+                    }
+                }
+            }
+        }
+
+        self.steps += 1;
+
+        let instruction = match self.decode_instruction() {
+            Ok(instruction) => instruction,
+            Err(Error::UnknownOpcode { .. }) if self.on_unknown_opcode == OnUnknownOpcode::Skip => {
+                return Ok((true, self.pointer + 1));
+            }
+            Err(Error::UnknownOpcode { .. }) if self.on_unknown_opcode == OnUnknownOpcode::Halt => {
+                return Ok((false, self.pointer));
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Jumps replace the address of the next instruction.
+        let mut next = instruction.address + instruction.parameters_count + 1;
+
+        let running = match instruction.opcode {
+            OpCode::Arithmetic(operation) => match self.get_parameter(&instruction, 0)? {
+                Some(operand1) => match self.get_parameter(&instruction, 1)? {
+                    Some(operand2) => match instruction.parameters().get(2) {
+                        Some(result_address) => {
+                            let result = self.compute_operation(operation, operand1, operand2)?;
+                            self.set(self.get_address(&instruction, result_address)?, result)?;
+                            Ok(true)
+                        }
+                        None => Err(Error::MissingParameter(
+                            "Invalid third parameter in operation (1|2)",
+                        )),
+                    },
+                    None => Err(Error::MissingParameter(
+                        "Invalid second parameter in operation (1|2)",
+                    )),
+                },
+                None => Err(Error::MissingParameter(
+                    "Invalid first parameter pointer in operation (1|2)",
+                )),
+            },
+            OpCode::Input => match instruction.parameters().first() {
+                Some(input_address) => {
+                    let address = self.get_address(&instruction, input_address)?;
+                    match self.request_input() {
+                        Ok(input) => {
+                            self.set(address, input)?;
+                            Ok(true)
+                        }
+                        Err(e) => {
+                            // The pointer stays on the input instruction, so
+                            // it's retried when the program is resumed.
+                            self.steps -= 1;
+                            Err(e)
+                        }
+                    }
+                }
+                None => Err(Error::MissingParameter(
+                    "Invalid first parameter pointer in input (3)",
+                )),
+            },
+            OpCode::Output => match self.get_parameter(&instruction, 0)? {
+                Some(output) => {
+                    self.push_output(output)?;
+                    Ok(true)
+                }
+                None => Err(Error::MissingParameter(
+                    "Invalid first parameter pointer in output (4)",
+                )),
+            },
+            OpCode::Jump(condition) => match self.get_parameter(&instruction, 0)? {
+                Some(test) if self.check_condition(condition, test) => {
+                    match self.get_parameter(&instruction, 1)? {
+                        Some(target) => {
+                            next = self.jump_target(instruction.address, target)?;
+                            Ok(true)
+                        }
+                        None => Err(Error::MissingParameter(
+                            "Invalid second parameter pointer in jump_if (5|6)",
+                        )),
+                    }
+                }
+                None => Err(Error::MissingParameter(
+                    "Invalid first parameter pointer in jump_if (5|6)",
+                )),
+                _ => Ok(true),
+            },
+            OpCode::Test(comparison) => match self.get_parameter(&instruction, 0)? {
+                Some(operand1) => match self.get_parameter(&instruction, 1)? {
+                    Some(operand2) => match instruction.parameters().get(2) {
+                        Some(test_result_address) => {
+                            self.set(
+                                self.get_address(&instruction, test_result_address)?,
+                                if self.compare(comparison, operand1, operand2) {
+                                    1
+                                } else {
+                                    0
+                                },
+                            )?;
+                            Ok(true)
+                        }
+                        None => Err(Error::MissingParameter(
+                            "Invalid third parameter pointer in test (7|8)",
+                        )),
+                    },
+                    None => Err(Error::MissingParameter(
+                        "Invalid second parameter pointer in test (7|8)",
+                    )),
+                },
+                None => Err(Error::MissingParameter(
+                    "Invalid first parameter pointer in test (7|8)",
+                )),
+            },
+            OpCode::AdjustRelativeBase => match self.get_parameter(&instruction, 0)? {
+                Some(relative_base) => match add_to_base(self.relative_base, relative_base) {
+                    Some(relative_base) => {
+                        self.relative_base = relative_base;
+                        Ok(true)
+                    }
+                    None => Err(Error::InvalidAddress("Relative base overflow")),
+                },
+                None => Err(Error::MissingParameter(
+                    "Invalid parameter in adjust_relative_base (9)",
+                )),
+            },
+            OpCode::Halt => Ok(false),
+            OpCode::Custom(code) => {
+                let handler = Rc::clone(&self.custom_opcodes[&code].1);
+                let mut context = opcodes::OpcodeContext::new(self, instruction);
+                handler(&mut context)?;
+                if let Some(target) = context.jumped() {
+                    next = target;
+                }
+                Ok(true)
+            }
+        }?;
+
+        Ok((running, next))
+    }
+
+    /// Gives an output to the output sink, or else adds it to the outputs,
+    /// unless the program accumulated too many.
+    fn push_output(&mut self, output: Cell) -> Result<()> {
+        match &mut self.output_sink {
+            Some(sink) => {
+                catch_callback_panic(|| sink.emit(output))?;
+                self.drained_output += 1;
+            }
+            None if self.output.len() >= self.max_outputs => {
+                return Err(Error::LimitExceeded("Output limit exceeded"))
+            }
+            None => {
+                // Only moves the outputs when the ring buffer wraps around,
+                // after outputs were popped.
+                self.output.push_back(output);
+                self.output.make_contiguous();
+            }
+        }
+
+        self.emitted = Some(output);
+        self.undo_output();
+        self.record(|transcript| transcript.record_output(output));
+        self.log_io("out", output);
+        Ok(())
+    }
+
+    /// Parses an OPCode and returns a tuple containing the opcode
+    /// and the number of parameters for this opcode. Custom opcodes
+    /// are looked up when the code is not a standard one.
+    fn parse_opcode(&self, opcode_code: Cell) -> Option<(OpCode, usize)> {
+        let custom = || {
+            let code = opcode_code % 100;
+            self.custom_opcodes
+                .get(&code)
+                .map(|(parameters_count, _)| (OpCode::Custom(code), *parameters_count))
+        };
+
+        decode_opcode(opcode_code).or_else(custom)
+    }
+
+    /// Returns the error for an unknown opcode at the given address,
+    /// with a few cells around it.
+    fn unknown_opcode(&self, address: usize) -> Error {
+        let memory = (address.saturating_sub(3)..=address + 3)
+            .filter_map(|address| self.cell(address).map(|value| (address, value)))
+            .collect();
+
+        Error::UnknownOpcode {
+            opcode: self.cell(address).unwrap_or(0),
+            memory,
+        }
+    }
+
+    /// Pre-supposing the internal instruction pointer is at the beginning
+    /// of a new instruction, parses it and returns it. The pointer is
+    /// moved once the instruction is executed (see `forward`).
+    fn decode_instruction(&mut self) -> Result<Instruction> {
+        let cached = self
+            .decode_cache
+            .as_ref()
+            .and_then(|cache| cache.get(self.pointer).cloned().flatten());
+
+        if let Some(instruction) = cached {
+            return Ok(instruction);
+        }
+
+        match self.current() {
+            Some(opcode_code) => match self.parse_opcode(opcode_code) {
+                Some((_, parameters_count)) if self.offset(parameters_count).is_none() => {
+                    Err(Error::TruncatedInstruction {
+                        pointer: self.pointer,
+                        opcode: opcode_code,
+                    })
+                }
+                Some((opcode, parameters_count)) => {
+                    let mut parameters = [Parameter {
+                        data: 0,
+                        mode: ParameterMode::Position,
+                    }; 3];
+
+                    // The modes are the digits of the instruction code
+                    // before the opcode, from right to left.
+                    let mut modes = opcode_code / 100;
+                    for (i, parameter) in parameters.iter_mut().take(parameters_count).enumerate() {
+                        *parameter = Parameter {
+                            data: self.offset(i + 1).unwrap(),
+                            mode: ParameterMode::from_digit(modes % 10).ok_or(
+                                Error::InvalidParameterMode {
+                                    pointer: self.pointer,
+                                    opcode: opcode_code,
+                                    parameter: i,
+                                },
+                            )?,
+                        };
+                        modes /= 10;
+                    }
+
+                    let instruction = Instruction {
+                        address: self.pointer,
+                        opcode,
+                        parameters,
+                        parameters_count,
+                    };
+
+                    // Only instructions within the memory vector are cached,
+                    // so a jump to a far address in sparse memory doesn't
+                    // grow the cache as far.
+                    if let Some(cache) = &mut self.decode_cache {
+                        if self.pointer < self.memory.len() {
+                            if cache.len() <= self.pointer {
+                                cache.resize(self.memory.len(), None);
+                            }
+                            cache[self.pointer] = Some(instruction);
+                        }
+                    }
+
+                    Ok(instruction)
+                }
+                None => Err(self.unknown_opcode(self.pointer)),
+            },
+            None => Err(Error::RanOffEnd {
+                pointer: self.pointer,
+            }),
+        }
+    }
+}
+
+/// Adds an offset to a relative base, unless it overflows.
+// Cells are wider than the relative base with the `wide-cells` feature.
+#[allow(clippy::useless_conversion)]
+fn add_to_base(base: i64, offset: Cell) -> Option<i64> {
+    i64::try_from(offset)
+        .ok()
+        .and_then(|offset| base.checked_add(offset))
+}
+
+/// Returns the opcode of an instruction code, with its number of
+/// parameters, if valid.
+fn decode_opcode(opcode_code: Cell) -> Option<(OpCode, usize)> {
+    match opcode_code % 100 {
+        1 => Some((OpCode::Arithmetic(Operation::Add), 3)),
+        2 => Some((OpCode::Arithmetic(Operation::Multiply), 3)),
+        3 => Some((OpCode::Input, 1)),
+        4 => Some((OpCode::Output, 1)),
+        5 => Some((OpCode::Jump(JumpCondition::IfTrue), 2)),
+        6 => Some((OpCode::Jump(JumpCondition::IfFalse), 2)),
+        7 => Some((OpCode::Test(Comparison::LessThan), 3)),
+        8 => Some((OpCode::Test(Comparison::Equals), 3)),
+        9 => Some((OpCode::AdjustRelativeBase, 1)),
+        99 => Some((OpCode::Halt, 0)),
+        _ => None,
+    }
+}
+
+/// An error while reading output tuples. See `tuples`.
+#[derive(Debug)]
+pub enum TupleError {
+    /// The program halted in the middle of a tuple; contains the
+    /// values of the incomplete tuple.
+    Incomplete(Vec<Cell>),
+
+    /// The program failed.
+    Program(Error),
+}
+
+/// Runs the program and yields its outputs grouped into tuples of `N`
+/// values, as they are produced. The iterator ends when the program halts
+/// on a tuple boundary; if it halts in the middle of a tuple, an error
+/// containing the partial tuple is yielded instead.
+///
+/// ```rust
+/// # use lib::intcode::{tuples, Cell, Program, TupleError};
+/// let source_code = "104,1,104,2,104,3,104,4,99";
+///
+/// let mut program: Program = source_code.parse().unwrap();
+/// let pairs: Vec<[Cell; 2]> = tuples(&mut program).map(Result::unwrap).collect();
+/// assert_eq!(pairs, vec![[1, 2], [3, 4]]);
+///
+/// let mut program: Program = source_code.parse().unwrap();
+/// let mut triples = tuples::<3>(&mut program);
+/// assert_eq!(triples.next().unwrap().unwrap(), [1, 2, 3]);
+/// match triples.next() {
+///     Some(Err(TupleError::Incomplete(partial))) => assert_eq!(partial, vec![4]),
+///     _ => panic!("Expected an incomplete tuple"),
+/// }
+/// assert!(triples.next().is_none());
+/// ```
+pub fn tuples<const N: usize>(
+    program: &mut Program,
+) -> impl Iterator<Item = std::result::Result<[Cell; N], TupleError>> + '_ {
+    let mut outputs = program.outputs();
+
+    std::iter::from_fn(move || {
+        let mut tuple = [0; N];
+
+        for (i, value) in tuple.iter_mut().enumerate() {
+            match outputs.next() {
+                Some(Ok(output)) => *value = output,
+                Some(Err(e)) => return Some(Err(TupleError::Program(e))),
+                None => {
+                    return match i {
+                        0 => None,
+                        _ => Some(Err(TupleError::Incomplete(tuple[..i].to_vec()))),
+                    }
+                }
+            }
+        }
+
+        Some(Ok(tuple))
+    })
+}
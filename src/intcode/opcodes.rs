@@ -0,0 +1,163 @@
+//! Custom opcodes, to experiment with extended Intcode dialects.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! // Outputs 17 modulo 5, with a custom opcode 21.
+//! let mut program: Program = "1121,17,5,7,4,7,99,0".parse().unwrap();
+//! program
+//!     .register_opcode(21, 3, |context| {
+//!         let (a, b) = (context.value(0)?, context.value(1)?);
+//!         context.write(2, a % b)
+//!     })
+//!     .unwrap();
+//!
+//! assert_eq!(program.execute().unwrap(), vec![2]);
+//! ```
+
+use super::{decode_opcode, Cell, Error, Instruction, Program, Result};
+use std::rc::Rc;
+
+/// The implementation of a custom opcode. See `Program::register_opcode`.
+pub(super) type OpcodeHandler = Rc<dyn Fn(&mut OpcodeContext<'_>) -> Result<()>>;
+
+/// The view of the program given to a custom opcode, with the decoded
+/// parameters of the instruction being executed.
+pub struct OpcodeContext<'a> {
+    program: &'a mut Program,
+    instruction: Instruction,
+
+    /// Where the handler jumped, if it did.
+    jump: Option<usize>,
+}
+
+impl<'a> OpcodeContext<'a> {
+    pub(super) fn new(program: &'a mut Program, instruction: Instruction) -> Self {
+        OpcodeContext {
+            program,
+            instruction,
+            jump: None,
+        }
+    }
+
+    /// Returns the address the handler jumped to, if it did.
+    pub(super) fn jumped(&self) -> Option<usize> {
+        self.jump
+    }
+
+    /// Returns the number of parameters of the instruction.
+    pub fn parameters_count(&self) -> usize {
+        self.instruction.parameters_count
+    }
+
+    /// Returns the value of a parameter (starting at zero), according to
+    /// its mode.
+    pub fn value(&mut self, parameter: usize) -> Result<Cell> {
+        self.program
+            .get_parameter(&self.instruction, parameter)?
+            .ok_or(Error::MissingParameter(
+                "Invalid parameter in custom opcode",
+            ))
+    }
+
+    /// Returns the address a parameter points to. Parameters in immediate
+    /// mode are not addresses.
+    pub fn address(&self, parameter: usize) -> Result<usize> {
+        match self.instruction.parameters().get(parameter) {
+            Some(parameter) => self.program.get_address(&self.instruction, parameter),
+            None => Err(Error::MissingParameter(
+                "Invalid parameter in custom opcode",
+            )),
+        }
+    }
+
+    /// Writes a value at the address a parameter points to.
+    pub fn write(&mut self, parameter: usize, value: Cell) -> Result<()> {
+        let address = self.address(parameter)?;
+        self.program.set(address, value)
+    }
+
+    /// Reads the memory at the given address.
+    pub fn read_memory(&self, address: usize) -> Cell {
+        self.program.get(address)
+    }
+
+    /// Writes the memory at the given address.
+    pub fn write_memory(&mut self, address: usize, value: Cell) -> Result<()> {
+        self.program.set(address, value)
+    }
+
+    /// Outputs a value, as the Output opcode.
+    pub fn output(&mut self, value: Cell) -> Result<()> {
+        self.program.push_output(value)
+    }
+
+    /// Moves the pointer to the given address, as jumps do. Else, the
+    /// program continues after the instruction.
+    pub fn jump(&mut self, address: usize) {
+        self.jump = Some(address);
+    }
+
+    /// Returns the current relative base.
+    pub fn relative_base(&self) -> i64 {
+        self.program.relative_base
+    }
+}
+
+impl Program {
+    ///
+    /// Registers a custom opcode, called for instructions ending with the
+    /// given code (below 100), with the given number of parameters (at
+    /// most three). The handler reads and writes through its parameters
+    /// with an `OpcodeContext`. Registered opcodes are kept by resets and
+    /// forks.
+    ///
+    /// The opcodes of the standard Intcode can't be overridden.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,99".parse().unwrap();
+    /// assert!(program.register_opcode(4, 1, |_| Ok(())).is_err());
+    /// assert!(program.register_opcode(42, 4, |_| Ok(())).is_err());
+    ///
+    /// // Opcode 42 outputs its parameter twice, then skips the next cell.
+    /// let mut program: Program = "142,7,0,104,1,99".parse().unwrap();
+    /// program
+    ///     .register_opcode(42, 1, |context| {
+    ///         let value = context.value(0)?;
+    ///         context.output(value)?;
+    ///         context.output(value)?;
+    ///         context.jump(3);
+    ///         Ok(())
+    ///     })
+    ///     .unwrap();
+    ///
+    /// assert_eq!(program.execute().unwrap(), vec![7, 7, 1]);
+    /// ```
+    pub fn register_opcode(
+        &mut self,
+        code: Cell,
+        parameters_count: usize,
+        handler: impl Fn(&mut OpcodeContext<'_>) -> Result<()> + 'static,
+    ) -> Result<()> {
+        if !(0..100).contains(&code) || decode_opcode(code).is_some() {
+            return Err(Error::CustomOpcode(
+                "Opcode unavailable for a custom opcode",
+            ));
+        }
+
+        if parameters_count > 3 {
+            return Err(Error::CustomOpcode(
+                "Too many parameters for a custom opcode",
+            ));
+        }
+
+        self.custom_opcodes
+            .insert(code, (parameters_count, Rc::new(handler)));
+
+        if let Some(cache) = &mut self.decode_cache {
+            cache.clear();
+        }
+
+        Ok(())
+    }
+}
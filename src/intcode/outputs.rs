@@ -0,0 +1,124 @@
+//! Output sinks, receiving the outputs of a program as they happen, given
+//! to `Program::set_output_sink`. Without a sink, the outputs are collected
+//! by the program, and read with `Program::output`.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! // Counts forever.
+//! let mut program: Program = "104,0,1001,1,1,1,1105,1,0".parse().unwrap();
+//! let mut outputs = 0;
+//! program.set_output_sink(move |_| {
+//!     outputs += 1;
+//!     if outputs < 3 {
+//!         Ok(())
+//!     } else {
+//!         Err(lib::intcode::Error::Other("Enough outputs".to_string()))
+//!     }
+//! });
+//!
+//! assert_eq!(program.execute().unwrap_err().message(), "Enough outputs");
+//! assert_eq!(program.outputs_produced(), 2);
+//! assert!(program.output().is_empty());
+//! ```
+
+use super::{Cell, Error, Result};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::Sender;
+
+/// A receiver of the outputs of a program.
+pub trait OutputSink {
+    /// Receives an output. An error stops the program, with this error.
+    fn emit(&mut self, value: Cell) -> Result<()>;
+}
+
+/// Closures receiving the outputs are output sinks too.
+impl<F: FnMut(Cell) -> Result<()>> OutputSink for F {
+    fn emit(&mut self, value: Cell) -> Result<()> {
+        self(value)
+    }
+}
+
+/// Collects the outputs in a vector shared with its clones, to read them
+/// while the program owns the sink.
+///
+/// ```rust
+/// # use lib::intcode::outputs::VecOutput;
+/// # use lib::intcode::Program;
+/// let mut program: Program = "104,1,104,2,99".parse().unwrap();
+/// let outputs = VecOutput::default();
+/// program.set_output_sink(outputs.clone());
+///
+/// program.execute_until_next_output().unwrap();
+/// assert_eq!(outputs.values(), vec![1]);
+///
+/// program.execute().unwrap();
+/// assert_eq!(outputs.values(), vec![1, 2]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct VecOutput(pub Rc<RefCell<Vec<Cell>>>);
+
+impl VecOutput {
+    /// Returns the outputs collected so far.
+    pub fn values(&self) -> Vec<Cell> {
+        self.0.borrow().clone()
+    }
+}
+
+impl OutputSink for VecOutput {
+    fn emit(&mut self, value: Cell) -> Result<()> {
+        self.0.borrow_mut().push(value);
+        Ok(())
+    }
+}
+
+/// What to do with outputs sent to a channel whose receiver is gone.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ClosedReceiver {
+    /// The output is lost, and the program continues.
+    Ignore,
+
+    /// The program fails.
+    Fail,
+}
+
+/// Sends the outputs to a channel, e.g. to connect programs running in
+/// different threads.
+///
+/// ```rust
+/// # use lib::intcode::outputs::{ChannelOutput, ClosedReceiver};
+/// # use lib::intcode::Program;
+/// # use std::sync::mpsc::channel;
+/// let (tx, rx) = channel();
+///
+/// let mut program: Program = "104,1,104,2,99".parse().unwrap();
+/// program.set_output_sink(ChannelOutput::new(tx.clone(), ClosedReceiver::Fail));
+/// program.execute().unwrap();
+/// assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![1, 2]);
+///
+/// drop(rx);
+/// assert_eq!(program.execute().unwrap_err().message(), "Cannot send output");
+///
+/// program.set_output_sink(ChannelOutput::new(tx, ClosedReceiver::Ignore));
+/// assert!(program.execute().is_ok());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelOutput {
+    sender: Sender<Cell>,
+    on_closed: ClosedReceiver,
+}
+
+impl ChannelOutput {
+    pub fn new(sender: Sender<Cell>, on_closed: ClosedReceiver) -> Self {
+        ChannelOutput { sender, on_closed }
+    }
+}
+
+impl OutputSink for ChannelOutput {
+    fn emit(&mut self, value: Cell) -> Result<()> {
+        match (self.sender.send(value), self.on_closed) {
+            (Err(_), ClosedReceiver::Fail) => Err(Error::InvalidOutput("Cannot send output")),
+            _ => Ok(()),
+        }
+    }
+}
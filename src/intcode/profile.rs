@@ -0,0 +1,491 @@
+//! A profiler, counting the instructions executed by address and by
+//! opcode, to find where a program spends its time.
+//!
+//! ```rust
+//! # use lib::intcode::asm::assemble;
+//! # use lib::intcode::Program;
+//! // Outputs 1, 2 and 3.
+//! let memory = assemble(
+//!     "loop:  out [n]
+//!             add [n], #1, [n]
+//!             lt [n], #4, [c]
+//!             jnz [c], loop
+//!             hlt
+//!      n:     data 1
+//!      c:     data 0",
+//! )
+//! .unwrap();
+//!
+//! let mut program = Program::from_memory(memory);
+//! program.enable_profiling();
+//! program.execute().unwrap();
+//!
+//! let profile = program.profile();
+//! assert_eq!(profile.instructions(), 13);
+//! assert_eq!(profile.count(6), 3);
+//! assert_eq!(profile.count(13), 1);
+//! assert_eq!(profile.count(14), 0);
+//! let opcodes: Vec<(&str, u64)> = profile
+//!     .opcode_totals()
+//!     .iter()
+//!     .map(|(name, count)| (name.as_str(), *count))
+//!     .collect();
+//! assert_eq!(
+//!     opcodes,
+//!     vec![("add", 3), ("jnz", 3), ("lt", 3), ("out", 3), ("hlt", 1)]
+//! );
+//!
+//! let hottest: Vec<(usize, u64)> = profile
+//!     .hottest(2)
+//!     .iter()
+//!     .map(|spot| (spot.address, spot.count))
+//!     .collect();
+//! assert_eq!(hottest, vec![(0, 3), (2, 3)]);
+//!
+//! // The first line gives the duration, which varies.
+//! let table = format!("{:.3}", profile);
+//! let lines: Vec<&str> = table.lines().skip(1).collect();
+//! assert_eq!(
+//!     lines,
+//!     vec![
+//!         "",
+//!         "Hottest addresses:",
+//!         "  address       count       %  instruction",
+//!         "        0           3   23.1%  out [14]",
+//!         "        2           3   23.1%  add [14], #1, [14]",
+//!         "        6           3   23.1%  lt [14], #4, [15]",
+//!         "",
+//!         "Opcodes:",
+//!         "  add              3   23.1%",
+//!         "  jnz              3   23.1%",
+//!         "  lt               3   23.1%",
+//!         "  out              3   23.1%",
+//!         "  hlt              1    7.7%",
+//!     ]
+//! );
+//!
+//! // Writes `hlt` a billion cells away, in sparse memory, and jumps to it.
+//! let program: Program = "1101,0,99,1000000000,1105,1,1000000000".parse().unwrap();
+//! let mut program = program.with_sparse_memory();
+//! program.enable_profiling();
+//! program.execute().unwrap();
+//! assert_eq!(program.profile().count(1_000_000_000), 1);
+//! ```
+
+use super::analysis::BasicBlock;
+use super::disasm::mnemonic;
+use super::{OpCode, Operation, Program};
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// The number of addresses listed when displaying a profile, unless a
+/// precision is given (e.g. `{:.5}`).
+const DEFAULT_TOP: usize = 10;
+
+/// The counters of the profiler. See `Program::enable_profiling`.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Profiler {
+    /// The number of instructions executed, by address, within the memory
+    /// when enabled; and beyond, e.g. in sparse memory.
+    counts: Vec<u64>,
+    far_counts: BTreeMap<usize, u64>,
+
+    /// The number of instructions executed, by opcode, in the order they
+    /// were first executed. Programs only use a few opcodes.
+    opcodes: Vec<(OpCode, u64)>,
+
+    /// When the first and the last instructions were executed.
+    started: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl Profiler {
+    /// Counts an instruction executed, from its event.
+    pub(super) fn record(&mut self, address: usize, opcode: OpCode) {
+        match self.counts.get_mut(address) {
+            Some(count) => *count += 1,
+            None => *self.far_counts.entry(address).or_insert(0) += 1,
+        }
+
+        match self.opcodes.iter_mut().find(|(known, _)| *known == opcode) {
+            Some((_, count)) => *count += 1,
+            None => self.opcodes.push((opcode, 1)),
+        }
+
+        let now = Instant::now();
+        self.started.get_or_insert(now);
+        self.last = Some(now);
+    }
+
+    /// Forgets the instructions counted, e.g. when the program restarts.
+    pub(super) fn clear(&mut self) {
+        self.counts.iter_mut().for_each(|count| *count = 0);
+        self.far_counts.clear();
+        self.opcodes.clear();
+        self.started = None;
+        self.last = None;
+    }
+}
+
+/// The name of an opcode in the report; custom ones have their code.
+fn opcode_name(opcode: OpCode) -> String {
+    match opcode {
+        OpCode::Custom(code) => format!("custom {}", code),
+        opcode => mnemonic(opcode).to_string(),
+    }
+}
+
+/// An instruction address, and how many times it was executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotSpot {
+    pub address: usize,
+    pub count: u64,
+
+    /// The instruction at the address, as disassembled when the report
+    /// was made (self-modifying programs may have executed others).
+    pub instruction: String,
+
+    /// The opcode of that instruction, if it's a valid one.
+    pub opcode: Option<OpCode>,
+}
+
+/// The weights of the opcodes, to compare programs by something closer to
+/// their running time than their number of instructions. See
+/// `ProfileReport::total_cost`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostModel {
+    /// The weight of the opcodes not listed.
+    default: u64,
+    weights: Vec<(OpCode, u64)>,
+}
+
+impl CostModel {
+    /// A model where every opcode weighs 1, so the cost of a program is
+    /// its number of instructions.
+    pub fn uniform() -> Self {
+        Self {
+            default: 1,
+            weights: vec![],
+        }
+    }
+
+    /// A model where multiplications weigh 3 and inputs 5, as they would
+    /// cost more on a real machine; other opcodes weigh 1.
+    pub fn weighted() -> Self {
+        Self::uniform()
+            .with_weight(OpCode::Arithmetic(Operation::Multiply), 3)
+            .with_weight(OpCode::Input, 5)
+    }
+
+    /// Sets the weight of an opcode.
+    pub fn with_weight(mut self, opcode: OpCode, weight: u64) -> Self {
+        match self.weights.iter_mut().find(|(known, _)| *known == opcode) {
+            Some((_, known)) => *known = weight,
+            None => self.weights.push((opcode, weight)),
+        }
+        self
+    }
+
+    /// Returns the weight of an opcode.
+    pub fn weight(&self, opcode: OpCode) -> u64 {
+        self.weights
+            .iter()
+            .find(|(known, _)| *known == opcode)
+            .map_or(self.default, |(_, weight)| *weight)
+    }
+}
+
+impl Default for CostModel {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+/// The cost of the instructions executed in a basic block. See
+/// `ProfileReport::block_costs`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockCost {
+    /// The addresses of the block.
+    pub addresses: Range<usize>,
+
+    /// The number of times the block was entered, i.e. its first
+    /// instruction was executed.
+    pub executions: u64,
+    pub cost: u64,
+}
+
+/// Where a program spent its time. See `Program::profile`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileReport {
+    /// The addresses executed, the most executed first.
+    spots: Vec<HotSpot>,
+
+    /// The number of instructions by opcode, the most executed first; and
+    /// as counted, for the costs.
+    opcodes: Vec<(String, u64)>,
+    opcode_counts: Vec<(OpCode, u64)>,
+
+    instructions: u64,
+    duration: Duration,
+}
+
+impl ProfileReport {
+    /// Returns the `n` most executed addresses, the most executed first,
+    /// then by address.
+    pub fn hottest(&self, n: usize) -> &[HotSpot] {
+        &self.spots[..n.min(self.spots.len())]
+    }
+
+    /// Returns the number of times the instruction at the address was
+    /// executed.
+    pub fn count(&self, address: usize) -> u64 {
+        self.spots
+            .iter()
+            .find(|spot| spot.address == address)
+            .map_or(0, |spot| spot.count)
+    }
+
+    /// Returns the number of instructions executed by opcode mnemonic, the
+    /// most executed first, then by name.
+    pub fn opcode_totals(&self) -> &[(String, u64)] {
+        &self.opcodes
+    }
+
+    /// Returns the total number of instructions executed.
+    pub fn instructions(&self) -> u64 {
+        self.instructions
+    }
+
+    ///
+    /// Returns the cost of the instructions executed: the sum of the
+    /// weights of their opcodes in the model.
+    ///
+    /// ```rust
+    /// # use lib::intcode::asm::assemble;
+    /// # use lib::intcode::profile::CostModel;
+    /// # use lib::intcode::Program;
+    /// // Outputs the square of its input.
+    /// let memory = assemble("in [n]\nmul [n], [n], [n]\nout [n]\nhlt\nn: data 0").unwrap();
+    /// let mut program = Program::from_memory(memory);
+    /// program.enable_profiling();
+    /// program.push_input(7);
+    /// assert_eq!(program.execute().unwrap(), vec![49]);
+    ///
+    /// let profile = program.profile();
+    /// assert_eq!(profile.total_cost(&CostModel::uniform()), 4);
+    /// assert_eq!(profile.total_cost(&CostModel::weighted()), 1 + 3 + 5 + 1);
+    /// ```
+    pub fn total_cost(&self, model: &CostModel) -> u64 {
+        self.opcode_counts
+            .iter()
+            .map(|(opcode, count)| count * model.weight(*opcode))
+            .sum()
+    }
+
+    ///
+    /// Returns the cost of the instructions executed in each basic block
+    /// (see `analysis::control_flow_graph`), for the blocks executed, by
+    /// address. The instructions are weighed as disassembled when the
+    /// report was made; instructions outside of the blocks, e.g. reached
+    /// by computed jumps only, are not counted.
+    ///
+    /// ```rust
+    /// # use lib::intcode::analysis::control_flow_graph;
+    /// # use lib::intcode::asm::assemble;
+    /// # use lib::intcode::profile::{BlockCost, CostModel};
+    /// # use lib::intcode::Program;
+    /// // Two loops: the first doubles its input three times, the second
+    /// // counts down from the result.
+    /// let memory = assemble(
+    ///     "       in [n]
+    ///      twice: mul [n], #2, [n]
+    ///             add [i], #1, [i]
+    ///             lt [i], #3, [c]
+    ///             jnz [c], twice
+    ///      down:  add [n], #-1, [n]
+    ///             jnz [n], down
+    ///             hlt
+    ///      n:     data 0
+    ///      i:     data 0
+    ///      c:     data 0",
+    /// )
+    /// .unwrap();
+    /// let blocks = control_flow_graph(&memory).blocks;
+    ///
+    /// let mut program = Program::from_memory(memory);
+    /// program.enable_profiling();
+    /// program.push_input(2);
+    /// program.execute().unwrap();
+    ///
+    /// let profile = program.profile();
+    /// let block = |addresses, executions, cost| BlockCost { addresses, executions, cost };
+    /// assert_eq!(
+    ///     profile.block_costs(&CostModel::weighted(), &blocks),
+    ///     vec![
+    ///         block(0..2, 1, 5),
+    ///         block(2..17, 3, 3 * (3 + 1 + 1 + 1)),
+    ///         block(17..24, 16, 16 * 2),
+    ///         block(24..25, 1, 1),
+    ///     ]
+    /// );
+    /// assert_eq!(profile.total_cost(&CostModel::weighted()), 5 + 18 + 32 + 1);
+    /// ```
+    pub fn block_costs(&self, model: &CostModel, blocks: &[BasicBlock]) -> Vec<BlockCost> {
+        blocks
+            .iter()
+            .map(|block| {
+                let spots = self
+                    .spots
+                    .iter()
+                    .filter(|spot| block.addresses.contains(&spot.address));
+
+                BlockCost {
+                    addresses: block.addresses.clone(),
+                    executions: self.count(block.addresses.start),
+                    cost: spots
+                        .map(|spot| spot.count * spot.opcode.map_or(0, |op| model.weight(op)))
+                        .sum(),
+                }
+            })
+            .filter(|block| block.cost > 0)
+            .collect()
+    }
+
+    /// Returns the wall-clock time between the first instruction executed
+    /// and the last one, including the time spent waiting for inputs.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the number of instructions executed per second; zero if
+    /// nothing was timed.
+    pub fn instructions_per_second(&self) -> f64 {
+        match self.duration.as_secs_f64() {
+            seconds if seconds > 0.0 => self.instructions as f64 / seconds,
+            _ => 0.0,
+        }
+    }
+
+    /// Returns the share of the instructions executed, as a percentage.
+    fn percentage(&self, count: u64) -> f64 {
+        match self.instructions {
+            0 => 0.0,
+            total => count as f64 * 100.0 / total as f64,
+        }
+    }
+}
+
+/// Prints the totals, the most executed addresses, and the opcodes. The
+/// precision sets the number of addresses listed (10 by default).
+impl Display for ProfileReport {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} instructions in {:?} ({:.0} instructions/s)",
+            self.instructions,
+            self.duration,
+            self.instructions_per_second()
+        )?;
+
+        writeln!(f, "\nHottest addresses:")?;
+        writeln!(f, "  address       count       %  instruction")?;
+        for spot in self.hottest(f.precision().unwrap_or(DEFAULT_TOP)) {
+            writeln!(
+                f,
+                "  {:>7}  {:>10}  {:>5.1}%  {}",
+                spot.address,
+                spot.count,
+                self.percentage(spot.count),
+                spot.instruction
+            )?;
+        }
+
+        writeln!(f, "\nOpcodes:")?;
+        for (name, count) in &self.opcodes {
+            writeln!(
+                f,
+                "  {:<6}  {:>10}  {:>5.1}%",
+                name,
+                count,
+                self.percentage(*count)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Program {
+    /// Counts the instructions executed, by address and by opcode, and
+    /// times them, for `profile`. The counts start over when the program
+    /// restarts.
+    pub fn enable_profiling(&mut self) {
+        self.profiler = Some(Profiler {
+            counts: vec![0; self.memory_len()],
+            ..Profiler::default()
+        });
+        self.update_event();
+    }
+
+    /// Returns the profile of the instructions executed. Empty if the
+    /// profiling is not enabled.
+    pub fn profile(&self) -> ProfileReport {
+        let profiler = match &self.profiler {
+            Some(profiler) => profiler,
+            None => {
+                return ProfileReport {
+                    spots: vec![],
+                    opcodes: vec![],
+                    opcode_counts: vec![],
+                    instructions: 0,
+                    duration: Duration::default(),
+                }
+            }
+        };
+
+        let mut spots: Vec<HotSpot> = profiler
+            .counts
+            .iter()
+            .enumerate()
+            .chain(
+                profiler
+                    .far_counts
+                    .iter()
+                    .map(|(address, count)| (*address, count)),
+            )
+            .filter(|(_, count)| **count > 0)
+            .map(|(address, count)| HotSpot {
+                address,
+                count: *count,
+                instruction: self.listing_at(address).0,
+                opcode: self
+                    .cell(address)
+                    .and_then(|code| self.parse_opcode(code))
+                    .map(|(opcode, _)| opcode),
+            })
+            .collect();
+        spots.sort_by(|a, b| b.count.cmp(&a.count).then(a.address.cmp(&b.address)));
+
+        let mut opcodes: Vec<(String, u64)> = profiler
+            .opcodes
+            .iter()
+            .map(|(opcode, count)| (opcode_name(*opcode), *count))
+            .collect();
+        opcodes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        let duration = match (profiler.started, profiler.last) {
+            (Some(started), Some(last)) => last - started,
+            _ => Duration::default(),
+        };
+
+        ProfileReport {
+            instructions: opcodes.iter().map(|(_, count)| count).sum(),
+            spots,
+            opcodes,
+            opcode_counts: profiler.opcodes.clone(),
+            duration,
+        }
+    }
+}
@@ -0,0 +1,206 @@
+//! Time-travel debugging: the changes made by the last instructions are
+//! recorded, so they can be undone one at a time, e.g. to find what
+//! corrupted the memory once it's noticed.
+//!
+//! For each instruction, the pointer and the relative base before it, the
+//! cells it wrote with their previous values, and its input or output are
+//! recorded. Stepping back restores them; the inputs read are queued again,
+//! so executing the program from there replays the same execution. Outputs
+//! given to an output sink can't be taken back, and are given again.
+//! Counters and caches (heatmap, coverage, history, loop detection) are not
+//! rewound.
+
+use super::{Cell, ExecutionStatus, Program};
+use std::collections::VecDeque;
+
+/// What an instruction changed, to undo it.
+#[derive(Debug, Clone, Default)]
+struct Undo {
+    pointer: usize,
+    relative_base: i64,
+
+    /// The cells written, with their values before, in order.
+    writes: Vec<(usize, Cell)>,
+
+    /// The input read, if any.
+    input: Option<Cell>,
+
+    /// True if the instruction outputted a value.
+    output: bool,
+}
+
+/// The instructions recorded, to step back. See `Program::enable_recording`.
+#[derive(Debug, Clone)]
+pub(super) struct Recording {
+    /// The number of instructions kept.
+    depth: usize,
+
+    /// The last instructions executed, oldest first.
+    steps: VecDeque<Undo>,
+
+    /// The instruction being executed.
+    current: Undo,
+}
+
+impl Recording {
+    /// Forgets the instructions recorded, e.g. when the program restarts.
+    pub(super) fn clear(&mut self) {
+        self.steps.clear();
+    }
+}
+
+impl Program {
+    /// Records the last instructions executed, up to the given number, so
+    /// they can be undone with `step_back`. Zero stops recording.
+    pub fn enable_recording(&mut self, max_steps: usize) {
+        self.recording = match max_steps {
+            0 => None,
+            depth => Some(Recording {
+                depth,
+                steps: VecDeque::with_capacity(depth),
+                current: Undo::default(),
+            }),
+        };
+    }
+
+    /// Returns the number of instructions which can be undone.
+    pub fn recorded_steps(&self) -> usize {
+        self.recording
+            .as_ref()
+            .map_or(0, |recording| recording.steps.len())
+    }
+
+    ///
+    /// Undoes the last instruction executed, if recorded (see
+    /// `enable_recording`). Returns false if there is nothing to undo.
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::DAY9_QUINE;
+    /// # use lib::intcode::{Cell, Program};
+    /// // Outputs its input, doubled, until it receives 0.
+    /// let doubler = "3,15,1006,15,14,1002,15,2,16,4,16,1105,1,0,99,0,0";
+    /// let load = |source: &str| {
+    ///     let mut program: Program = source.parse().unwrap();
+    ///     program.set_input(|n| Ok(n as Cell + 1));
+    ///     program
+    /// };
+    ///
+    /// for source in &[doubler, DAY9_QUINE.source_code] {
+    ///     let mut uninterrupted = load(source);
+    ///     for _ in 0..20 {
+    ///         uninterrupted.step().unwrap();
+    ///     }
+    ///
+    ///     let mut program = load(source);
+    ///     program.enable_recording(10);
+    ///     for _ in 0..20 {
+    ///         program.step().unwrap();
+    ///     }
+    ///     assert_eq!(program.recorded_steps(), 10);
+    ///
+    ///     for _ in 0..5 {
+    ///         assert!(program.step_back());
+    ///     }
+    ///     assert_eq!(program.steps_executed(), 15);
+    ///     assert_ne!(program.state_key(), uninterrupted.state_key());
+    ///
+    ///     for _ in 0..5 {
+    ///         program.step().unwrap();
+    ///     }
+    ///     assert_eq!(program.state_key(), uninterrupted.state_key());
+    ///     assert_eq!(program.output(), uninterrupted.output());
+    ///     assert_eq!(program.inputs_consumed(), uninterrupted.inputs_consumed());
+    /// }
+    ///
+    /// // Up to the recording depth.
+    /// let mut program = load(doubler);
+    /// program.enable_recording(2);
+    /// program.execute_until_next_output().unwrap();
+    /// assert!(program.step_back() && program.step_back());
+    /// assert!(!program.step_back());
+    /// assert_eq!((program.pointer(), program.steps_executed()), (5, 2));
+    /// assert!(program.output().is_empty());
+    /// ```
+    pub fn step_back(&mut self) -> bool {
+        let undo = match self
+            .recording
+            .as_mut()
+            .and_then(|recording| recording.steps.pop_back())
+        {
+            Some(undo) => undo,
+            None => return false,
+        };
+
+        for (address, value) in undo.writes.iter().rev() {
+            self.store(*address, *value);
+        }
+
+        self.pointer = undo.pointer;
+        self.relative_base = undo.relative_base;
+
+        if let Some(value) = undo.input {
+            self.queued_input.push_front(value);
+            self.input_count -= 1;
+        }
+        if undo.output && self.output.pop_back().is_none() {
+            self.drained_output = self.drained_output.saturating_sub(1);
+        }
+
+        self.steps -= 1;
+        self.emitted = None;
+        self.status = ExecutionStatus::Running;
+        true
+    }
+
+    /// Starts recording the changes of the instruction about to be
+    /// executed, if recording.
+    pub(super) fn begin_undo(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            let current = &mut recording.current;
+            current.pointer = self.pointer;
+            current.relative_base = self.relative_base;
+            current.writes.clear();
+            current.input = None;
+            current.output = false;
+        }
+    }
+
+    /// Records a write of the instruction being executed, before it's done.
+    pub(super) fn undo_write(&mut self, address: usize) {
+        if self.recording.is_some() {
+            let value = self.cell(address).unwrap_or(0);
+            if let Some(recording) = &mut self.recording {
+                recording.current.writes.push((address, value));
+            }
+        }
+    }
+
+    /// Records an input read by the instruction being executed.
+    pub(super) fn undo_input(&mut self, value: Cell) {
+        if let Some(recording) = &mut self.recording {
+            recording.current.input = Some(value);
+        }
+    }
+
+    /// Records an output of the instruction being executed.
+    pub(super) fn undo_output(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            recording.current.output = true;
+        }
+    }
+
+    /// Keeps the changes of the instruction executed, forgetting the oldest
+    /// ones if needed.
+    pub(super) fn commit_undo(&mut self) {
+        if let Some(recording) = &mut self.recording {
+            // The oldest record is reused, to avoid allocating.
+            let mut oldest = if recording.steps.len() >= recording.depth {
+                recording.steps.pop_front().unwrap_or_default()
+            } else {
+                Undo::default()
+            };
+            oldest.clone_from(&recording.current);
+            recording.steps.push_back(oldest);
+        }
+    }
+}
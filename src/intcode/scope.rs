@@ -0,0 +1,101 @@
+//! Input sources borrowing local state, for the duration of a scope.
+//!
+//! The input source of a program is kept by the program, so it can't borrow
+//! anything: a source reading a local buffer must share it through an `Rc`
+//! and a `RefCell`. `Program::with_input_scope` lifts this restriction, at
+//! the cost of flexibility: the borrowed source is never stored in the
+//! program, it is called by the executions of the scope (see `InputScope`)
+//! each time the program pauses on an input instruction. Executions
+//! started directly on the program, e.g. through `InputScope::program`,
+//! don't use it.
+
+use super::{Cell, Event, Program, Result};
+
+/// A program with an input source borrowed for the duration of a scope.
+/// See `Program::with_input_scope`.
+pub struct InputScope<'p, 's> {
+    program: &'p mut Program,
+    source: &'s mut dyn FnMut(usize) -> Result<Cell>,
+}
+
+impl InputScope<'_, '_> {
+    /// Gives the program the next input of the borrowed source.
+    fn feed(&mut self) -> Result<()> {
+        let value = (self.source)(self.program.input_count)?;
+        self.program.push_input(value);
+        Ok(())
+    }
+
+    /// Executes the program until it halts, as `Program::execute`, with the
+    /// inputs of the borrowed source.
+    pub fn execute(&mut self) -> Result<Vec<Cell>> {
+        loop {
+            match self.program.run_until_event()? {
+                Event::Output(_) => {}
+                Event::NeedsInput => self.feed()?,
+                Event::Halted | Event::Stopped(_) => break Ok(self.program.output().to_vec()),
+            }
+        }
+    }
+
+    /// Executes the program until its next output, as
+    /// `Program::execute_until_next_output`, with the inputs of the borrowed
+    /// source.
+    pub fn execute_until_next_output(&mut self) -> Result<Option<Cell>> {
+        loop {
+            match self.program.run_until_event()? {
+                Event::Output(value) => break Ok(Some(value)),
+                Event::NeedsInput => self.feed()?,
+                Event::Halted | Event::Stopped(_) => break Ok(None),
+            }
+        }
+    }
+
+    /// Returns the program, e.g. to read its memory or queue inputs.
+    pub fn program(&mut self) -> &mut Program {
+        self.program
+    }
+}
+
+impl Program {
+    ///
+    /// Calls `f` with the program and an input source borrowing local
+    /// state, and returns its result. For the duration of the scope, the
+    /// borrowed source replaces the input source and the default input of
+    /// the program, which are restored afterwards. Queued inputs are still
+    /// given first.
+    ///
+    /// ```rust
+    /// # use lib::intcode::{Cell, Program};
+    /// // Outputs the sum of its two inputs.
+    /// let mut program: Program = "3,11,3,12,1,11,12,13,4,13,99,0,0,0".parse().unwrap();
+    /// program.set_input(|_| Ok(1));
+    ///
+    /// let inputs = vec![40, 2];
+    /// let outputs = program
+    ///     .with_input_scope(|n| Ok(inputs[n]), |scope| scope.execute())
+    ///     .unwrap();
+    /// assert_eq!(outputs, vec![42]);
+    ///
+    /// program.reset();
+    /// assert_eq!(program.execute().unwrap(), vec![2]);
+    /// ```
+    pub fn with_input_scope<R>(
+        &mut self,
+        mut source: impl FnMut(usize) -> Result<Cell>,
+        f: impl FnOnce(&mut InputScope<'_, '_>) -> R,
+    ) -> R {
+        let input_source = self.input_source.take();
+        let input_default = self.input_default.take();
+
+        let result = f(&mut InputScope {
+            program: self,
+            source: &mut source,
+        });
+
+        self.input_source = input_source;
+        self.input_default = input_default;
+
+        result
+    }
+}
@@ -0,0 +1,138 @@
+//! Scripts of commands given to ASCII programs, e.g. to replay a session.
+
+use super::inputs::InputSource;
+use super::{Cell, Error, Program, Result};
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, BufRead};
+use std::path::Path;
+
+/// What happens when a program asks for an input after the end of its
+/// script. See `Program::set_ascii_script`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ScriptEnd {
+    /// The program fails.
+    Fail,
+
+    /// Executions pausing on inputs pause, e.g. to push more inputs; other
+    /// executions fail.
+    Pause,
+
+    /// The next commands are read from stdin, one per line.
+    Interactive,
+}
+
+/// An input source giving the commands of a script to an ASCII program.
+#[derive(Debug, Clone)]
+pub struct AsciiScript {
+    /// The ASCII codes of the commands not given yet, each followed by a
+    /// new line.
+    codes: VecDeque<Cell>,
+    end: ScriptEnd,
+}
+
+impl AsciiScript {
+    ///
+    /// Parses a script: a command per line, skipping blank lines and those
+    /// starting with `#`. If some lines start with `> `, the script is a
+    /// transcript (see `Program::record_transcript`), and only these lines
+    /// are commands.
+    ///
+    /// ```rust
+    /// # use lib::intcode::script::{AsciiScript, ScriptEnd};
+    /// assert_eq!(AsciiScript::new("a\n\n# Comment\n  b \n", ScriptEnd::Fail).commands(), "a\nb\n");
+    /// assert_eq!(AsciiScript::new("Hi\n> a\nOK\n> b\n", ScriptEnd::Fail).commands(), "a\nb\n");
+    /// ```
+    pub fn new(script: &str, end: ScriptEnd) -> Self {
+        let transcript = script.lines().any(|line| line.starts_with("> "));
+
+        let codes = script
+            .lines()
+            .filter_map(|line| {
+                if transcript {
+                    line.strip_prefix("> ")
+                } else {
+                    Some(line)
+                }
+            })
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .flat_map(|line| line.bytes().chain(Some(b'\n')))
+            .map(Cell::from)
+            .collect();
+
+        AsciiScript { codes, end }
+    }
+
+    /// Returns the commands not given yet, each followed by a new line.
+    pub fn commands(&self) -> String {
+        self.codes.iter().map(|code| *code as u8 as char).collect()
+    }
+}
+
+impl InputSource for AsciiScript {
+    fn next(&mut self, _: usize) -> Result<Cell> {
+        if self.codes.is_empty() && self.end == ScriptEnd::Interactive {
+            let mut line = String::new();
+
+            if io::stdin().lock().read_line(&mut line)? == 0 {
+                return Err(Error::InvalidInput("Unexpected end of input"));
+            }
+
+            let line = line.trim_end_matches(&['\r', '\n'][..]);
+            self.codes
+                .extend(line.bytes().chain(Some(b'\n')).map(Cell::from));
+        }
+
+        self.codes
+            .pop_front()
+            .ok_or(Error::InvalidInput("End of the script"))
+    }
+
+    fn is_ready(&self) -> bool {
+        !self.codes.is_empty() || self.end != ScriptEnd::Pause
+    }
+}
+
+impl Program {
+    ///
+    /// Gives the commands of a script to the program, as ASCII inputs (see
+    /// `AsciiScript::new` for the format), then does as `end` says.
+    ///
+    /// ```rust
+    /// # use lib::intcode::script::ScriptEnd;
+    /// # use lib::intcode::{ExecutionState, Program};
+    /// # use std::fs;
+    /// let path = std::env::temp_dir().join(format!("aoc-2019-script-{}.txt", std::process::id()));
+    ///
+    /// // Echoes its inputs, forever.
+    /// let mut program: Program = "3,100,4,100,1105,1,0".parse().unwrap();
+    /// program.set_ascii_script("# Walk\nnorth\n\ntake coin\n", ScriptEnd::Pause);
+    /// program.record_transcript(&path).unwrap();
+    ///
+    /// assert_eq!(program.execute_until_input_needed().unwrap(), ExecutionState::WaitingForInput);
+    /// assert_eq!(fs::read_to_string(&path).unwrap(), "north\n> north\n\ntake coin\n> take coin\n\n");
+    /// assert_eq!(program.execute().unwrap_err().message(), "End of the script");
+    ///
+    /// // A transcript replays the session.
+    /// let mut replay: Program = "3,100,4,100,1105,1,0".parse().unwrap();
+    /// replay.set_ascii_script_file(&path, ScriptEnd::Fail).unwrap();
+    /// assert_eq!(replay.execute().unwrap_err().message(), "End of the script");
+    /// assert_eq!(replay.output_str(), program.output_str());
+    /// # fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn set_ascii_script(&mut self, script: &str, end: ScriptEnd) {
+        self.set_input_source(AsciiScript::new(script, end));
+    }
+
+    /// Gives the commands of a script file to the program, as
+    /// `set_ascii_script`.
+    pub fn set_ascii_script_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        end: ScriptEnd,
+    ) -> io::Result<()> {
+        self.set_ascii_script(&fs::read_to_string(path)?, end);
+        Ok(())
+    }
+}
@@ -0,0 +1,143 @@
+//! Save states of programs, to stop a long session and resume it later.
+//! Needs the `serde` feature.
+//!
+//! The input source, the hooks and the custom opcodes are not saved: the
+//! input source must be set again after loading, with `set_input`.
+//! Counters and caches (heatmap, coverage, loop detection) start empty.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! // Outputs its own source code.
+//! let source = "109,1,204,-1,1001,100,1,100,1008,100,16,101,1006,101,0,99";
+//! let path = std::env::temp_dir().join(format!("aoc-2019-state-{}.json", std::process::id()));
+//!
+//! let mut program: Program = source.parse().unwrap();
+//! for _ in 0..5 {
+//!     program.execute_until_next_output().unwrap();
+//! }
+//! program.save_state(&path).unwrap();
+//!
+//! let mut restored = Program::load_state(&path).unwrap();
+//! assert_eq!(restored.pointer(), program.pointer());
+//! assert_eq!(restored.output(), program.output());
+//!
+//! let mut uninterrupted: Program = source.parse().unwrap();
+//! assert_eq!(restored.execute().unwrap(), uninterrupted.execute().unwrap());
+//! # std::fs::remove_file(&path).unwrap();
+//! ```
+
+use super::debug::Stopped;
+use super::{Cell, ExecutionStatus, OnUnknownOpcode, Program};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The saved part of a program.
+#[derive(Serialize, Deserialize)]
+struct State {
+    memory: Vec<Cell>,
+    sparse: Option<BTreeMap<usize, Cell>>,
+    initial_memory: Vec<Cell>,
+    patches: Vec<(usize, Cell)>,
+    pointer: usize,
+    relative_base: i64,
+    input_count: usize,
+    queued_input: VecDeque<Cell>,
+    input_default: Option<Cell>,
+    idle_reads: usize,
+    output: Vec<Cell>,
+    drained_output: usize,
+    max_outputs: usize,
+    status: ExecutionStatus,
+    program_len: usize,
+    highest_written: Option<usize>,
+    memory_limit: usize,
+    steps: u64,
+    max_steps: Option<u64>,
+    strict_jumps: bool,
+    on_unknown_opcode: OnUnknownOpcode,
+    history_depth: usize,
+}
+
+impl Serialize for Program {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        State {
+            memory: self.memory.clone(),
+            sparse: self.sparse.clone(),
+            initial_memory: self.initial_memory.to_vec(),
+            patches: self.patches.clone(),
+            pointer: self.pointer,
+            relative_base: self.relative_base,
+            input_count: self.input_count,
+            queued_input: self.queued_input.clone(),
+            input_default: self.input_default,
+            idle_reads: self.idle_reads,
+            output: self.output().to_vec(),
+            drained_output: self.drained_output,
+            max_outputs: self.max_outputs,
+            status: self.status,
+            program_len: self.program_len,
+            highest_written: self.highest_written,
+            memory_limit: self.memory_limit,
+            steps: self.steps,
+            max_steps: self.max_steps,
+            strict_jumps: self.strict_jumps,
+            on_unknown_opcode: self.on_unknown_opcode,
+            history_depth: self.history_depth,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Program {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let state = State::deserialize(deserializer)?;
+
+        let mut program = Program::from_memory(state.memory);
+        program.sparse = state.sparse;
+        program.initial_memory = Rc::new(state.initial_memory);
+        program.patches = state.patches;
+        program.pointer = state.pointer;
+        program.relative_base = state.relative_base;
+        program.input_count = state.input_count;
+        program.queued_input = state.queued_input;
+        program.input_default = state.input_default;
+        program.idle_reads = state.idle_reads;
+        program.output = state.output.into();
+        program.drained_output = state.drained_output;
+        program.max_outputs = state.max_outputs;
+        program.status = state.status;
+        program.resuming = matches!(
+            state.status,
+            ExecutionStatus::Stopped(Stopped::Breakpoint(_))
+        );
+        program.program_len = state.program_len;
+        program.highest_written = state.highest_written;
+        program.memory_limit = state.memory_limit;
+        program.steps = state.steps;
+        program.max_steps = state.max_steps;
+        program.strict_jumps = state.strict_jumps;
+        program.on_unknown_opcode = state.on_unknown_opcode;
+        program.set_history_depth(state.history_depth);
+
+        Ok(program)
+    }
+}
+
+impl Program {
+    /// Saves the state of the program to a file, as JSON.
+    pub fn save_state(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(file, self).map_err(io::Error::from)
+    }
+
+    /// Loads a program saved with `save_state`. Inputs queued when saved
+    /// are kept, but the input source must be set again.
+    pub fn load_state(path: impl AsRef<Path>) -> io::Result<Program> {
+        let file = BufReader::new(File::open(path)?);
+        serde_json::from_reader(file).map_err(io::Error::from)
+    }
+}
@@ -0,0 +1,196 @@
+//! Assertions to test Intcode programs, and the interpreter itself.
+//!
+//! ```rust
+//! # use lib::intcode::Program;
+//! let mut program: Program = "3,9,8,9,10,9,4,9,99,-1,8".parse().unwrap();
+//! program.assert_outputs(&[8], &[1]).unwrap();
+//!
+//! let mut program: Program = "1105,1,0".parse().unwrap();
+//! assert!(program.assert_halts_within(1000).is_err());
+//! ```
+
+use super::inputs::QueueInput;
+use super::{Cell, Error, Program};
+use std::fmt::{self, Display, Formatter};
+
+/// A finished execution of a program. See `Program::assert_halts_within`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Execution {
+    /// The number of instructions executed.
+    pub steps: u64,
+
+    /// Everything the program outputted.
+    pub outputs: Vec<Cell>,
+}
+
+/// A difference between the outputs of a program and the expected ones,
+/// at a given index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mismatch {
+    /// Both values are there, but different.
+    Different {
+        index: usize,
+        expected: Cell,
+        actual: Cell,
+    },
+
+    /// The program stopped outputting before this value.
+    Missing { index: usize, expected: Cell },
+
+    /// The program outputted more values than expected.
+    Unexpected { index: usize, actual: Cell },
+}
+
+/// Why an assertion on a program failed.
+#[derive(Debug)]
+pub enum AssertionError {
+    /// The program itself failed.
+    Program(Error),
+
+    /// The program was still running after this number of steps.
+    StepLimit(u64),
+
+    /// The program outputted something else than expected.
+    Outputs(Vec<Mismatch>),
+}
+
+impl Display for AssertionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AssertionError::Program(error) => write!(f, "program failed: {}", error),
+            AssertionError::StepLimit(steps) => {
+                write!(f, "program still running after {} steps", steps)
+            }
+            AssertionError::Outputs(mismatches) => {
+                write!(f, "outputs differ from the expected ones:")?;
+
+                for mismatch in mismatches {
+                    match mismatch {
+                        Mismatch::Different {
+                            index,
+                            expected,
+                            actual,
+                        } => write!(f, "\n  [{}] expected {}, got {}", index, expected, actual)?,
+                        Mismatch::Missing { index, expected } => {
+                            write!(f, "\n  [{}] expected {}, got nothing", index, expected)?
+                        }
+                        Mismatch::Unexpected { index, actual } => {
+                            write!(f, "\n  [{}] unexpected {}", index, actual)?
+                        }
+                    }
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<Error> for AssertionError {
+    fn from(error: Error) -> Self {
+        AssertionError::Program(error)
+    }
+}
+
+///
+/// Lists the differences between actual and expected outputs, index by index.
+///
+/// ```rust
+/// # use lib::intcode::testing::{compare_outputs, Mismatch};
+/// assert_eq!(compare_outputs(&[1, 2], &[1, 2]), vec![]);
+/// assert_eq!(
+///     compare_outputs(&[1, 5, 7], &[1, 2]),
+///     vec![
+///         Mismatch::Different { index: 1, expected: 2, actual: 5 },
+///         Mismatch::Unexpected { index: 2, actual: 7 },
+///     ]
+/// );
+/// assert_eq!(
+///     compare_outputs(&[], &[4]),
+///     vec![Mismatch::Missing { index: 0, expected: 4 }]
+/// );
+/// ```
+pub fn compare_outputs(actual: &[Cell], expected: &[Cell]) -> Vec<Mismatch> {
+    (0..actual.len().max(expected.len()))
+        .filter_map(|index| match (expected.get(index), actual.get(index)) {
+            (Some(expected), Some(actual)) if expected == actual => None,
+            (Some(&expected), Some(&actual)) => Some(Mismatch::Different {
+                index,
+                expected,
+                actual,
+            }),
+            (Some(&expected), None) => Some(Mismatch::Missing { index, expected }),
+            (None, Some(&actual)) => Some(Mismatch::Unexpected { index, actual }),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+impl Program {
+    ///
+    /// Executes the program, failing if it doesn't halt within the given
+    /// number of steps (instructions).
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "104,1,104,2,99".parse().unwrap();
+    /// let execution = program.assert_halts_within(10).unwrap();
+    /// assert_eq!(execution.steps, 3);
+    /// assert_eq!(execution.outputs, vec![1, 2]);
+    ///
+    /// let mut program: Program = "104,1,104,2,99".parse().unwrap();
+    /// let error = program.assert_halts_within(2).unwrap_err();
+    /// assert_eq!(error.to_string(), "program still running after 2 steps");
+    /// ```
+    pub fn assert_halts_within(&mut self, steps: u64) -> Result<Execution, AssertionError> {
+        self.start();
+
+        for step in 1..=steps {
+            if !self.forward()? {
+                return Ok(Execution {
+                    steps: step,
+                    outputs: self.output().to_vec(),
+                });
+            }
+        }
+
+        Err(AssertionError::StepLimit(steps))
+    }
+
+    ///
+    /// Executes the program with the given inputs, failing if its outputs
+    /// are not the expected ones. The error lists every mismatch.
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// let mut program: Program = "3,0,4,0,104,2,104,3,99".parse().unwrap();
+    /// let error = program.assert_outputs(&[1], &[1, 5]).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "outputs differ from the expected ones:\n  [1] expected 5, got 2\n  [2] unexpected 3"
+    /// );
+    ///
+    /// let mut program: Program = "3,0,99".parse().unwrap();
+    /// let error = program.assert_outputs(&[], &[]).unwrap_err();
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "program failed: No more inputs at address 0, in instruction 3,0"
+    /// );
+    /// ```
+    pub fn assert_outputs(
+        &mut self,
+        inputs: &[Cell],
+        expected: &[Cell],
+    ) -> Result<(), AssertionError> {
+        self.set_input_source(QueueInput::new(inputs));
+
+        let mismatches = compare_outputs(&self.execute()?, expected);
+
+        if mismatches.is_empty() {
+            Ok(())
+        } else {
+            Err(AssertionError::Outputs(mismatches))
+        }
+    }
+}
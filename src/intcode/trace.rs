@@ -0,0 +1,375 @@
+//! Traces of the instructions executed by programs, built on observers
+//! (see `Program::set_observer`).
+//!
+//! Traces are written as text, to read, or as JSON lines, to process them
+//! (needs the `serde` feature). JSON traces can be checked against a
+//! program, e.g. to make sure a change to the interpreter doesn't change
+//! how programs run.
+//!
+//! ```rust
+//! # #[cfg(feature = "serde")]
+//! # {
+//! # use lib::intcode::trace::{verify, VerifyError};
+//! # use lib::intcode::Program;
+//! # use std::fs::{self, File};
+//! # use std::io::{BufReader, Cursor};
+//! let path = std::env::temp_dir().join(format!("aoc-2019-trace-{}.json", std::process::id()));
+//!
+//! // Outputs 1 if its input is 8, else 0.
+//! let source = "3,9,8,9,10,9,4,9,99,-1,8";
+//! let mut program: Program = source.parse().unwrap();
+//! program.push_input(8);
+//! program.trace_json_to(File::create(&path).unwrap());
+//! program.execute().unwrap();
+//!
+//! let trace = fs::read_to_string(&path).unwrap();
+//! assert_eq!(
+//!     trace.lines().nth(2).unwrap(),
+//!     r#"{"step":3,"pointer":6,"opcode":4,"params":[{"mode":"position","value":9,"resolved":1}],"write":null,"output":1,"input":null}"#
+//! );
+//!
+//! // The inputs are taken from the trace.
+//! assert_eq!(verify(source, BufReader::new(File::open(&path).unwrap())).unwrap(), 4);
+//!
+//! let doctored = trace.replace(r#""output":1"#, r#""output":0"#);
+//! match verify(source, Cursor::new(doctored)).unwrap_err() {
+//!     VerifyError::Divergence { record, expected, actual } => {
+//!         assert_eq!(record, 3);
+//!         assert_eq!(expected.output, Some(0));
+//!         assert_eq!(actual.unwrap().output, Some(1));
+//!     }
+//!     error => panic!("unexpected error: {}", error),
+//! }
+//!
+//! let extended = format!("{}{}\n", trace, trace.lines().last().unwrap());
+//! assert_eq!(
+//!     verify(source, Cursor::new(extended)).unwrap_err().to_string(),
+//!     "the trace diverges at record 5: the program halted before"
+//! );
+//! # fs::remove_file(&path).unwrap();
+//! # }
+//! ```
+
+#[cfg(feature = "serde")]
+use super::inputs::QueueInput;
+use super::{disasm, InstructionEvent, Program};
+#[cfg(feature = "serde")]
+use super::{Cell, Error, ExecutionStatus, ParameterMode};
+use itertools::Itertools;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "serde")]
+use std::cell::RefCell;
+#[cfg(feature = "serde")]
+use std::fmt::{self, Display, Formatter};
+#[cfg(feature = "serde")]
+use std::io::BufRead;
+use std::io::Write;
+#[cfg(feature = "serde")]
+use std::rc::Rc;
+
+/// The mode of a parameter, in a JSON trace.
+#[cfg(feature = "serde")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    Position,
+    Immediate,
+    Relative,
+}
+
+/// A parameter of an instruction, in a JSON trace.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceParameter {
+    pub mode: Mode,
+
+    /// The parameter, as in memory.
+    pub value: Cell,
+
+    /// The value read by the instruction, resolved according to the mode;
+    /// none if the parameter wasn't read (e.g. written to).
+    pub resolved: Option<Cell>,
+}
+
+/// An instruction executed, as a line of a JSON trace.
+#[cfg(feature = "serde")]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceRecord {
+    pub step: u64,
+    pub pointer: usize,
+
+    /// The opcode, without the parameter modes.
+    pub opcode: Cell,
+    pub params: Vec<TraceParameter>,
+
+    /// The address written by the instruction and the value written, if any.
+    pub write: Option<(usize, Cell)>,
+
+    pub output: Option<Cell>,
+    pub input: Option<Cell>,
+}
+
+#[cfg(feature = "serde")]
+impl From<&InstructionEvent> for TraceRecord {
+    fn from(event: &InstructionEvent) -> Self {
+        let opcode = event.instruction % 100;
+        let mut modes = event.instruction / 100;
+
+        // The parameters written to are the last ones, so the values read
+        // are those of the first parameters.
+        let params = event
+            .parameters
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                let mode = match ParameterMode::from_digit(modes % 10) {
+                    Some(ParameterMode::Immediate) => Mode::Immediate,
+                    Some(ParameterMode::Relative) => Mode::Relative,
+                    _ => Mode::Position,
+                };
+                modes /= 10;
+
+                TraceParameter {
+                    mode,
+                    value: *value,
+                    resolved: event.values.get(i).cloned(),
+                }
+            })
+            .collect();
+
+        TraceRecord {
+            step: event.step,
+            pointer: event.address,
+            opcode,
+            params,
+            write: event.write,
+            output: event.values.first().cloned().filter(|_| opcode == 4),
+            input: event.write.map(|(_, value)| value).filter(|_| opcode == 3),
+        }
+    }
+}
+
+/// Why a JSON trace doesn't match a program. See `verify`.
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum VerifyError {
+    /// A line of the trace (starting at 1) can't be read.
+    InvalidTrace { line: usize, message: String },
+
+    /// The program failed, at this record of the trace (starting at 1; 0 if
+    /// the source code is invalid).
+    Program { record: usize, error: Error },
+
+    /// A record of the trace (starting at 1) is not what the program did,
+    /// or the program halted before it.
+    Divergence {
+        record: usize,
+        expected: Box<TraceRecord>,
+        actual: Option<Box<TraceRecord>>,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl Display for VerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let json = |record: &TraceRecord| serde_json::to_string(record).unwrap_or_default();
+
+        match self {
+            VerifyError::InvalidTrace { line, message } => {
+                write!(f, "invalid trace at line {}: {}", line, message)
+            }
+            VerifyError::Program { record, error } => {
+                write!(f, "the program failed at record {}: {}", record, error)
+            }
+            VerifyError::Divergence {
+                record,
+                actual: None,
+                ..
+            } => write!(
+                f,
+                "the trace diverges at record {}: the program halted before",
+                record
+            ),
+            VerifyError::Divergence {
+                record,
+                expected,
+                actual: Some(actual),
+            } => write!(
+                f,
+                "the trace diverges at record {}: expected {}, got {}",
+                record,
+                json(expected),
+                json(actual)
+            ),
+        }
+    }
+}
+
+///
+/// Runs a program and checks it executes the instructions of a JSON trace
+/// (see `Program::trace_json_to`), giving it the inputs of the trace.
+/// Returns the number of records checked, or the first divergence. Only
+/// the instructions in the trace are checked: the program may continue
+/// after them.
+#[cfg(feature = "serde")]
+pub fn verify(source_code: &str, trace: impl BufRead) -> Result<usize, VerifyError> {
+    let mut records = vec![];
+    for (index, line) in trace.lines().enumerate() {
+        let invalid = |message: String| VerifyError::InvalidTrace {
+            line: index + 1,
+            message,
+        };
+
+        let line = line.map_err(|e| invalid(e.to_string()))?;
+        if !line.trim().is_empty() {
+            records.push(
+                serde_json::from_str::<TraceRecord>(&line).map_err(|e| invalid(e.to_string()))?,
+            );
+        }
+    }
+
+    let mut program: Program = source_code
+        .parse()
+        .map_err(|error| VerifyError::Program { record: 0, error })?;
+    program.set_input_source(QueueInput(
+        records.iter().filter_map(|record| record.input).collect(),
+    ));
+
+    let last = Rc::new(RefCell::new(None));
+    let observed = Rc::clone(&last);
+    program.set_observer(move |event| *observed.borrow_mut() = Some(TraceRecord::from(event)));
+
+    for (index, expected) in records.iter().enumerate() {
+        let record = index + 1;
+
+        if let ExecutionStatus::Halted | ExecutionStatus::Failed = program.status() {
+            return Err(VerifyError::Divergence {
+                record,
+                expected: Box::new(expected.clone()),
+                actual: None,
+            });
+        }
+
+        program
+            .step()
+            .map_err(|error| VerifyError::Program { record, error })?;
+
+        let actual = last.borrow_mut().take();
+        if actual.as_ref() != Some(expected) {
+            return Err(VerifyError::Divergence {
+                record,
+                expected: Box::new(expected.clone()),
+                actual: actual.map(Box::new),
+            });
+        }
+    }
+
+    Ok(records.len())
+}
+
+/// Returns the line of a trace for an instruction executed.
+fn trace_line(event: &InstructionEvent) -> String {
+    let cells: Vec<_> = std::iter::once(event.instruction)
+        .chain(event.parameters.iter().cloned())
+        .collect();
+
+    // Custom opcodes are not known to the disassembler.
+    let listing = match disasm::decode(&cells) {
+        Some((line, _)) => line.to_listing(None),
+        None => cells.iter().join(","),
+    };
+
+    let mut line = format!("step {} @ {}: {}", event.step, event.address, listing);
+    if !event.values.is_empty() {
+        line.push_str(&format!("; read {}", event.values.iter().join(", ")));
+    }
+    if let Some((address, value)) = event.write {
+        line.push_str(&format!("; wrote {} at {}", value, address));
+    }
+
+    line
+}
+
+impl Program {
+    ///
+    /// Writes a line to the writer for each instruction executed, with the
+    /// number of the step, the address and the instruction, disassembled
+    /// (see `disasm`), the values of the parameters it read and what it
+    /// wrote. Each line is flushed, so a trace is usable even if the
+    /// program never ends. Errors writing the trace don't stop the program:
+    /// a warning is printed, and the trace stops.
+    ///
+    /// The trace is written by an observer, replacing any other (see
+    /// `set_observer`).
+    ///
+    /// ```rust
+    /// # use lib::intcode::Program;
+    /// # use std::cell::RefCell;
+    /// # use std::io::{self, Write};
+    /// # use std::rc::Rc;
+    /// /// A buffer shared with the program.
+    /// #[derive(Clone, Default)]
+    /// struct Buffer(Rc<RefCell<Vec<u8>>>);
+    ///
+    /// impl Write for Buffer {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         self.0.borrow_mut().write(buf)
+    ///     }
+    ///
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut program: Program = "1101,2,3,14,1002,14,2,14,4,14,1105,0,0,99,0".parse().unwrap();
+    /// let trace = Buffer::default();
+    /// program.trace_to(trace.clone());
+    /// assert_eq!(program.execute().unwrap(), vec![10]);
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(trace.0.borrow().clone()).unwrap(),
+    ///     "step 1 @ 0: add #2, #3, [14]; read 2, 3; wrote 5 at 14
+    /// step 2 @ 4: mul [14], #2, [14]; read 5, 2; wrote 10 at 14
+    /// step 3 @ 8: out [14]; read 10
+    /// step 4 @ 10: jnz #0, #0; read 0
+    /// step 5 @ 13: hlt
+    /// "
+    /// );
+    /// ```
+    pub fn trace_to(&mut self, writer: impl Write + 'static) {
+        let mut writer = Some(writer);
+
+        self.set_observer(move |event| {
+            if let Some(trace) = &mut writer {
+                if let Err(e) = writeln!(trace, "{}", trace_line(event)).and_then(|_| trace.flush())
+                {
+                    eprintln!("Unable to write the trace, tracing stopped: {}", e);
+                    writer = None;
+                }
+            }
+        });
+    }
+
+    /// Writes a JSON trace to the writer: a line for each instruction
+    /// executed, as a `TraceRecord`. As `trace_to`, each line is flushed,
+    /// and errors writing the trace stop it.
+    #[cfg(feature = "serde")]
+    pub fn trace_json_to(&mut self, writer: impl Write + 'static) {
+        let mut writer = Some(writer);
+
+        self.set_observer(move |event| {
+            if let Some(trace) = &mut writer {
+                let written = serde_json::to_writer(&mut *trace, &TraceRecord::from(event))
+                    .map_err(std::io::Error::from)
+                    .and_then(|_| writeln!(trace))
+                    .and_then(|_| trace.flush());
+
+                if let Err(e) = written {
+                    eprintln!("Unable to write the trace, tracing stopped: {}", e);
+                    writer = None;
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,208 @@
+//! Static validation of programs, to find mistakes without running them.
+//!
+//! The instructions are decoded from address 0, following the jumps whose
+//! target is known without running the program (immediate targets). Both
+//! branches of a conditional jump are followed, unless its condition is an
+//! immediate value. Self-modifying programs are checked as loaded, so they
+//! may get warnings for instructions they rewrite before executing them.
+
+use super::{Cell, OpCode, Program};
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::fmt::{self, Display, Formatter};
+
+/// The kinds of mistakes found by `Program::validate`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// An unknown opcode, or an unknown parameter mode.
+    UnknownOpcode,
+
+    /// A parameter written to, in immediate mode.
+    ImmediateWrite,
+
+    /// A jump to a negative address, or after the end of the memory.
+    JumpOutOfRange,
+
+    /// An instruction truncated by the end of the memory.
+    RunsOffEnd,
+
+    /// No halt instruction can be reached from the start of the program.
+    NoReachableHalt,
+}
+
+/// A possible mistake in a program. See `Program::validate`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationWarning {
+    /// The address of the instruction, or 0 for the whole program.
+    pub address: usize,
+    pub kind: WarningKind,
+    pub description: String,
+}
+
+impl Display for ValidationWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.address, self.description)
+    }
+}
+
+impl Program {
+    ///
+    /// Checks the program without running it, for unknown opcodes, writes
+    /// in immediate mode, jumps out of the memory, instructions running off
+    /// its end, and the lack of a reachable halt instruction. Returns the
+    /// warnings sorted by address; a correct program has none. See the
+    /// `validation` module for the limits of this check.
+    ///
+    /// ```rust
+    /// # use lib::intcode::catalog::DAY5_COMPARE_TO_8;
+    /// # use lib::intcode::validation::WarningKind;
+    /// # use lib::intcode::Program;
+    /// let program: Program = DAY5_COMPARE_TO_8.source_code.parse().unwrap();
+    /// assert!(program.validate().is_empty());
+    ///
+    /// let program: Program = "11101,1,2,3,1005,3,100,1006,3,12,42,0,104".parse().unwrap();
+    /// let warnings = program.validate();
+    /// assert_eq!(
+    ///     warnings.iter().map(|w| (w.address, w.kind)).collect::<Vec<_>>(),
+    ///     vec![
+    ///         (0, WarningKind::ImmediateWrite),
+    ///         (0, WarningKind::NoReachableHalt),
+    ///         (4, WarningKind::JumpOutOfRange),
+    ///         (10, WarningKind::UnknownOpcode),
+    ///         (12, WarningKind::RunsOffEnd),
+    ///     ]
+    /// );
+    /// assert_eq!(warnings[2].to_string(), "4: Jump to 100, outside of the memory");
+    /// ```
+    pub fn validate(&self) -> Vec<ValidationWarning> {
+        let mut warnings = vec![];
+        let mut warn = |address, kind, description| {
+            warnings.push(ValidationWarning {
+                address,
+                kind,
+                description,
+            })
+        };
+
+        let mut visited = HashSet::new();
+        let mut pending = vec![0];
+        let mut halts = false;
+
+        while let Some(address) = pending.pop() {
+            if !visited.insert(address) {
+                continue;
+            }
+
+            let opcode_code = match self.cell(address) {
+                Some(opcode_code) => opcode_code,
+                None => {
+                    warn(
+                        address,
+                        WarningKind::RunsOffEnd,
+                        "Runs off the end of the memory".to_string(),
+                    );
+                    continue;
+                }
+            };
+
+            let (opcode, parameters_count) = match self.parse_opcode(opcode_code) {
+                Some(opcode) => opcode,
+                None => {
+                    warn(
+                        address,
+                        WarningKind::UnknownOpcode,
+                        format!("Unknown opcode {}", opcode_code),
+                    );
+                    continue;
+                }
+            };
+
+            let modes: Vec<Cell> = (0..parameters_count as u32)
+                .map(|i| opcode_code / (100 * (10 as Cell).pow(i)) % 10)
+                .collect();
+
+            if modes.iter().any(|mode| *mode > 2) {
+                warn(
+                    address,
+                    WarningKind::UnknownOpcode,
+                    format!("Unknown parameter mode in {}", opcode_code),
+                );
+                continue;
+            }
+
+            if self.cell(address + parameters_count).is_none() {
+                warn(
+                    address,
+                    WarningKind::RunsOffEnd,
+                    format!(
+                        "Instruction {} truncated by the end of the memory",
+                        opcode_code
+                    ),
+                );
+                continue;
+            }
+
+            let parameter = |i: usize| self.cell(address + 1 + i).unwrap_or(0);
+            let next = address + parameters_count + 1;
+
+            let written = match opcode {
+                OpCode::Arithmetic(_) | OpCode::Test(_) => Some(2),
+                OpCode::Input => Some(0),
+                _ => None,
+            };
+
+            if let Some(written) = written {
+                if modes[written] == 1 {
+                    warn(
+                        address,
+                        WarningKind::ImmediateWrite,
+                        format!(
+                            "Parameter {} of {} is written to in immediate mode",
+                            written + 1,
+                            opcode_code
+                        ),
+                    );
+                }
+            }
+
+            match opcode {
+                OpCode::Halt => halts = true,
+                OpCode::Jump(condition) => {
+                    let taken = match modes[0] {
+                        1 => Some(self.check_condition(condition, parameter(0))),
+                        _ => None,
+                    };
+
+                    if taken != Some(true) {
+                        pending.push(next);
+                    }
+
+                    // Targets read from memory are only known when running.
+                    if taken != Some(false) && modes[1] == 1 {
+                        let target = parameter(1);
+                        match usize::try_from(target) {
+                            Ok(target) if target < self.memory_len() => pending.push(target),
+                            _ => warn(
+                                address,
+                                WarningKind::JumpOutOfRange,
+                                format!("Jump to {}, outside of the memory", target),
+                            ),
+                        }
+                    }
+                }
+                _ => pending.push(next),
+            }
+        }
+
+        if !halts {
+            warn(
+                0,
+                WarningKind::NoReachableHalt,
+                "No halt instruction reachable from the start".to_string(),
+            );
+        }
+
+        warnings.sort_by_key(|warning| warning.address);
+        warnings
+    }
+}
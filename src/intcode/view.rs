@@ -0,0 +1,258 @@
+//! A live view of programs running: the pointer, the code around it, a
+//! window of the memory and the last outputs, refreshed as the program
+//! runs.
+//!
+//! In a terminal, the view is redrawn in place; otherwise, e.g. when the
+//! output is redirected, each frame is printed after the previous one.
+
+use super::{Cell, Error, Program, Result};
+use itertools::Itertools;
+use std::io::{self, IsTerminal, Write};
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Clears the terminal, and moves the cursor to its top left corner.
+const CLEAR_SCREEN: &str = "\x1b[H\x1b[2J";
+
+/// The number of cells on a line of the memory window.
+const MEMORY_ROW_LEN: usize = 8;
+
+/// What the view shows, and how often. See `Program::run_with_view`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ViewOptions {
+    /// The number of instructions executed between two frames.
+    pub steps_per_frame: u64,
+
+    /// The longest time between two frames, for slow instructions (e.g.
+    /// waiting for inputs).
+    pub frame_interval: Duration,
+
+    /// The number of instructions listed before the pointer, and from it.
+    pub code_before: usize,
+    pub code_after: usize,
+
+    /// The addresses of the memory shown.
+    pub memory: Range<usize>,
+
+    /// The number of last outputs shown.
+    pub outputs: usize,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        ViewOptions {
+            steps_per_frame: 1000,
+            frame_interval: Duration::from_millis(100),
+            code_before: 3,
+            code_after: 6,
+            memory: 0..32,
+            outputs: 10,
+        }
+    }
+}
+
+impl Program {
+    /// Returns the addresses and the listings of the instructions around
+    /// the pointer. Those before it are decoded from the start of the
+    /// memory, so they may be data if the code doesn't line up.
+    fn code_window(&self, before: usize, after: usize) -> Vec<(usize, String)> {
+        let mut lines = vec![];
+        let mut address = 0;
+
+        while address < self.pointer {
+            let (listing, length) = self.listing_at(address);
+            if address + length <= self.pointer {
+                lines.push((address, listing));
+            }
+            address += length;
+        }
+        lines.drain(..lines.len().saturating_sub(before));
+
+        let mut address = self.pointer;
+        for _ in 0..after {
+            let (listing, length) = self.listing_at(address);
+            lines.push((address, listing));
+            address += length;
+        }
+
+        lines
+    }
+
+    ///
+    /// Renders a frame of the view of the program, as text.
+    ///
+    /// ```rust
+    /// # use lib::intcode::asm::assemble;
+    /// # use lib::intcode::view::ViewOptions;
+    /// # use lib::intcode::Program;
+    /// // Outputs 1, 2 and 3.
+    /// let memory = assemble(
+    ///     "loop:  out [n]
+    ///             add [n], #1, [n]
+    ///             lt [n], #4, [c]
+    ///             jnz [c], loop
+    ///             hlt
+    ///      n:     data 1
+    ///      c:     data 0",
+    /// )
+    /// .unwrap();
+    ///
+    /// let mut program = Program::from_memory(memory);
+    /// for _ in 0..6 {
+    ///     program.step().unwrap();
+    /// }
+    ///
+    /// let options = ViewOptions {
+    ///     code_before: 2,
+    ///     code_after: 3,
+    ///     memory: 10..20,
+    ///     outputs: 1,
+    ///     ..ViewOptions::default()
+    /// };
+    /// assert_eq!(
+    ///     program.render_view(&options),
+    ///     "Step 6, pointer 6, relative base 0: Running
+    ///
+    /// Code:
+    ///       0: out [14]
+    ///       2: add [14], #1, [14]
+    ///   ->  6: lt [14], #4, [15]
+    ///      10: jnz [15], #0
+    ///      13: hlt
+    ///
+    /// Memory:
+    ///      10: 1005 15 0 99 3 1
+    ///
+    /// Outputs (2): ..., 2
+    /// "
+    /// );
+    /// ```
+    pub fn render_view(&self, options: &ViewOptions) -> String {
+        let mut frame = format!(
+            "Step {}, pointer {}, relative base {}: {:?}\n\nCode:\n",
+            self.steps, self.pointer, self.relative_base, self.status
+        );
+
+        let code = self.code_window(options.code_before, options.code_after);
+        let width = code
+            .iter()
+            .map(|(address, _)| address.to_string().len())
+            .max()
+            .unwrap_or(0);
+        for (address, listing) in code {
+            let marker = if address == self.pointer { "->" } else { "" };
+            frame.push_str(&format!(
+                "  {:2} {:>width$}: {}\n",
+                marker,
+                address,
+                listing,
+                width = width
+            ));
+        }
+
+        frame.push_str("\nMemory:\n");
+        let end = options.memory.end.min(self.memory_len());
+        for row in (options.memory.start..end).step_by(MEMORY_ROW_LEN) {
+            let len = MEMORY_ROW_LEN.min(end - row);
+            frame.push_str(&format!(
+                "  {:>5}: {}\n",
+                row,
+                self.get_range(row, len).iter().join(" ")
+            ));
+        }
+
+        let outputs = self.output();
+        let shown = &outputs[outputs.len().saturating_sub(options.outputs)..];
+        frame.push_str(&format!("\nOutputs ({}): ", self.outputs_produced()));
+        if shown.len() < outputs.len() {
+            frame.push_str("..., ");
+        }
+        frame.push_str(&shown.iter().join(", "));
+        frame.push('\n');
+
+        frame
+    }
+
+    /// Runs the program with its view on stdout, as `run_with_view_to`,
+    /// redrawn in place if stdout is a terminal.
+    pub fn run_with_view(&mut self, options: &ViewOptions) -> Result<Vec<Cell>> {
+        let live = io::stdout().is_terminal();
+        self.run_with_view_to(options, io::stdout(), live)
+    }
+
+    ///
+    /// Runs the program until it halts or stops, writing a frame of its
+    /// view (see `render_view`) every few instructions, or every few
+    /// moments, as set in the options, and when it ends. In live mode,
+    /// each frame replaces the previous one, as in a terminal. Returns the
+    /// outputs of the program; on errors, the last frame shows where the
+    /// program failed.
+    ///
+    /// ```rust
+    /// # use lib::intcode::view::ViewOptions;
+    /// # use lib::intcode::Program;
+    /// # use std::time::Duration;
+    /// // Outputs 1, 2 and 3, in 5 instructions.
+    /// let mut program: Program = "104,1,104,2,1101,1,2,11,4,11,99,0".parse().unwrap();
+    /// let options = ViewOptions {
+    ///     steps_per_frame: 2,
+    ///     frame_interval: Duration::from_secs(60),
+    ///     ..ViewOptions::default()
+    /// };
+    ///
+    /// let mut frames = vec![];
+    /// assert_eq!(program.run_with_view_to(&options, &mut frames, false).unwrap(), vec![1, 2, 3]);
+    ///
+    /// let frames = String::from_utf8(frames).unwrap();
+    /// let headers: Vec<&str> = frames.lines().filter(|line| line.starts_with("Step")).collect();
+    /// assert_eq!(
+    ///     headers,
+    ///     vec![
+    ///         "Step 2, pointer 4, relative base 0: Running",
+    ///         "Step 4, pointer 10, relative base 0: Running",
+    ///         "Step 5, pointer 11, relative base 0: Halted",
+    ///     ]
+    /// );
+    /// ```
+    pub fn run_with_view_to(
+        &mut self,
+        options: &ViewOptions,
+        mut output: impl Write,
+        live: bool,
+    ) -> Result<Vec<Cell>> {
+        let mut draw = |program: &Program| {
+            let frame = program.render_view(options);
+            match live {
+                true => write!(output, "{}{}", CLEAR_SCREEN, frame),
+                false => writeln!(output, "{}", frame),
+            }
+            .and_then(|_| output.flush())
+            .map_err(|_| Error::InvalidOutput("Cannot write output"))
+        };
+
+        self.start();
+        let mut steps = 0;
+        let mut last_frame = Instant::now();
+
+        loop {
+            let result = self.forward();
+            steps += 1;
+
+            let running = matches!(result, Ok(true));
+            if !running
+                || steps >= options.steps_per_frame
+                || last_frame.elapsed() >= options.frame_interval
+            {
+                draw(self)?;
+                steps = 0;
+                last_frame = Instant::now();
+            }
+
+            if !result? {
+                break;
+            }
+        }
+
+        Ok(self.output().to_vec())
+    }
+}
@@ -1,47 +1,345 @@
+//! My answers to Advent of Code 2019, usable as a library.
+//!
+//! The `prelude` module re-exports what other programs can rely on. The
+//! rest of the crate is not stable: it may be moved or changed at any
+//! time, as the solutions are reworked.
+
 #![feature(result_map_or_else)]
 
 extern crate itertools;
 
-use std::fmt::Display;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::cell::RefCell;
+use std::fmt::{self, Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub mod intcode;
 
 pub mod days;
 
+pub mod doctor;
+
+pub mod init;
+
+pub mod prelude;
+
+pub mod input;
+
+pub mod render;
+
+pub mod util;
+
+/// Whether the intermediate values of the solutions are collected.
+static EXPLAIN: AtomicBool = AtomicBool::new(false);
+
+/// Whether the statistics of the inputs are collected, for the days
+/// computing some.
+static STATS: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// The intermediate values collected while solving, on this thread.
+    static EXPLANATIONS: RefCell<Vec<(String, String)>> = const { RefCell::new(vec![]) };
+
+    /// The labels replacing those of the day for the answers, on this
+    /// thread.
+    static LABELS: RefCell<[Option<String>; 2]> = const { RefCell::new([None, None]) };
+}
+
+/// An error preventing a day from being solved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub message: String,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl From<intcode::Error> for Error {
+    fn from(error: intcode::Error) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for Error {
+    fn from(error: Box<dyn std::error::Error>) -> Self {
+        Error {
+            message: error.to_string(),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// An answer to a puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(i128),
+
+    /// Answers that aren't numbers, like the images some puzzles draw.
+    Text(String),
+
+    /// The part is not solved yet, e.g. a stub for the second part of a
+    /// day in progress.
+    NotImplemented,
+}
+
+impl Value {
+    ///
+    /// Checks if the answer is an actual one, i.e. the part is implemented.
+    ///
+    /// ```rust
+    /// # use lib::{format_answer, Value};
+    /// assert!(Value::Number(42).is_implemented());
+    /// assert!(!Value::NotImplemented.is_implemented());
+    /// assert_eq!(
+    ///     format_answer(2, "Fuel", &Value::NotImplemented, false),
+    ///     "2 - Fuel: not implemented yet\n"
+    /// );
+    /// ```
+    pub fn is_implemented(&self) -> bool {
+        *self != Value::NotImplemented
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::Text(text) => write!(f, "{}", text),
+            Value::NotImplemented => write!(f, "not implemented yet"),
+        }
+    }
+}
+
+macro_rules! impl_value_from_number {
+    ($($number:ty),*) => {
+        $(impl From<$number> for Value {
+            fn from(number: $number) -> Self {
+                Value::Number(number as i128)
+            }
+        })*
+    };
+}
+
+impl_value_from_number!(i32, u32, i64, u64, i128, usize);
+
+impl From<String> for Value {
+    fn from(text: String) -> Self {
+        Value::Text(text)
+    }
+}
+
+///
+/// Solves a day from its input, returning the answers to both parts. This
+/// is the entry point to use this crate as a library: it does not print
+/// anything nor access any file.
 ///
-/// Loads the input from the sources directory. Files have to be in
-/// /input/day-12-2.txt for day 12 problem 2 (and the same for others).
+/// The input is the content of the puzzle input file.
 ///
-fn input(day: u8) -> Vec<String> {
-    let filename = format!("input/day-{day}.txt", day = day);
+/// ```rust
+/// # use lib::{solve_day, Value};
+/// assert_eq!(
+///     solve_day(1, "12\n14\n1969\n100756\n"),
+///     Ok((Value::Number(2 + 2 + 654 + 33583), Value::Number(2 + 2 + 966 + 50346)))
+/// );
+///
+/// assert_eq!(
+///     solve_day(2, "1,0,0,0,99,19690000,720,0,0,0,0,0,0"),
+///     Ok((Value::Number(2), Value::Number(506)))
+/// );
+///
+/// assert_eq!(
+///     solve_day(4, "111110-111123"),
+///     Ok((Value::Number(11), Value::Number(1)))
+/// );
+///
+/// assert_eq!(
+///     solve_day(6, "COM)B\nB)C\nC)D\nD)E\nE)F\nB)G\nG)H\nD)I\nE)J\nJ)K\nK)L\nK)YOU\nI)SAN\n"),
+///     Ok((Value::Number(54), Value::Number(4)))
+/// );
+///
+/// assert!(solve_day(42, "").is_err());
+/// ```
+pub fn solve_day(day: u8, input: &str) -> Result<(Value, Value)> {
+    match days::day(day) {
+        Some(day) => (day.solve)(input),
+        None => Err(Error::from("Nothing for this day")),
+    }
+}
+
+/// Enables or disables the collection of the intermediate values of the
+/// solutions. See `take_explanations`.
+pub fn set_explain(enabled: bool) {
+    EXPLAIN.store(enabled, Ordering::Relaxed);
+}
+
+/// Enables or disables the collection of the statistics of the inputs,
+/// e.g. the structure of the day 6 orbit map. They are returned by
+/// `take_explanations`, with the intermediate values.
+pub fn set_stats(enabled: bool) {
+    STATS.store(enabled, Ordering::Relaxed);
+}
+
+///
+/// Returns the intermediate values and the statistics collected while
+/// solving on this thread, as `(label, value)` pairs, and forgets them.
+/// Nothing is collected unless enabled with `set_explain` or `set_stats`.
+///
+/// ```rust
+/// # use lib::{set_explain, solve_day, take_explanations};
+/// set_explain(true);
+/// solve_day(3, "R8,U5,L5,D3\nU7,R6,D4,L4").unwrap();
+///
+/// assert_eq!(
+///     take_explanations()[..2],
+///     [
+///         ("Intersections found".to_string(), "2".to_string()),
+///         ("Closest intersection".to_string(), "(3, 3)".to_string())
+///     ]
+/// );
+/// assert!(take_explanations().is_empty());
+/// ```
+pub fn take_explanations() -> Vec<(String, String)> {
+    EXPLANATIONS.with(|explanations| explanations.replace(vec![]))
+}
+
+///
+/// Returns the labels given by the last solution solved on this thread to
+/// its answers, replacing those of the day (e.g. to include intermediate
+/// values), and forgets them.
+///
+/// ```rust
+/// # use lib::{solve_day, take_labels};
+/// solve_day(2, "1,0,0,0,99,19690000,720,0,0,0,0,0,0").unwrap();
+/// assert_eq!(
+///     take_labels(),
+///     [None, Some("Found noun = 5 and verb = 6, so".to_string())]
+/// );
+/// assert_eq!(take_labels(), [None, None]);
+/// ```
+pub fn take_labels() -> [Option<String>; 2] {
+    LABELS.with(|labels| labels.replace([None, None]))
+}
 
-    let file = File::open(&filename).expect(
-        format!(
-            "Unable to open input file in {filename}",
-            filename = filename
-        )
-        .as_str(),
-    );
+/// Replaces the label of an answer (1 or 2) of the solution being solved.
+fn relabel(part: usize, label: String) {
+    LABELS.with(|labels| labels.borrow_mut()[part - 1] = Some(label));
+}
+
+/// Collects an intermediate value of a solution, if explanations are enabled.
+fn explain(label: &str, val: &dyn Display) {
+    if explain_enabled() {
+        collect(label, val);
+    }
+}
+
+/// Checks if the intermediate values of the solutions are collected, for
+/// those costly to compute only to be explained. See `set_explain`.
+fn explain_enabled() -> bool {
+    EXPLAIN.load(Ordering::Relaxed)
+}
+
+/// Checks if the statistics of the inputs are to be collected, with
+/// `collect`. See `set_stats`.
+fn stats_enabled() -> bool {
+    STATS.load(Ordering::Relaxed)
+}
+
+/// Collects a value, returned by `take_explanations`.
+fn collect(label: &str, val: &dyn Display) {
+    EXPLANATIONS.with(|explanations| {
+        explanations
+            .borrow_mut()
+            .push((label.to_string(), val.to_string()))
+    });
+}
 
-    BufReader::new(file)
-        .lines()
-        .map(|l| {
-            l.expect(format!("Unable to read line in {filename}", filename = filename).as_str())
-        })
-        .filter(|l| !l.is_empty())
-        .collect()
+/// Returns the non-empty lines of an input.
+fn lines(input: &str) -> impl Iterator<Item = &str> {
+    input.lines().filter(|line| !line.is_empty())
 }
 
-fn answer(num: usize, label: &str, val: &dyn Display) {
-    println!("{} - {}: {}", num, label, val)
+/// Returns the first non-empty line of an input, e.g. an Intcode program.
+fn first_line(input: &str) -> Result<&str> {
+    lines(input)
+        .next()
+        .ok_or_else(|| Error::from("The input is empty"))
 }
 
-fn first_answer(label: &str, val: &dyn Display) {
-    answer(1, label, val)
+///
+/// Formats an answer the way it's printed, including the final line break.
+/// Multiline answers start on their own line. In quiet mode, only the value
+/// is kept, and multiline answers are followed by a blank line to separate
+/// them from the next answer.
+///
+/// ```rust
+/// # use lib::format_answer;
+/// assert_eq!(format_answer(1, "Fuel", &42, false), "1 - Fuel: 42\n");
+/// assert_eq!(format_answer(1, "Fuel", &42, true), "42\n");
+/// assert_eq!(format_answer(2, "Image", &"×\n ×\n", false), "2 - Image: \n\n×\n ×\n\n");
+/// assert_eq!(format_answer(2, "Image", &"×\n ×\n", true), "×\n ×\n\n");
+/// ```
+pub fn format_answer(num: usize, label: &str, val: &dyn Display, quiet: bool) -> String {
+    let value = val.to_string();
+
+    match (quiet, value.contains('\n')) {
+        (true, _) => format!("{}\n", value),
+        (false, true) => format!("{} - {}: \n\n{}\n", num, label, value),
+        (false, false) => format!("{} - {}: {}\n", num, label, value),
+    }
+}
+
+/// How answers are formatted for humans. See `format_value`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Whether the digits of numbers are grouped by thousands, with spaces.
+    pub group_digits: bool,
 }
 
-fn second_answer(label: &str, val: &dyn Display) {
-    answer(2, label, val)
+///
+/// Formats an answer's value with the given options. Only numbers are
+/// affected by the options; other values are formatted as they are
+/// displayed.
+///
+/// ```rust
+/// # use lib::{format_value, FormatOptions, Value};
+/// let grouped = FormatOptions { group_digits: true };
+///
+/// assert_eq!(format_value(&Value::Number(4686774924), &grouped), "4 686 774 924");
+/// assert_eq!(format_value(&Value::Number(-1234), &grouped), "-1 234");
+/// assert_eq!(format_value(&Value::Number(123), &grouped), "123");
+/// assert_eq!(
+///     format_value(&Value::Number(i128::MIN), &grouped),
+///     "-170 141 183 460 469 231 731 687 303 715 884 105 728"
+/// );
+/// assert_eq!(format_value(&Value::Number(4686774924), &FormatOptions::default()), "4686774924");
+/// assert_eq!(format_value(&Value::Text("12345".to_string()), &grouped), "12345");
+/// ```
+pub fn format_value(value: &Value, options: &FormatOptions) -> String {
+    match value {
+        Value::Number(number) if options.group_digits => {
+            let digits = number.unsigned_abs().to_string();
+            let mut groups: Vec<&str> = (0..digits.len())
+                .rev()
+                .step_by(3)
+                .map(|end| &digits[end.saturating_sub(2)..=end])
+                .collect();
+            groups.reverse();
+
+            format!("{}{}", if *number < 0 { "-" } else { "" }, groups.join(" "))
+        }
+        _ => value.to_string(),
+    }
 }
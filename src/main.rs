@@ -1,21 +1,408 @@
 extern crate lib;
 
+use lib::days::{self, Day, Inputs};
+use lib::doctor::{self, Status};
+use lib::init;
+use lib::input::{self, InputSource};
+use lib::intcode::inputs::StdinInput;
+use lib::intcode::view::ViewOptions;
+use lib::intcode::{self, catalog, Program};
+use lib::FormatOptions;
 use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// The directory of the input files, relative to the working directory.
+const INPUT_DIR: &str = "input";
+
+/// The flags accepted after a day selection, besides `--input-literal`
+/// and `--trace-last`.
+const DAY_FLAGS: [&str; 7] = [
+    "--explain",
+    "--stats",
+    "--quiet",
+    "--self-check",
+    "--strict",
+    "--group-digits",
+    "--sparse-memory",
+];
+
+/// The commands, and the options of the day selections.
+const USAGE: &str = "Usage:
+    <days> [--explain] [--stats] [--quiet] [--self-check] [--strict] [--group-digits]
+           [--sparse-memory] [--input-literal <input>] [--trace-last <instructions>]
+    list [--format json]
+    selftest [--offline]
+    doctor
+    init [--examples]
+    debug <program>
+    watch <program> [--speed <instructions per frame>]";
+
+///
+/// Returns the path of the input file for the given day.
+///
+fn input_path(day: u8) -> PathBuf {
+    PathBuf::from(INPUT_DIR).join(format!("day-{day}.txt", day = day))
+}
+
+/// Checks if the input file for a day is present.
+fn has_input(day: &Day) -> bool {
+    input_path(day.number).exists()
+}
+
+/// Finds the inputs of a day: its input file, and its examples, in
+/// `input/examples`, named `day-N.txt` or `day-N-<name>.txt`.
+fn inputs(day: &Day) -> Inputs {
+    let name = format!("day-{}", day.number);
+    let is_example =
+        |file: &str| file == format!("{}.txt", name) || file.starts_with(&format!("{}-", name));
+
+    let examples = fs::read_dir(PathBuf::from(INPUT_DIR).join("examples"))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| is_example(&entry.file_name().to_string_lossy()))
+                .count()
+        })
+        .unwrap_or(0);
+
+    Inputs {
+        input: has_input(day),
+        examples,
+    }
+}
+
+/// Solves a day with the given input, and prints the answers. Returns false
+/// if the day could not be solved, or has parts not implemented.
+fn run(
+    day: &Day,
+    source: &InputSource,
+    explain: bool,
+    stats: bool,
+    quiet: bool,
+    options: FormatOptions,
+) -> bool {
+    let raw = match source.read() {
+        Ok(raw) => raw,
+        Err(error) => {
+            eprintln!("Unable to read {}: {}", source, error);
+            if let InputSource::File(_) = source {
+                eprintln!("Run `cargo run init` to create the input directory.");
+            }
+            return false;
+        }
+    };
+
+    let input = match input::sanitize(&raw) {
+        Ok(input) => input,
+        Err(problem) => {
+            eprintln!("Invalid {}: {}", source, problem);
+            return false;
+        }
+    };
+
+    if input.windows_line_endings && !quiet {
+        eprintln!("Warning: {} has Windows line endings", source);
+    }
+
+    lib::set_explain(explain && !quiet);
+    lib::set_stats(stats && !quiet);
+
+    let solution = lib::solve_day(day.number, &input.text);
+    let labels = lib::take_labels();
+    let label = |part: usize| labels[part - 1].as_deref().unwrap_or(day.labels[part - 1]);
+
+    match solution {
+        Ok((first, second)) => {
+            // Only answers printed for humans are formatted.
+            let options = if quiet {
+                FormatOptions::default()
+            } else {
+                options
+            };
+
+            print!(
+                "{}",
+                lib::format_answer(1, label(1), &lib::format_value(&first, &options), quiet)
+            );
+
+            // The explanations are printed between the two answers.
+            lib::take_explanations()
+                .iter()
+                .for_each(|(label, value)| println!("    {}: {}", label, value));
+
+            print!(
+                "{}",
+                lib::format_answer(2, label(2), &lib::format_value(&second, &options), quiet)
+            );
+
+            first.is_implemented() && second.is_implemented()
+        }
+        Err(e) => {
+            eprintln!("Unable to solve day {}: {}", day.number, e);
+            false
+        }
+    }
+}
+
+/// Verifies a day's implementation against a reference one, if it has any.
+fn self_check(day: &Day) {
+    const SAMPLE: usize = 100_000;
+    const SEED: u64 = 2019;
+
+    match day.number {
+        4 => {
+            let input = fs::read_to_string(input_path(4)).expect("Unable to open input file");
+            let range = days::day04::parse_range(&input).expect("Invalid range in input file");
+
+            match days::day04::verify_against_oracle(range, SAMPLE, SEED) {
+                Ok(()) => println!("Self-check passed ({} numbers, seed {})", SAMPLE, SEED),
+                Err(mismatch) => println!(
+                    "Self-check failed for {}: expected {:?}, got {:?}",
+                    mismatch.number, mismatch.expected, mismatch.actual
+                ),
+            }
+        }
+        _ => eprintln!("No self-check for this day"),
+    }
+}
+
+/// Runs the logic of the Intcode days against stand-ins for their inputs.
+fn offline_self_test() {
+    println!("Offline stand-ins: these are not the real answers.");
+
+    for (number, source_code) in catalog::STAND_INS.iter() {
+        let day = days::day(*number).expect("Stand-in for an unknown day");
+
+        let solution = lib::solve_day(day.number, source_code);
+        let labels = lib::take_labels();
+        let label = |part: usize| labels[part - 1].as_deref().unwrap_or(day.labels[part - 1]);
+
+        match solution {
+            Ok((first, second)) => {
+                println!("Day {} - {}", day.number, day.title);
+                print!("{}", lib::format_answer(1, label(1), &first, false));
+                print!("{}", lib::format_answer(2, label(2), &second, false));
+            }
+            Err(e) => println!("Day {} failed: {}", day.number, e),
+        }
+    }
+}
+
+/// Checks the disassembler against the assembler and the interpreter, on
+/// the Intcode examples and inputs, and prints the results. Returns false
+/// if an inconsistency was found.
+fn self_test() -> bool {
+    let results = intcode::self_test();
+
+    for (name, inconsistencies) in &results {
+        if inconsistencies.is_empty() {
+            println!("{}: consistent", name);
+        } else {
+            println!("{}: {} inconsistencies", name, inconsistencies.len());
+            inconsistencies.iter().for_each(|i| println!("    {}", i));
+        }
+    }
+
+    results
+        .iter()
+        .all(|(_, inconsistencies)| inconsistencies.is_empty())
+}
+
+/// Checks the environment, and prints the results. Returns false if a
+/// check failed.
+fn doctor() -> bool {
+    let checks = doctor::checks(&days::DAYS, PathBuf::from(INPUT_DIR).is_dir(), has_input);
+    checks.iter().for_each(|check| println!("{}", check));
+
+    checks.iter().all(|check| check.status != Status::Fail)
+}
+
+/// Creates the directories and files missing in the working directory,
+/// and prints what to do next.
+fn init(examples: bool) {
+    let actions = init::plan(Path::new(""), &days::DAYS, examples);
+    actions.iter().for_each(|action| println!("{}", action));
+
+    match init::apply(&actions) {
+        Ok(()) => {
+            if actions.is_empty() {
+                println!("Everything is already there.");
+            }
+
+            println!(
+                "Save your puzzle inputs as {}/day-N.txt, then run e.g. `cargo run 1`.",
+                INPUT_DIR
+            );
+        }
+        Err(error) => {
+            eprintln!("Unable to initialize: {}", error);
+            process::exit(1);
+        }
+    }
+}
+
+/// Loads an Intcode program from a file, or exits.
+fn load_program(path: &str) -> Program {
+    let program = fs::read_to_string(path)
+        .map_err(|error| error.to_string())
+        .and_then(|source| {
+            source
+                .trim()
+                .parse::<Program>()
+                .map_err(|error| error.to_string())
+        });
+
+    program.unwrap_or_else(|error| {
+        eprintln!("Unable to load {}: {}", path, error);
+        process::exit(1);
+    })
+}
+
+/// Debugs an Intcode program in the terminal.
+fn debug(path: &str) {
+    if let Err(error) = load_program(path).run_debugger() {
+        eprintln!("Unable to debug {}: {}", path, error);
+        process::exit(1);
+    }
+}
+
+/// Runs an Intcode program with its live view, executing the given number
+/// of instructions between two frames. Inputs are read from stdin.
+fn watch(path: &str, speed: Option<u64>) {
+    let mut program = load_program(path);
+    program.set_input_source(StdinInput::default());
+
+    let defaults = ViewOptions::default();
+    let options = ViewOptions {
+        steps_per_frame: speed.unwrap_or(defaults.steps_per_frame),
+        ..defaults
+    };
+
+    if let Err(error) = program.run_with_view(&options) {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+/// Prints an error about the arguments, and exits.
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
 
 fn main() {
     let args: Vec<String> = env::args().collect();
-    let day: u8 = args[1].parse::<u8>().expect("Invalid day number");
-
-    match day {
-        1 => lib::days::day01::run(),
-        2 => lib::days::day02::run(),
-        3 => lib::days::day03::run(),
-        4 => lib::days::day04::run(),
-        5 => lib::days::day05::run(),
-        6 => lib::days::day06::run(),
-        7 => lib::days::day07::run(),
-        8 => lib::days::day08::run(),
-        9 => lib::days::day09::run(),
-        _ => eprintln!("Nothing for this day"),
+
+    match args.get(1).map(String::as_str) {
+        Some("list") => match args.get(2..).unwrap_or(&[]) {
+            [] => print!("{}", days::list_text(&days::DAYS, inputs)),
+            [flag, format] if flag == "--format" && format == "json" => {
+                println!("{}", days::list_json(&days::DAYS, inputs))
+            }
+            _ => usage_error("Usage: list [--format json]"),
+        },
+        Some("doctor") => {
+            if !doctor() {
+                process::exit(1);
+            }
+        }
+        Some("init") => match args.get(2..).unwrap_or(&[]) {
+            [] => init(false),
+            [flag] if flag == "--examples" => init(true),
+            _ => usage_error("Usage: init [--examples]"),
+        },
+        Some("debug") => match args.get(2..).unwrap_or(&[]) {
+            [path] => debug(path),
+            _ => usage_error("Usage: debug <program>"),
+        },
+        Some("watch") => match args.get(2..).unwrap_or(&[]) {
+            [path] => watch(path, None),
+            [path, flag, speed] if flag == "--speed" => match speed.parse() {
+                Ok(speed) if speed > 0 => watch(path, Some(speed)),
+                _ => usage_error(&format!("Invalid speed: {}", speed)),
+            },
+            _ => usage_error("Usage: watch <program> [--speed <instructions per frame>]"),
+        },
+        Some("selftest") => match args.get(2..).unwrap_or(&[]) {
+            [] => {
+                if !self_test() {
+                    process::exit(1);
+                }
+            }
+            [flag] if flag == "--offline" => offline_self_test(),
+            _ => usage_error("Usage: selftest [--offline]"),
+        },
+        Some("--help") | Some("-h") => println!("{}", USAGE),
+        Some(selection) => {
+            let selection = days::parse_selection(selection).unwrap_or_else(|error| {
+                usage_error(&format!("Invalid day selection: {}\n{}", error, USAGE))
+            });
+            let selected: Vec<&Day> = selection.iter().filter_map(|day| days::day(*day)).collect();
+
+            let mut flags = vec![];
+            let mut literal = None;
+            let mut options = args[2..].iter();
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--input-literal" => match options.next() {
+                        Some(input) => literal = Some(input),
+                        None => usage_error("Missing literal input after --input-literal"),
+                    },
+                    "--trace-last" => match options.next().map(|depth| depth.parse()) {
+                        Some(Ok(depth)) => intcode::set_history_depth_default(depth),
+                        _ => usage_error("Missing number of instructions after --trace-last"),
+                    },
+                    "--help" | "-h" => {
+                        println!("{}", USAGE);
+                        return;
+                    }
+                    flag if DAY_FLAGS.contains(&flag) => flags.push(flag),
+                    _ => usage_error(&format!("Unknown option: {}\n{}", option, USAGE)),
+                }
+            }
+
+            let flag = |flag: &str| flags.contains(&flag);
+            let quiet = flag("--quiet");
+            let mut complete = true;
+
+            if flag("--sparse-memory") {
+                intcode::set_sparse_memory_default(true);
+            }
+
+            for day in &selected {
+                if selected.len() > 1 && !quiet {
+                    println!("Day {} - {}", day.number, day.title);
+                }
+
+                if flag("--self-check") {
+                    self_check(day);
+                } else {
+                    let source = match literal {
+                        Some(input) => InputSource::Literal(input.clone()),
+                        None => InputSource::File(input_path(day.number)),
+                    };
+
+                    complete &= run(
+                        day,
+                        &source,
+                        flag("--explain"),
+                        flag("--stats"),
+                        quiet,
+                        FormatOptions {
+                            group_digits: flag("--group-digits"),
+                        },
+                    );
+                }
+            }
+
+            if selected.is_empty() {
+                usage_error("Nothing for this day");
+            } else if !complete && flag("--strict") {
+                process::exit(1);
+            }
+        }
+        None => usage_error(USAGE),
     };
 }
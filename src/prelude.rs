@@ -0,0 +1,21 @@
+//! The stable surface of the crate, for programs using it as a library.
+//!
+//! ```rust
+//! use lib::prelude::*;
+//!
+//! let mut program: Program = "1,9,10,3,2,3,11,0,99,30,40,50".parse().unwrap();
+//! program.execute().unwrap();
+//! assert_eq!(program.get(0), 3500);
+//!
+//! assert_eq!(compute_fuel_total(1969), 966);
+//! assert!(ordered_numbers(&111123));
+//! assert_eq!(solve_day(1, "12\n").unwrap().0, Value::Number(2));
+//! ```
+
+pub use crate::days::day01::{compute_fuel_naive, compute_fuel_total};
+pub use crate::days::day04::{number_groups_sizes_match, ordered_numbers};
+pub use crate::days::day06::OrbitalMap;
+pub use crate::intcode::catalog;
+pub use crate::intcode::{Cell, Error, Event, ExecutionState, ExecutionStatus, Program};
+pub use crate::render::compose;
+pub use crate::{solve_day, Value};
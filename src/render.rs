@@ -0,0 +1,46 @@
+//! Rendering of pictures as text, like the images some puzzles draw.
+
+///
+/// Renders a picture of `width` × `height` characters, line by line, each
+/// line ending with a newline. The string is allocated once, at its exact
+/// size: `pixel_at(x, y)` is called once to measure each character, then
+/// once to write it, so it should be cheap.
+///
+/// ```rust
+/// # use lib::render::compose;
+/// use std::fmt::Write;
+///
+/// let picture = compose(3, 2, |x, y| if x == y { '×' } else { '.' });
+/// assert_eq!(picture, "×..\n.×.\n");
+/// assert_eq!(picture.capacity(), picture.len());
+///
+/// // The same as writing the characters one by one.
+/// let (width, height) = (200, 100);
+/// let pixel_at = |x: usize, y: usize| if (x * y) % 7 == 0 { '×' } else { ' ' };
+/// let mut expected = String::new();
+/// for y in 0..height {
+///     for x in 0..width {
+///         expected.write_char(pixel_at(x, y)).unwrap();
+///     }
+///     expected.write_char('\n').unwrap();
+/// }
+/// assert_eq!(compose(width, height, pixel_at), expected);
+///
+/// assert_eq!(compose(0, 2, |_, _| 'x'), "\n\n");
+/// assert_eq!(compose(2, 0, |_, _| 'x'), "");
+/// ```
+pub fn compose(width: usize, height: usize, pixel_at: impl Fn(usize, usize) -> char) -> String {
+    let size: usize = (0..height)
+        .map(|y| (0..width).map(|x| pixel_at(x, y).len_utf8()).sum::<usize>() + 1)
+        .sum();
+    let mut picture = String::with_capacity(size);
+
+    for y in 0..height {
+        for x in 0..width {
+            picture.push(pixel_at(x, y));
+        }
+        picture.push('\n');
+    }
+
+    picture
+}
@@ -0,0 +1,75 @@
+//! Small helpers shared by several days.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+///
+/// Interns strings: each distinct string is stored once, and identified
+/// by a `u32` symbol, cheap to copy, hash and compare.
+///
+/// ```rust
+/// # use lib::util::Interner;
+/// let mut interner = Interner::new();
+///
+/// let com = interner.get_or_intern("COM");
+/// let b = interner.get_or_intern("B");
+///
+/// assert_ne!(com, b);
+/// assert_eq!(interner.get_or_intern("COM"), com);
+/// assert_eq!(interner.len(), 2);
+///
+/// assert_eq!(interner.resolve(b), "B");
+/// assert_eq!(interner.get("B"), Some(b));
+/// assert_eq!(interner.get("C"), None);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    /// The interned strings, indexed by symbol.
+    strings: Vec<Rc<str>>,
+
+    /// The symbols, indexed by string. The keys share their allocation
+    /// with `strings`.
+    symbols: HashMap<Rc<str>, u32>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Returns the symbol of the given string, interning it if needed.
+    pub fn get_or_intern(&mut self, string: &str) -> u32 {
+        match self.symbols.get(string) {
+            Some(symbol) => *symbol,
+            None => {
+                let symbol = self.strings.len() as u32;
+                let string: Rc<str> = Rc::from(string);
+
+                self.strings.push(Rc::clone(&string));
+                self.symbols.insert(string, symbol);
+
+                symbol
+            }
+        }
+    }
+
+    /// Returns the symbol of the given string, if interned.
+    pub fn get(&self, string: &str) -> Option<u32> {
+        self.symbols.get(string).cloned()
+    }
+
+    /// Returns the string of a symbol. Panics if the symbol doesn't come
+    /// from this interner.
+    pub fn resolve(&self, symbol: u32) -> &str {
+        &self.strings[symbol as usize]
+    }
+
+    /// Returns the number of distinct strings interned.
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
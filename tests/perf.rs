@@ -0,0 +1,46 @@
+//! Performance regression guards, ignored by default as they depend on the
+//! machine and on the build profile.
+//!
+//! ```bash
+//! $ cargo test --release -- --ignored perf
+//! ```
+
+use lib::prelude::{Cell, Program};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+/// The input of the second part of day 9: runs the program in sensor
+/// boost mode.
+const SENSOR_BOOST_MODE: Cell = 2;
+
+/// A generous budget, to catch order-of-magnitude regressions only.
+const DAY_09_BUDGET: Duration = Duration::from_millis(500);
+
+#[test]
+#[ignore]
+fn perf_day09_sensor_mode() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("input/day-9.txt");
+    let source_code = match fs::read_to_string(&path) {
+        Ok(source_code) => source_code,
+        Err(_) => {
+            eprintln!("Skipped: {} is missing", path.display());
+            return;
+        }
+    };
+
+    let mut program: Program = source_code.parse().expect("Invalid program");
+    program.push_input(SENSOR_BOOST_MODE);
+
+    let start = Instant::now();
+    let output = program.execute().expect("The program failed");
+    let elapsed = start.elapsed();
+
+    assert_eq!(output, vec![80805]);
+    assert!(
+        elapsed <= DAY_09_BUDGET,
+        "Sensor mode took {:?}, over the budget of {:?}",
+        elapsed,
+        DAY_09_BUDGET
+    );
+}